@@ -1,8 +1,8 @@
-use crate::lexer::{span::TextSpan, token::Token};
-use std::{
-    borrow::Cow,
-    fmt::{Display, Formatter},
+use crate::lexer::{
+    span::TextSpan,
+    token::{Token, TokenKind},
 };
+use std::fmt::{Display, Formatter};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Ast {
@@ -15,6 +15,12 @@ impl Ast {
     }
 }
 
+impl Default for Ast {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub trait GetSpan {
     fn span(&self) -> TextSpan;
 }
@@ -28,7 +34,15 @@ pub enum Stmt {
     Return(Return),
     Fn(Fn),
     Let(Let),
-    // TODO: loop, continue, break
+    While(While),
+    For(For),
+    Loop(Loop),
+    Break(Break),
+    Continue(Continue),
+    Struct(Struct),
+    Enum(Enum),
+    Impl(Impl),
+    Interface(Interface),
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -45,10 +59,13 @@ impl From<Expr> for Stmt {
 }
 
 impl Stmt {
+    #[allow(clippy::too_many_arguments)]
     pub fn new_fn(
         fn_token: Token,
         name: String,
+        type_params: Vec<GenericParam>,
         params: Vec<FnParam>,
+        receiver: Option<Token>,
         body: Block,
         exported: bool,
         return_type: Option<FunctionType>,
@@ -56,6 +73,8 @@ impl Stmt {
         Stmt::Fn(Fn {
             fn_token,
             name,
+            type_params,
+            receiver,
             params,
             body,
             exported,
@@ -102,6 +121,88 @@ impl Stmt {
     pub fn new_return(return_token: Token, expr: Option<Box<Expr>>) -> Self {
         Stmt::Return(Return { return_token, expr })
     }
+
+    pub fn new_while(
+        while_token: Token,
+        label: Option<Token>,
+        condition: Box<Expr>,
+        body: Block,
+    ) -> Self {
+        Stmt::While(While {
+            while_token,
+            label,
+            condition,
+            body,
+        })
+    }
+
+    pub fn new_for(
+        for_token: Token,
+        label: Option<Token>,
+        ident: Token,
+        iterable: Box<Expr>,
+        body: Block,
+    ) -> Self {
+        Stmt::For(For {
+            for_token,
+            label,
+            ident,
+            iterable,
+            body,
+        })
+    }
+
+    pub fn new_loop(loop_token: Token, label: Option<Token>, body: Block) -> Self {
+        Stmt::Loop(Loop {
+            loop_token,
+            label,
+            body,
+        })
+    }
+
+    pub fn new_enum(enum_token: Token, name: Token, variants: Vec<Variant>) -> Self {
+        Stmt::Enum(Enum {
+            enum_token,
+            name,
+            variants,
+        })
+    }
+
+    pub fn new_impl(
+        impl_token: Token,
+        interface: Option<Token>,
+        target: Token,
+        methods: Vec<Fn>,
+    ) -> Self {
+        Stmt::Impl(Impl {
+            impl_token,
+            interface,
+            target,
+            methods,
+        })
+    }
+
+    pub fn new_interface(interface_token: Token, name: Token, methods: Vec<FnSignature>) -> Self {
+        Stmt::Interface(Interface {
+            interface_token,
+            name,
+            methods,
+        })
+    }
+
+    pub fn new_struct(
+        struct_token: Token,
+        name: Token,
+        type_params: Vec<GenericParam>,
+        fields: Vec<StructField>,
+    ) -> Self {
+        Stmt::Struct(Struct {
+            struct_token,
+            name,
+            type_params,
+            fields,
+        })
+    }
 }
 
 impl Stmt {
@@ -122,25 +223,127 @@ pub struct FnParam {
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypeAnnotation {
     pub colon: Token,
-    pub type_name: Token,
+    pub type_name: TypeName,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct FunctionType {
     pub arrow: Token,
-    pub type_name: Token,
+    pub type_name: TypeName,
 }
 
 impl FunctionType {
-    pub fn new(arrow: Token, type_name: Token) -> Self {
+    pub fn new(arrow: Token, type_name: TypeName) -> Self {
         Self { arrow, type_name }
     }
 }
 
+/// A type as written in the source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeName {
+    /// `int`, `Point`
+    Named(Token),
+    /// `[int]`, or `[int; 4]` when `size` fixes the length
+    Array {
+        open: Token,
+        element: Box<TypeName>,
+        size: Option<Token>,
+        close: Token,
+    },
+    /// `Pair<int, string>`, an instance of a generic struct
+    Generic {
+        name: Token,
+        args: Vec<TypeName>,
+        close: Token,
+    },
+    /// `fn(int, int) -> int`, the type of functions and closures
+    Function {
+        fn_token: Token,
+        params: Vec<TypeName>,
+        close: Token,
+        return_type: Option<Box<TypeName>>,
+    },
+}
+
+impl TypeName {
+    /// The fixed length of an array type, `None` for any other type.
+    pub fn size(&self) -> Option<usize> {
+        match self {
+            TypeName::Array {
+                size: Some(size), ..
+            } => match size.kind {
+                TokenKind::Integer(size) => Some(size as usize),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl GetSpan for TypeName {
+    fn span(&self) -> TextSpan {
+        match self {
+            TypeName::Named(token) => token.span.clone(),
+            TypeName::Array { open, close, .. } => {
+                TextSpan::new(open.span.start, close.span.end, self.to_string())
+            }
+            TypeName::Generic { name, close, .. } => {
+                TextSpan::new(name.span.start, close.span.end, self.to_string())
+            }
+            TypeName::Function {
+                fn_token,
+                close,
+                return_type,
+                ..
+            } => {
+                let end = match return_type {
+                    Some(return_type) => return_type.span().end,
+                    None => close.span.end,
+                };
+                TextSpan::new(fn_token.span.start, end, self.to_string())
+            }
+        }
+    }
+}
+
+impl Display for TypeName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeName::Named(token) => write!(f, "{}", token.literal()),
+            TypeName::Array {
+                element,
+                size: Some(size),
+                ..
+            } => write!(f, "[{}; {}]", element, size.literal()),
+            TypeName::Array { element, .. } => write!(f, "[{}]", element),
+            TypeName::Generic { name, args, .. } => {
+                let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                write!(f, "{}<{}>", name.literal(), args.join(", "))
+            }
+            TypeName::Function {
+                params,
+                return_type,
+                ..
+            } => {
+                let params: Vec<String> = params.iter().map(|param| param.to_string()).collect();
+                write!(f, "fn({})", params.join(", "))?;
+                match return_type {
+                    Some(return_type) => write!(f, " -> {}", return_type),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Fn {
     pub fn_token: Token,
     pub name: String,
+    /// The `T` in `fn max<T>(a: T, b: T) -> T`.
+    pub type_params: Vec<GenericParam>,
+    /// The `self` parameter of a method, passed before `params`.
+    pub receiver: Option<Token>,
     pub params: Vec<FnParam>,
     pub body: Block,
     pub exported: bool,
@@ -170,6 +373,22 @@ pub struct Use {
     pub items: Vec<Token>,
 }
 
+impl Use {
+    /// The module as written between the quotes, `std::io` or `./math`.
+    pub fn module(&self) -> String {
+        match &self.from.kind {
+            TokenKind::String(module) => module.clone(),
+            _ => self.from.literal(),
+        }
+    }
+
+    /// Whether the module is a file of the project, as opposed to the standard library.
+    pub fn is_relative(&self) -> bool {
+        let module = self.module();
+        module.starts_with("./") || module.starts_with("../")
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Block {
     pub stmts: Vec<Stmt>,
@@ -181,6 +400,117 @@ pub struct Return {
     pub expr: Option<Box<Expr>>,
 }
 
+/// `label: while condition { ... }`
+#[derive(Clone, Debug, PartialEq)]
+pub struct While {
+    pub while_token: Token,
+    pub label: Option<Token>,
+    pub condition: Box<Expr>,
+    pub body: Block,
+}
+
+/// `label: for ident in iterable { ... }`
+#[derive(Clone, Debug, PartialEq)]
+pub struct For {
+    pub for_token: Token,
+    pub label: Option<Token>,
+    pub ident: Token,
+    pub iterable: Box<Expr>,
+    pub body: Block,
+}
+
+/// `label: loop { ... }`, only left through `break` or `return`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Loop {
+    pub loop_token: Token,
+    pub label: Option<Token>,
+    pub body: Block,
+}
+
+/// `struct Point { x: float, y: float }`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Struct {
+    pub struct_token: Token,
+    pub name: Token,
+    /// The `A` and `B` in `struct Pair<A, B>`.
+    pub type_params: Vec<GenericParam>,
+    pub fields: Vec<StructField>,
+}
+
+/// The name a function of an `impl` block is registered under, `Point::length`.
+pub fn qualified_name(type_name: &str, name: &str) -> String {
+    format!("{}::{}", type_name, name)
+}
+
+/// `impl Point { fn length(self) -> float { ... } }`, methods and associated
+/// functions of a struct or enum. `impl Display for Point { ... }` implements the
+/// methods of an interface.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Impl {
+    pub impl_token: Token,
+    pub interface: Option<Token>,
+    pub target: Token,
+    pub methods: Vec<Fn>,
+}
+
+/// `interface Display { fn show(self) -> string; }`, methods a type promises to have.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Interface {
+    pub interface_token: Token,
+    pub name: Token,
+    pub methods: Vec<FnSignature>,
+}
+
+/// A function declared without a body, like the methods of an interface.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FnSignature {
+    pub fn_token: Token,
+    pub name: Token,
+    pub receiver: Option<Token>,
+    pub params: Vec<FnParam>,
+    pub return_type: Option<FunctionType>,
+}
+
+/// `T` in `fn max<T>(...)`, or `T: Display` with the interfaces it must implement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GenericParam {
+    pub name: Token,
+    pub bounds: Vec<Token>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StructField {
+    pub ident: Token,
+    pub type_annotation: TypeAnnotation,
+}
+
+/// `enum Shape { Circle(float), Rect(float, float), Empty }`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Enum {
+    pub enum_token: Token,
+    pub name: Token,
+    pub variants: Vec<Variant>,
+}
+
+/// A variant and the type names of its payload, empty for `Empty`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variant {
+    pub ident: Token,
+    pub fields: Vec<TypeName>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Break {
+    pub break_token: Token,
+    pub label: Option<Token>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Continue {
+    pub continue_token: Token,
+    pub label: Option<Token>,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum LiteralType {
     Int(i64),
@@ -278,24 +608,144 @@ pub struct Parenthesized {
     pub expr: Box<Expr>,
 }
 
+/// `callee(args)`, where `token` is the name the callee ends in.
 #[derive(Debug, Clone, PartialEq)]
 pub struct CallExpr {
-    pub callee: String,
+    pub callee: Box<Expr>,
     pub args: Vec<Expr>,
     pub token: Token,
 }
 
+impl CallExpr {
+    /// The called function when the callee is a plain name like `add` or `Circle`.
+    pub fn function_name(&self) -> Option<&str> {
+        match self.callee.as_ref() {
+            Expr::Variable(var) => Some(&var.ident),
+            _ => None,
+        }
+    }
+}
+
+/// `fn(x: int) -> int { return x + n; }`, an anonymous function. It captures the
+/// variables in scope where it's created by value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Closure {
+    pub function: Box<Fn>,
+}
+
+/// `Point::new`, an associated function of a type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    pub type_name: Token,
+    pub name: Token,
+}
+
+/// `target = value`, where `target` is a variable or a field or element of one.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Assign {
-    pub ident: Token,
+    pub target: Box<Expr>,
     pub value: Box<Expr>,
     pub token: Token,
 }
 
+/// `Point { x: 1.0, y: 2.0 }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructLiteral {
+    pub name: Token,
+    pub fields: Vec<FieldInit>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldInit {
+    pub ident: Token,
+    pub value: Expr,
+}
+
+/// `object.field`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    pub object: Box<Expr>,
+    pub field: Token,
+    pub token: Token,
+}
+
+/// `[1, 2, 3]`, or `[0; 4]` repeating the single element `repeat` times
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArrayLiteral {
+    pub open: Token,
+    pub elements: Vec<Expr>,
+    pub repeat: Option<Token>,
+}
+
+/// `object[index]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Index {
+    pub object: Box<Expr>,
+    pub index: Box<Expr>,
+    pub open: Token,
+    pub close: Token,
+}
+
+/// `start..end`, or `start..=end` when `inclusive`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Range {
+    pub start: Box<Expr>,
+    pub end: Box<Expr>,
+    pub inclusive: bool,
+    pub token: Token,
+}
+
+/// `match scrutinee { pattern => body, ... }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    pub match_token: Token,
+    pub scrutinee: Box<Expr>,
+    pub arms: Vec<MatchArm>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: ArmBody,
+}
+
+/// An arm is either a single expression giving the value of the `match`, or a block.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArmBody {
+    Expr(Expr),
+    Block(Block),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    /// `_`
+    Wildcard(Token),
+    /// `1`, `-1`, `true` or `"text"`
+    Literal(Literal),
+    /// `Rect(w, h)`, where a `_` binding ignores that field
+    Variant { name: Token, bindings: Vec<Token> },
+}
+
+impl GetSpan for Pattern {
+    fn span(&self) -> TextSpan {
+        match self {
+            Pattern::Wildcard(token) => token.span.clone(),
+            Pattern::Literal(literal) => literal.token.span.clone(),
+            Pattern::Variant { name, bindings } => {
+                let mut spans = vec![name.span.clone()];
+                spans.extend(bindings.iter().map(|binding| binding.span.clone()));
+
+                TextSpan::combine(spans)
+            }
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum UnOpKind {
     Minus,
     BitwiseNot,
+    Not,
 }
 
 impl Display for UnOpKind {
@@ -303,6 +753,7 @@ impl Display for UnOpKind {
         match self {
             UnOpKind::Minus => write!(f, "-"),
             UnOpKind::BitwiseNot => write!(f, "~"),
+            UnOpKind::Not => write!(f, "!"),
         }
     }
 }
@@ -341,16 +792,17 @@ impl BinOperator {
             BinOpKind::BitwiseAnd => 17,
             BinOpKind::BitwiseXor => 16,
             BinOpKind::BitwiseOr => 15,
-            BinOpKind::Equals => 30,
-            BinOpKind::NotEquals => 30,
-            BinOpKind::LessThan => 29,
-            BinOpKind::LessThanOrEqual => 29,
-            BinOpKind::GreaterThan => 29,
-            BinOpKind::GreaterThanOrEqual => 29,
+            // Comparisons bind looser than arithmetic so `i % 2 == 0` compares `i % 2`
+            BinOpKind::Equals => 13,
+            BinOpKind::NotEquals => 13,
+            BinOpKind::LessThan => 14,
+            BinOpKind::LessThanOrEqual => 14,
+            BinOpKind::GreaterThan => 14,
+            BinOpKind::GreaterThanOrEqual => 14,
             BinOpKind::And => 10,
             BinOpKind::Or => 9,
-            BinOpKind::EqualsEquals => 30,
-            BinOpKind::BangEquals => 30,
+            BinOpKind::EqualsEquals => 13,
+            BinOpKind::BangEquals => 13,
             BinOpKind::Increment => 17,
             BinOpKind::Decrement => 17,
             BinOpKind::MinusEquals => 17,
@@ -382,6 +834,14 @@ pub enum Expr {
     Parenthesized(Parenthesized),
     Call(CallExpr),
     Assign(Assign),
+    Range(Range),
+    StructLiteral(StructLiteral),
+    Member(Member),
+    Match(Match),
+    ArrayLiteral(ArrayLiteral),
+    Index(Index),
+    Path(Path),
+    Closure(Closure),
 }
 
 impl GetSpan for Expr {
@@ -398,13 +858,36 @@ impl GetSpan for Expr {
             Expr::Variable(v) => v.clone().token.span,
             Expr::Logical(l) => l.clone().token.span,
             Expr::Parenthesized(p) => p.expr.span(),
-            Expr::Call(c) => c.clone().token.span,
+            Expr::Call(c) => c.callee.span(),
             Expr::Assign(a) => {
-                let ident = a.clone().ident.span;
+                let target = a.target.span();
                 let value = a.value.span();
 
-                TextSpan::combine(vec![ident, value])
+                TextSpan::combine(vec![target, value])
             }
+            Expr::Range(r) => TextSpan::combine(vec![r.start.span(), r.end.span()]),
+            // Only the name, literals usually span several lines
+            Expr::StructLiteral(s) => s.name.span.clone(),
+            Expr::Member(m) => TextSpan::combine(vec![
+                m.object.span(),
+                m.token.span.clone(),
+                m.field.span.clone(),
+            ]),
+            Expr::Match(m) => m.match_token.span.clone(),
+            // Only the opening bracket, like struct literals they may span several lines
+            Expr::ArrayLiteral(a) => a.open.span.clone(),
+            Expr::Index(i) => TextSpan::combine(vec![
+                i.object.span(),
+                i.open.span.clone(),
+                i.index.span(),
+                i.close.span.clone(),
+            ]),
+            Expr::Path(p) => TextSpan::new(
+                p.type_name.span.start,
+                p.name.span.end,
+                qualified_name(&p.type_name.literal(), &p.name.literal()),
+            ),
+            Expr::Closure(c) => c.function.fn_token.span.clone(),
         }
     }
 }
@@ -418,14 +901,51 @@ impl Expr {
         })
     }
 
-    pub fn new_assign(ident: Token, token: Token, value: Expr) -> Self {
+    pub fn new_assign(target: Expr, token: Token, value: Expr) -> Self {
         Expr::Assign(Assign {
-            ident,
+            target: Box::new(target),
             value: Box::new(value),
             token,
         })
     }
 
+    pub fn new_struct_literal(name: Token, fields: Vec<FieldInit>) -> Self {
+        Expr::StructLiteral(StructLiteral { name, fields })
+    }
+
+    pub fn new_match(match_token: Token, scrutinee: Expr, arms: Vec<MatchArm>) -> Self {
+        Expr::Match(Match {
+            match_token,
+            scrutinee: Box::new(scrutinee),
+            arms,
+        })
+    }
+
+    pub fn new_array_literal(open: Token, elements: Vec<Expr>, repeat: Option<Token>) -> Self {
+        Expr::ArrayLiteral(ArrayLiteral {
+            open,
+            elements,
+            repeat,
+        })
+    }
+
+    pub fn new_index(object: Expr, open: Token, index: Expr, close: Token) -> Self {
+        Expr::Index(Index {
+            object: Box::new(object),
+            index: Box::new(index),
+            open,
+            close,
+        })
+    }
+
+    pub fn new_member(object: Expr, field: Token, token: Token) -> Self {
+        Expr::Member(Member {
+            object: Box::new(object),
+            field,
+            token,
+        })
+    }
+
     pub fn new_binary(left: Expr, operator: BinOperator, right: Expr) -> Self {
         Expr::Binary(Binary {
             left: Box::new(left),
@@ -462,14 +982,24 @@ impl Expr {
         })
     }
 
-    pub fn new_call(callee: String, args: Vec<Expr>, token: Token) -> Self {
+    pub fn new_call(callee: Expr, args: Vec<Expr>, token: Token) -> Self {
         Expr::Call(CallExpr {
-            callee,
+            callee: Box::new(callee),
             args,
             token,
         })
     }
 
+    pub fn new_closure(function: Fn) -> Self {
+        Expr::Closure(Closure {
+            function: Box::new(function),
+        })
+    }
+
+    pub fn new_path(type_name: Token, name: Token) -> Self {
+        Expr::Path(Path { type_name, name })
+    }
+
     pub fn new_string(token: Token, value: String) -> Self {
         Expr::Literal(Literal {
             token,
@@ -477,6 +1007,15 @@ impl Expr {
         })
     }
 
+    pub fn new_range(start: Expr, end: Expr, inclusive: bool, token: Token) -> Self {
+        Expr::Range(Range {
+            start: Box::new(start),
+            end: Box::new(end),
+            inclusive,
+            token,
+        })
+    }
+
     pub fn new_parenthesized(expr: Expr) -> Self {
         Expr::Parenthesized(Parenthesized {
             expr: Box::new(expr),
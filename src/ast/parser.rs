@@ -1,24 +1,40 @@
 use crate::{
     ast::ast::{
-        Ast, BinOpAssociativity, BinOpKind, BinOperator, Block, ElseBlock, Expr, FnParam,
-        FunctionType, Stmt, TypeAnnotation, UnOpKind, UnOperator,
+        ArmBody, Ast, BinOpAssociativity, BinOpKind, BinOperator, Block, Break, Continue,
+        ElseBlock, Expr, FieldInit, Fn, FnParam, FnSignature, FunctionType, GenericParam, GetSpan,
+        Literal, LiteralType, MatchArm, Pattern, Stmt, StructField, TypeAnnotation, TypeName,
+        UnOpKind, UnOperator, Variant,
+    },
+    error::{
+        PulseError,
+        PulseError::{Errors, ExpectedToken, UnexpectedToken},
+    },
+    lexer::{
+        span::TextSpan,
+        token::{Token, TokenKind},
     },
-    error::PulseError::{ExpectedToken, UnexpectedToken},
-    lexer::token::{Token, TokenKind},
 };
 use anyhow::Result;
 use log::debug;
-use std::hint::black_box;
 
 #[derive(Debug)]
 pub struct Parser {
     pub tokens: Vec<Token>,
     pub current: usize,
+    pub errors: Vec<PulseError>,
+    /// Set while parsing the header of an `if`, `while` or `for`, where `name {` opens
+    /// the body instead of a struct literal.
+    no_struct_literal: bool,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        Self {
+            tokens,
+            current: 0,
+            errors: vec![],
+            no_struct_literal: false,
+        }
     }
 }
 
@@ -27,16 +43,96 @@ impl Parser {
         let mut ast = Ast::new();
 
         while !self.is_eof() {
-            let stmt = self.parse_stmt()?;
+            let stmt = self.parse_stmt_or_recover(false)?;
 
             if let Some(stmt) = stmt {
                 ast.stmts.push(stmt);
             }
         }
 
+        if !self.errors.is_empty() {
+            return Err(Errors(std::mem::take(&mut self.errors)).into());
+        }
+
         Ok(ast)
     }
 
+    /// Parses a statement, or records the syntax error and skips to the next statement
+    /// boundary so the rest of the file still gets checked.
+    fn parse_stmt_or_recover(&mut self, in_block: bool) -> Result<Option<Stmt>> {
+        let start = self.current;
+
+        match self.parse_stmt() {
+            Ok(stmt) => Ok(stmt),
+            Err(err) => {
+                self.errors.push(err.downcast::<PulseError>()?);
+
+                // Always make progress, even if the offending token is a boundary itself
+                if self.current == start {
+                    self.consume();
+                }
+                let depth =
+                    self.tokens[start..self.current]
+                        .iter()
+                        .fold(0, |depth: usize, token| match token.kind {
+                            TokenKind::LeftBrace => depth + 1,
+                            TokenKind::RightBrace => depth.saturating_sub(1),
+                            _ => depth,
+                        });
+                self.synchronize(in_block, depth);
+
+                Ok(None)
+            }
+        }
+    }
+
+    /// Panic-mode recovery: skips tokens until just after a `;`, or until a `}` or the
+    /// start of a `fn`, `let`, `use`, `struct`, `enum` or `impl`. A `}` is left for the
+    /// enclosing block to consume. `depth` counts the braces the failed statement opened
+    /// and didn't close yet, everything up to their closing braces is skipped first.
+    fn synchronize(&mut self, in_block: bool, mut depth: usize) {
+        if depth == 0 && self.previous().kind == TokenKind::Semicolon {
+            return;
+        }
+
+        while !self.is_eof() {
+            match self.peek().kind {
+                TokenKind::LeftBrace => {
+                    depth += 1;
+                    self.consume();
+                }
+                TokenKind::RightBrace if depth > 0 => {
+                    depth -= 1;
+                    self.consume();
+                }
+                _ if depth > 0 => {
+                    self.consume();
+                }
+                TokenKind::Semicolon => {
+                    self.consume();
+                    return;
+                }
+                TokenKind::RightBrace => {
+                    if !in_block {
+                        self.consume();
+                    }
+                    return;
+                }
+                TokenKind::Fn
+                | TokenKind::Export
+                | TokenKind::Let
+                | TokenKind::Use
+                | TokenKind::Struct
+                | TokenKind::Enum
+                | TokenKind::Impl
+                | TokenKind::Interface => return,
+                _ => {
+                    self.consume();
+                }
+            }
+        }
+    }
+
     pub fn consume(&mut self) -> Token {
         if !self.is_eof() {
             self.current += 1;
@@ -56,7 +152,7 @@ impl Parser {
     }
 
     pub fn peek_next(&self) -> Token {
-        self.tokens[self.current + 1].clone()
+        self.tokens[(self.current + 1).min(self.tokens.len() - 1)].clone()
     }
 
     pub fn is_eof(&self) -> bool {
@@ -90,8 +186,16 @@ impl Parser {
         let token = self.peek();
 
         let stmt = match token.kind {
+            // `fn(...)` starts a closure rather than a declaration
+            TokenKind::Fn if self.peek_next().kind == TokenKind::LeftParen => {
+                Some(self.expression_stmt()?)
+            }
             TokenKind::Fn | TokenKind::Export => Some(self.parse_fn()?),
             TokenKind::Use => Some(self.parse_use()?),
+            TokenKind::Struct => Some(self.parse_struct()?),
+            TokenKind::Enum => Some(self.parse_enum()?),
+            TokenKind::Impl => Some(self.parse_impl()?),
+            TokenKind::Interface => Some(self.parse_interface()?),
             TokenKind::If => Some(self.parse_if()?),
             TokenKind::Let => Some(self.parse_let()?),
             TokenKind::LeftBrace => {
@@ -100,6 +204,13 @@ impl Parser {
                 self.expect(TokenKind::RightBrace)?;
                 Some(Stmt::Block(block))
             }
+            TokenKind::While | TokenKind::For | TokenKind::Loop => Some(self.parse_loop(None)?),
+            TokenKind::Identifier if self.peek_next().kind == TokenKind::Colon => {
+                let label = self.consume();
+                self.consume();
+                Some(self.parse_loop(Some(label))?)
+            }
+            TokenKind::Break | TokenKind::Continue => Some(self.parse_loop_control()),
             TokenKind::Return => self.parse_return()?,
             TokenKind::Semicolon => {
                 self.consume();
@@ -125,6 +236,81 @@ impl Parser {
         Ok(Some(Stmt::new_return(return_token, value)))
     }
 
+    /// Parses `while`, `for` and `loop`, `label` is the already consumed `label:` prefix.
+    pub fn parse_loop(&mut self, label: Option<Token>) -> Result<Stmt> {
+        debug!("Parsing loop");
+        let token = self.peek();
+
+        let stmt = match token.kind {
+            TokenKind::While => {
+                let while_token = self.consume();
+                let condition = self.parse_header_expr()?;
+                let body = self.parse_loop_body()?;
+
+                Stmt::new_while(while_token, label, Box::new(condition), body)
+            }
+            TokenKind::For => {
+                let for_token = self.consume();
+                let ident = self.expect(TokenKind::Identifier)?;
+                self.expect(TokenKind::In)?;
+                let iterable = self.parse_header_expr()?;
+                let body = self.parse_loop_body()?;
+
+                Stmt::new_for(for_token, label, ident, Box::new(iterable), body)
+            }
+            TokenKind::Loop => {
+                let loop_token = self.consume();
+                let body = self.parse_loop_body()?;
+
+                Stmt::new_loop(loop_token, label, body)
+            }
+            _ => {
+                return Err(ExpectedToken(
+                    "loop".to_string(),
+                    "Only 'while', 'for' and 'loop' can be labeled".to_string(),
+                    token.span.clone(),
+                )
+                .into())
+            }
+        };
+
+        Ok(stmt)
+    }
+
+    fn parse_loop_body(&mut self) -> Result<Block> {
+        self.expect(TokenKind::LeftBrace)?;
+        let body = self.parse_block()?;
+        self.expect(TokenKind::RightBrace)?;
+
+        Ok(body)
+    }
+
+    /// `break` or `continue`, optionally followed by the label of an enclosing loop. The
+    /// label has to be on the same line, an identifier on the next one starts a new statement.
+    pub fn parse_loop_control(&mut self) -> Stmt {
+        let token = self.consume();
+        let label = if self.peek().kind == TokenKind::Identifier
+            && self.peek().span.start.line == token.span.start.line
+        {
+            Some(self.consume())
+        } else {
+            None
+        };
+
+        self.possible_check(TokenKind::Semicolon);
+
+        match token.kind {
+            TokenKind::Break => Stmt::Break(Break {
+                break_token: token,
+                label,
+            }),
+            _ => Stmt::Continue(Continue {
+                continue_token: token,
+                label,
+            }),
+        }
+    }
+
     pub fn parse_let(&mut self) -> Result<Stmt> {
         debug!("Parsing let statement");
         self.expect(TokenKind::Let)?;
@@ -141,7 +327,7 @@ impl Parser {
 
         self.possible_check(TokenKind::LeftParen);
 
-        let condition = self.parse_expr()?;
+        let condition = self.parse_header_expr()?;
 
         self.possible_check(TokenKind::RightParen);
 
@@ -158,10 +344,10 @@ impl Parser {
             self.consume();
 
             if self.peek().kind == TokenKind::If {
-                let if_token = self.consume();
+                self.consume();
                 self.possible_check(TokenKind::LeftParen);
 
-                let condition = self.parse_expr()?;
+                let condition = self.parse_header_expr()?;
                 self.possible_check(TokenKind::RightParen);
 
                 self.expect(TokenKind::LeftBrace)?;
@@ -192,7 +378,7 @@ impl Parser {
             if_token,
             condition.into(),
             body,
-            elseif_blocks.into(),
+            elseif_blocks,
             else_block,
         ))
     }
@@ -205,9 +391,7 @@ impl Parser {
 
         self.expect(TokenKind::LeftBrace)?;
 
-        let mut i = 0;
         while self.peek().kind != TokenKind::RightBrace && !self.is_eof() {
-            i += 1;
             let item = self.expect(TokenKind::Identifier)?;
 
             if self.peek().kind != TokenKind::RightBrace {
@@ -235,14 +419,261 @@ impl Parser {
         Ok(Stmt::new_use(use_token, from, items))
     }
 
+    pub fn parse_struct(&mut self) -> Result<Stmt> {
+        debug!("Parsing struct");
+        let struct_token = self.consume();
+        let name = self.expect(TokenKind::Identifier)?;
+        let type_params = self.parse_type_params()?;
+
+        self.expect(TokenKind::LeftBrace)?;
+
+        let mut fields = vec![];
+        while self.peek().kind != TokenKind::RightBrace && !self.is_eof() {
+            let ident = self.expect(TokenKind::Identifier)?;
+            let type_annotation = self.parse_type_annotation()?;
+
+            fields.push(StructField {
+                ident,
+                type_annotation,
+            });
+
+            if self.peek().kind != TokenKind::RightBrace {
+                self.expect(TokenKind::Comma)?;
+            }
+        }
+
+        self.expect(TokenKind::RightBrace)?;
+
+        Ok(Stmt::new_struct(struct_token, name, type_params, fields))
+    }
+
+    /// `<A, B: Display + Debug>` after the name of a generic function or struct, empty
+    /// when there is none.
+    fn parse_type_params(&mut self) -> Result<Vec<GenericParam>> {
+        let mut type_params = vec![];
+        if self.peek().kind != TokenKind::LessThan {
+            return Ok(type_params);
+        }
+
+        self.consume();
+        while self.peek().kind != TokenKind::GreaterThan && !self.is_eof() {
+            let name = self.expect(TokenKind::Identifier)?;
+            let mut bounds = vec![];
+            if self.peek().kind == TokenKind::Colon {
+                self.consume();
+                bounds.push(self.expect(TokenKind::Identifier)?);
+                while self.peek().kind == TokenKind::Plus {
+                    self.consume();
+                    bounds.push(self.expect(TokenKind::Identifier)?);
+                }
+            }
+            type_params.push(GenericParam { name, bounds });
+
+            if self.peek().kind != TokenKind::GreaterThan {
+                self.expect(TokenKind::Comma)?;
+            }
+        }
+        self.expect(TokenKind::GreaterThan)?;
+
+        Ok(type_params)
+    }
+
+    pub fn parse_impl(&mut self) -> Result<Stmt> {
+        debug!("Parsing impl");
+        let impl_token = self.consume();
+        let mut target = self.expect(TokenKind::Identifier)?;
+        let mut interface = None;
+        if self.peek().kind == TokenKind::For {
+            self.consume();
+            interface = Some(std::mem::replace(
+                &mut target,
+                self.expect(TokenKind::Identifier)?,
+            ));
+        }
+
+        self.expect(TokenKind::LeftBrace)?;
+
+        let mut methods = vec![];
+        while self.peek().kind != TokenKind::RightBrace && !self.is_eof() {
+            if self.peek().kind != TokenKind::Fn {
+                return Err(ExpectedToken(
+                    "function".to_string(),
+                    "Impl blocks can only contain functions".to_string(),
+                    self.peek().span.clone(),
+                )
+                .into());
+            }
+
+            match self.parse_fn()? {
+                Stmt::Fn(method) => methods.push(method),
+                _ => unreachable!("parse_fn only parses functions"),
+            }
+        }
+
+        self.expect(TokenKind::RightBrace)?;
+
+        Ok(Stmt::new_impl(impl_token, interface, target, methods))
+    }
+
+    pub fn parse_interface(&mut self) -> Result<Stmt> {
+        debug!("Parsing interface");
+        let interface_token = self.consume();
+        let name = self.expect(TokenKind::Identifier)?;
+
+        self.expect(TokenKind::LeftBrace)?;
+
+        let mut methods = vec![];
+        while self.peek().kind != TokenKind::RightBrace && !self.is_eof() {
+            let fn_token = self.expect(TokenKind::Fn)?;
+            let name = self.expect(TokenKind::Identifier)?;
+            let (receiver, params) = self.parse_params()?;
+            let return_type = self.parse_return_type()?;
+            self.expect(TokenKind::Semicolon)?;
+
+            methods.push(FnSignature {
+                fn_token,
+                name,
+                receiver,
+                params,
+                return_type,
+            });
+        }
+
+        self.expect(TokenKind::RightBrace)?;
+
+        Ok(Stmt::new_interface(interface_token, name, methods))
+    }
+
+    pub fn parse_enum(&mut self) -> Result<Stmt> {
+        debug!("Parsing enum");
+        let enum_token = self.consume();
+        let name = self.expect(TokenKind::Identifier)?;
+
+        self.expect(TokenKind::LeftBrace)?;
+
+        let mut variants = vec![];
+        while self.peek().kind != TokenKind::RightBrace && !self.is_eof() {
+            let ident = self.expect(TokenKind::Identifier)?;
+
+            let mut fields = vec![];
+            if self.peek().kind == TokenKind::LeftParen {
+                self.consume();
+                while self.peek().kind != TokenKind::RightParen && !self.is_eof() {
+                    fields.push(self.parse_type()?);
+
+                    if self.peek().kind != TokenKind::RightParen {
+                        self.expect(TokenKind::Comma)?;
+                    }
+                }
+                self.expect(TokenKind::RightParen)?;
+            }
+
+            variants.push(Variant { ident, fields });
+
+            if self.peek().kind != TokenKind::RightBrace {
+                self.expect(TokenKind::Comma)?;
+            }
+        }
+
+        self.expect(TokenKind::RightBrace)?;
+
+        Ok(Stmt::new_enum(enum_token, name, variants))
+    }
+
     pub fn parse_type_annotation(&mut self) -> Result<TypeAnnotation> {
         debug!("Parsing type annotation");
         let colon = self.expect(TokenKind::Colon)?;
-        let type_name = self.expect(TokenKind::Identifier)?;
+        let type_name = self.parse_type()?;
 
         Ok(TypeAnnotation { colon, type_name })
     }
 
+    /// Parses `name`, `[type]` or `[type; size]`.
+    pub fn parse_type(&mut self) -> Result<TypeName> {
+        match self.peek().kind {
+            TokenKind::LeftBracket => {}
+            TokenKind::Fn => return self.parse_function_type(),
+            _ => {
+                let name = self.expect(TokenKind::Identifier)?;
+                if self.peek().kind != TokenKind::LessThan {
+                    return Ok(TypeName::Named(name));
+                }
+
+                self.consume();
+                let mut args = vec![];
+                while self.peek().kind != TokenKind::GreaterThan && !self.is_eof() {
+                    args.push(self.parse_type()?);
+
+                    if self.peek().kind != TokenKind::GreaterThan {
+                        self.expect(TokenKind::Comma)?;
+                    }
+                }
+                let close = self.expect(TokenKind::GreaterThan)?;
+
+                return Ok(TypeName::Generic { name, args, close });
+            }
+        }
+
+        let open = self.consume();
+        let element = self.parse_type()?;
+        let size = if self.peek().kind == TokenKind::Semicolon {
+            self.consume();
+            Some(self.expect_array_size()?)
+        } else {
+            None
+        };
+        let close = self.expect(TokenKind::RightBracket)?;
+
+        Ok(TypeName::Array {
+            open,
+            element: Box::new(element),
+            size,
+            close,
+        })
+    }
+
+    /// `fn(int, int) -> int`
+    fn parse_function_type(&mut self) -> Result<TypeName> {
+        let fn_token = self.consume();
+        self.expect(TokenKind::LeftParen)?;
+
+        let mut params = vec![];
+        while self.peek().kind != TokenKind::RightParen && !self.is_eof() {
+            params.push(self.parse_type()?);
+
+            if self.peek().kind != TokenKind::RightParen {
+                self.expect(TokenKind::Comma)?;
+            }
+        }
+        let close = self.expect(TokenKind::RightParen)?;
+
+        let return_type = match self.parse_return_type()? {
+            Some(return_type) => Some(Box::new(return_type.type_name)),
+            None => None,
+        };
+
+        Ok(TypeName::Function {
+            fn_token,
+            params,
+            close,
+            return_type,
+        })
+    }
+
+    /// The `4` in `[int; 4]` and `[0; 4]`, which has to be an integer literal.
+    fn expect_array_size(&mut self) -> Result<Token> {
+        let token = self.peek();
+        match token.kind {
+            TokenKind::Integer(_) => Ok(self.consume()),
+            _ => Err(ExpectedToken(
+                "array length".to_string(),
+                "The length of an array has to be an integer literal".to_string(),
+                token.span.clone(),
+            )
+            .into()),
+        }
+    }
+
     pub fn parse_return_type(&mut self) -> Result<Option<FunctionType>> {
         debug!("Parsing return type");
         if self.peek().kind == TokenKind::Identifier {
@@ -252,11 +683,13 @@ impl Parser {
                 self.peek().span.clone(),
             )
             .into())
-        } else {
+        } else if self.peek().kind == TokenKind::Arrow {
             let arrow = self.consume();
-            let type_name = self.expect(TokenKind::Identifier)?;
+            let type_name = self.parse_type()?;
 
             Ok(Some(FunctionType { arrow, type_name }))
+        } else {
+            Ok(None)
         }
     }
 
@@ -265,7 +698,7 @@ impl Parser {
         let mut stmts = vec![];
 
         while self.peek().kind != TokenKind::RightBrace && !self.is_eof() {
-            let stmt = self.parse_stmt()?;
+            let stmt = self.parse_stmt_or_recover(true)?;
 
             if let Some(stmt) = stmt {
                 debug!("Adding statement to block");
@@ -298,10 +731,45 @@ impl Parser {
             self.consume()
         };
         let name = self.expect(TokenKind::Identifier)?;
+        let type_params = self.parse_type_params()?;
 
+        let (receiver, params) = self.parse_params()?;
+
+        let return_type = self.parse_return_type()?;
+
+        self.expect(TokenKind::LeftBrace)?;
+
+        let body = self.parse_block()?;
+
+        self.expect(TokenKind::RightBrace)?;
+
+        Ok(Stmt::new_fn(
+            fn_token,
+            name.literal(),
+            type_params,
+            params,
+            receiver,
+            body,
+            exported,
+            return_type,
+        ))
+    }
+
+    /// `(self, a: int, b: int)`, the parenthesized parameters of a function.
+    fn parse_params(&mut self) -> Result<(Option<Token>, Vec<FnParam>)> {
         self.expect(TokenKind::LeftParen)?;
         let mut params = vec![];
 
+        // A method takes `self` first, without a type
+        let receiver = if self.peek().kind == TokenKind::Identifier
+            && self.peek().literal() == "self"
+            && self.peek_next().kind != TokenKind::Colon
+        {
+            Some(self.consume())
+        } else {
+            None
+        };
+
         if self.peek().kind != TokenKind::RightParen {
             while self.peek().kind != TokenKind::RightParen && !self.is_eof() {
                 self.possible_check(TokenKind::Comma);
@@ -318,22 +786,29 @@ impl Parser {
 
         self.expect(TokenKind::RightParen)?;
 
+        Ok((receiver, params))
+    }
+
+    /// `fn(x: int) -> int { ... }`, an anonymous function used as a value.
+    fn parse_closure(&mut self, fn_token: Token) -> Result<Expr> {
+        debug!("Parsing closure");
+        let (receiver, params) = self.parse_params()?;
         let return_type = self.parse_return_type()?;
 
         self.expect(TokenKind::LeftBrace)?;
-
-        let body = self.parse_block()?;
-
+        let body = self.with_struct_literals(true, Self::parse_block)?;
         self.expect(TokenKind::RightBrace)?;
 
-        Ok(Stmt::new_fn(
+        Ok(Expr::new_closure(Fn {
             fn_token,
-            name.literal(),
+            name: "closure".to_string(),
+            type_params: vec![],
+            receiver,
             params,
             body,
-            exported,
+            exported: false,
             return_type,
-        ))
+        }))
     }
 }
 
@@ -342,6 +817,23 @@ impl Parser {
         self.parse_assignment()
     }
 
+    /// Parses the expression right before a `{` body, see `no_struct_literal`.
+    pub fn parse_header_expr(&mut self) -> Result<Expr> {
+        self.with_struct_literals(false, Self::parse_expr)
+    }
+
+    fn with_struct_literals<T>(
+        &mut self,
+        allowed: bool,
+        parse: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        let previous = std::mem::replace(&mut self.no_struct_literal, !allowed);
+        let parsed = parse(self);
+        self.no_struct_literal = previous;
+
+        parsed
+    }
+
     pub fn expression_stmt(&mut self) -> Result<Stmt> {
         let expr = self.parse_expr()?;
 
@@ -375,8 +867,6 @@ impl Parser {
             TokenKind::Percent => Some(BinOpKind::Modulo),
             TokenKind::And => Some(BinOpKind::And),
             TokenKind::Or => Some(BinOpKind::Or),
-            TokenKind::Increment => Some(BinOpKind::Increment),
-            TokenKind::Decrement => Some(BinOpKind::Decrement),
             TokenKind::MinusEquals => Some(BinOpKind::MinusEquals),
             TokenKind::PlusEquals => Some(BinOpKind::PlusEquals),
             _ => None,
@@ -422,6 +912,7 @@ impl Parser {
         let kind = match token.kind {
             TokenKind::Minus => Some(UnOpKind::Minus),
             TokenKind::Tilde => Some(UnOpKind::BitwiseNot),
+            TokenKind::Bang => Some(UnOpKind::Not),
             _ => None,
         };
         kind.map(|kind| UnOperator::new(kind, token.clone()))
@@ -433,44 +924,115 @@ impl Parser {
             let operand = self.parse_unary_expression();
             return Ok(Expr::new_unary(operator, operand?, token));
         }
-        self.parse_primary_expression()
+        self.parse_postfix_expression()
     }
 
-    pub fn parse_primary_expression(&mut self) -> Result<Expr> {
-        let token = self.consume();
+    pub fn parse_postfix_expression(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_primary_expression()?;
 
-        match &token.kind.clone() {
-            TokenKind::Integer(int) => Ok(Expr::new_integer(token, *int)),
-            TokenKind::Float(float) => Ok(Expr::new_float(token, *float)),
-            TokenKind::True | TokenKind::False => {
-                Ok(Expr::new_bool(token.clone(), token.as_bool().unwrap()))
+        loop {
+            match self.peek().kind {
+                TokenKind::Dot => {
+                    let dot = self.consume();
+                    let field = self.expect(TokenKind::Identifier)?;
+
+                    expr = Expr::new_member(expr, field, dot);
+                }
+                TokenKind::LeftParen => {
+                    // Calls are named after the last identifier of their callee
+                    let token = match &expr {
+                        Expr::Variable(var) => var.token.clone(),
+                        Expr::Member(member) => member.field.clone(),
+                        Expr::Path(path) => path.name.clone(),
+                        _ => self.peek(),
+                    };
+
+                    expr = self.parse_call_expr(expr, token)?;
+                }
+                TokenKind::LeftBracket => {
+                    let open = self.consume();
+                    let index = self.with_struct_literals(true, Self::parse_expr)?;
+                    let close = self.expect(TokenKind::RightBracket)?;
+
+                    expr = Expr::new_index(expr, open, index, close);
+                }
+                TokenKind::Increment | TokenKind::Decrement => {
+                    // `x++` is `x += 1`, so it's checked and lowered like one
+                    let token = self.consume();
+                    let kind = match token.kind {
+                        TokenKind::Increment => BinOpKind::PlusEquals,
+                        _ => BinOpKind::MinusEquals,
+                    };
+                    let one = Expr::new_integer(token.clone(), 1);
+
+                    expr = Expr::new_binary(expr, BinOperator::new(kind, token), one);
+                }
+                _ => break,
             }
+        }
+
+        Ok(expr)
+    }
+
+    pub fn parse_primary_expression(&mut self) -> Result<Expr> {
+        let token = self.peek();
+
+        // The offending token is left in place so error recovery can synchronize on it
+        if !matches!(
+            token.kind,
+            TokenKind::Integer(_)
+                | TokenKind::Float(_)
+                | TokenKind::True
+                | TokenKind::False
+                | TokenKind::Identifier
+                | TokenKind::LeftParen
+                | TokenKind::String(_)
+                | TokenKind::Match
+                | TokenKind::LeftBracket
+                | TokenKind::Fn
+        ) {
+            return Err(UnexpectedToken(token.kind.to_string(), token.span.clone()).into());
+        }
+        self.consume();
+
+        match &token.kind {
+            TokenKind::Integer(int) => Ok(Expr::new_integer(token.clone(), *int)),
+            TokenKind::Float(float) => Ok(Expr::new_float(token.clone(), *float)),
             TokenKind::Identifier => {
                 log::debug!("Parsing identifier: {}", token.literal());
-                if self.peek().kind == TokenKind::LeftParen {
-                    self.parse_call_expr(token)
+                if self.peek().kind == TokenKind::ColonColon {
+                    self.consume();
+                    let name = self.expect(TokenKind::Identifier)?;
+
+                    Ok(Expr::new_path(token, name))
+                } else if self.peek().kind == TokenKind::LeftBrace && !self.no_struct_literal {
+                    self.parse_struct_literal(token)
                 } else {
                     Ok(Expr::new_variable(token.clone(), token.literal()))
                 }
             }
             TokenKind::LeftParen => {
-                let expr = self.parse_expr()?;
+                let expr = self.with_struct_literals(true, Self::parse_expr)?;
+                self.expect(TokenKind::RightParen)?;
 
                 Ok(Expr::new_parenthesized(expr))
             }
             TokenKind::String(s) => Ok(Expr::new_string(token.clone(), s.clone())),
-            _ => Err(UnexpectedToken(token.kind.to_string(), token.span.clone()).into()),
+            TokenKind::Match => self.parse_match(token),
+            TokenKind::LeftBracket => self.parse_array_literal(token),
+            TokenKind::Fn => self.parse_closure(token),
+            _ => Ok(Expr::new_bool(token.clone(), token.as_bool().unwrap())),
         }
     }
 
-    pub fn parse_call_expr(&mut self, callee: Token) -> Result<Expr> {
+    pub fn parse_call_expr(&mut self, callee: Expr, token: Token) -> Result<Expr> {
         self.expect(TokenKind::LeftParen)?;
 
         let mut args = vec![];
 
         if self.peek().kind != TokenKind::RightParen {
             while self.peek().kind != TokenKind::RightParen && !self.is_eof() {
-                let arg = self.parse_expr()?;
+                let arg = self.with_struct_literals(true, Self::parse_expr)?;
 
                 args.push(arg);
 
@@ -482,7 +1044,145 @@ impl Parser {
 
         self.expect(TokenKind::RightParen)?;
 
-        Ok(Expr::new_call(callee.literal(), args, callee))
+        Ok(Expr::new_call(callee, args, token))
+    }
+
+    pub fn parse_struct_literal(&mut self, name: Token) -> Result<Expr> {
+        self.expect(TokenKind::LeftBrace)?;
+
+        let mut fields = vec![];
+        while self.peek().kind != TokenKind::RightBrace && !self.is_eof() {
+            let ident = self.expect(TokenKind::Identifier)?;
+            self.expect(TokenKind::Colon)?;
+            let value = self.parse_expr()?;
+
+            fields.push(FieldInit { ident, value });
+
+            if self.peek().kind != TokenKind::RightBrace {
+                self.expect(TokenKind::Comma)?;
+            }
+        }
+
+        self.expect(TokenKind::RightBrace)?;
+
+        Ok(Expr::new_struct_literal(name, fields))
+    }
+
+    pub fn parse_array_literal(&mut self, open: Token) -> Result<Expr> {
+        let mut elements = vec![];
+        let mut repeat = None;
+
+        while self.peek().kind != TokenKind::RightBracket && !self.is_eof() {
+            elements.push(self.with_struct_literals(true, Self::parse_expr)?);
+
+            if elements.len() == 1 && self.peek().kind == TokenKind::Semicolon {
+                self.consume();
+                repeat = Some(self.expect_array_size()?);
+                break;
+            }
+            if self.peek().kind != TokenKind::RightBracket {
+                self.expect(TokenKind::Comma)?;
+            }
+        }
+
+        self.expect(TokenKind::RightBracket)?;
+
+        Ok(Expr::new_array_literal(open, elements, repeat))
+    }
+
+    pub fn parse_match(&mut self, match_token: Token) -> Result<Expr> {
+        let scrutinee = self.parse_header_expr()?;
+
+        self.expect(TokenKind::LeftBrace)?;
+
+        let mut arms = vec![];
+        while self.peek().kind != TokenKind::RightBrace && !self.is_eof() {
+            let pattern = self.parse_pattern()?;
+            self.expect(TokenKind::FatArrow)?;
+
+            let body = if self.peek().kind == TokenKind::LeftBrace {
+                self.consume();
+                let block = self.parse_block()?;
+                self.expect(TokenKind::RightBrace)?;
+                self.possible_check(TokenKind::Comma);
+
+                ArmBody::Block(block)
+            } else {
+                let expr = self.with_struct_literals(true, Self::parse_expr)?;
+                if self.peek().kind != TokenKind::RightBrace {
+                    self.expect(TokenKind::Comma)?;
+                }
+
+                ArmBody::Expr(expr)
+            };
+
+            arms.push(MatchArm { pattern, body });
+        }
+
+        self.expect(TokenKind::RightBrace)?;
+
+        Ok(Expr::new_match(match_token, scrutinee, arms))
+    }
+
+    pub fn parse_pattern(&mut self) -> Result<Pattern> {
+        let token = self.peek();
+
+        let literal = |token: Token, value: LiteralType| Pattern::Literal(Literal { token, value });
+        let pattern = match &token.kind {
+            TokenKind::Identifier if token.literal() == "_" => Pattern::Wildcard(self.consume()),
+            TokenKind::Identifier => {
+                let name = self.consume();
+
+                let mut bindings = vec![];
+                if self.peek().kind == TokenKind::LeftParen {
+                    self.consume();
+                    while self.peek().kind != TokenKind::RightParen && !self.is_eof() {
+                        bindings.push(self.expect(TokenKind::Identifier)?);
+
+                        if self.peek().kind != TokenKind::RightParen {
+                            self.expect(TokenKind::Comma)?;
+                        }
+                    }
+                    self.expect(TokenKind::RightParen)?;
+                }
+
+                Pattern::Variant { name, bindings }
+            }
+            TokenKind::Integer(int) => literal(self.consume(), LiteralType::Int(*int)),
+            TokenKind::Minus if matches!(self.peek_next().kind, TokenKind::Integer(_)) => {
+                let minus = self.consume();
+                let int = self.consume();
+                let TokenKind::Integer(value) = int.kind else {
+                    unreachable!("Checked by the guard")
+                };
+                let token = Token::new(
+                    TokenKind::Integer(value.wrapping_neg()),
+                    TextSpan::combine(vec![minus.span, int.span]),
+                );
+
+                literal(token, LiteralType::Int(value.wrapping_neg()))
+            }
+            TokenKind::True | TokenKind::False => {
+                let token = self.consume();
+                let value = token.as_bool().unwrap();
+
+                literal(token, LiteralType::Bool(value))
+            }
+            TokenKind::String(string) => {
+                literal(self.consume(), LiteralType::String(string.clone()))
+            }
+            _ => {
+                return Err(ExpectedToken(
+                    "pattern".to_string(),
+                    "Patterns are '_', int, bool or string literals, or variants like 'Circle(r)'"
+                        .to_string(),
+                    token.span.clone(),
+                )
+                .into())
+            }
+        };
+
+        Ok(pattern)
     }
 
     pub fn parse_optional_type_annotation(&mut self) -> Result<Option<TypeAnnotation>> {
@@ -495,14 +1195,183 @@ impl Parser {
 
     pub fn parse_assignment(&mut self) -> Result<Expr> {
         log::debug!("Parsing assignment");
-        if self.peek().kind == TokenKind::Identifier && self.peek_next().kind == TokenKind::Equals {
-            let ident = self.consume();
-            let equals = self.consume();
-            let value = self.parse_expr()?;
+        let target = self.parse_range()?;
 
-            Ok(Expr::new_assign(ident, equals, value))
-        } else {
-            Ok(self.parse_binary_expression()?)
+        if self.peek().kind != TokenKind::Equals {
+            return Ok(target);
+        }
+        if !matches!(target, Expr::Variable(_) | Expr::Member(_) | Expr::Index(_)) {
+            return Err(ExpectedToken(
+                "assignable expression".to_string(),
+                "Only variables and their fields or elements can be assigned to".to_string(),
+                target.span(),
+            )
+            .into());
+        }
+
+        let equals = self.consume();
+        let value = self.parse_expr()?;
+
+        Ok(Expr::new_assign(target, equals, value))
+    }
+
+    /// Ranges bind looser than any binary operator, so `0..n + 1` ends at `n + 1`.
+    pub fn parse_range(&mut self) -> Result<Expr> {
+        let start = self.parse_binary_expression()?;
+
+        let inclusive = match self.peek().kind {
+            TokenKind::DotDot => false,
+            TokenKind::DotDotEquals => true,
+            _ => return Ok(start),
+        };
+        let token = self.consume();
+        let end = self.parse_binary_expression()?;
+
+        Ok(Expr::new_range(start, end, inclusive, token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse(source: &str) -> Ast {
+        let tokens = Lexer::from_source(source.to_string()).lex().unwrap();
+        Parser::new(tokens).parse().unwrap()
+    }
+
+    /// The messages of the syntax errors in `source`.
+    fn errors(source: &str) -> Vec<String> {
+        let tokens = Lexer::from_source(source.to_string()).lex().unwrap();
+        match Parser::new(tokens).parse() {
+            Ok(_) => vec![],
+            Err(err) => match err.downcast::<PulseError>() {
+                Ok(Errors(errors)) => errors.iter().map(ToString::to_string).collect(),
+                other => panic!("Unexpected error {:?}", other),
+            },
         }
     }
+
+    #[test]
+    fn loops_take_labels() {
+        let ast = parse("fn main() { rows: for i in xs { cols: while i < 3 { } } }");
+        let Some(Stmt::Fn(main)) = ast.stmts.first() else {
+            panic!("Expected a function");
+        };
+        let Some(Stmt::For(for_stmt)) = main.body.stmts.first() else {
+            panic!("Expected a for loop");
+        };
+        let Some(Stmt::While(while_stmt)) = for_stmt.body.stmts.first() else {
+            panic!("Expected a while loop");
+        };
+
+        assert_eq!(
+            for_stmt.label.as_ref().map(Token::literal),
+            Some("rows".to_string())
+        );
+        assert_eq!(for_stmt.ident.literal(), "i");
+        assert_eq!(
+            while_stmt.label.as_ref().map(Token::literal),
+            Some("cols".to_string())
+        );
+    }
+
+    #[test]
+    fn range_binds_looser_than_arithmetic() {
+        let ast = parse("fn main() { for i in 0..n + 1 { } }");
+        let Some(Stmt::Fn(main)) = ast.stmts.first() else {
+            panic!("Expected a function");
+        };
+        let Some(Stmt::For(for_stmt)) = main.body.stmts.first() else {
+            panic!("Expected a for loop");
+        };
+        let Expr::Range(range) = for_stmt.iterable.as_ref() else {
+            panic!("Expected a range, found {:?}", for_stmt.iterable);
+        };
+
+        assert!(!range.inclusive);
+        assert!(matches!(range.start.as_ref(), Expr::Literal(_)));
+        assert!(
+            matches!(range.end.as_ref(), Expr::Binary(binary) if binary.operator == BinOpKind::Plus)
+        );
+    }
+
+    /// The statements of the body of the first loop in `main`.
+    fn loop_body(ast: &Ast) -> &[Stmt] {
+        let Some(Stmt::Fn(main)) = ast.stmts.first() else {
+            panic!("Expected a function");
+        };
+        match main.body.stmts.first() {
+            Some(Stmt::Loop(loop_stmt)) => &loop_stmt.body.stmts,
+            other => panic!("Expected a loop, found {:?}", other),
+        }
+    }
+
+    fn label(stmt: &Stmt) -> Option<String> {
+        match stmt {
+            Stmt::Break(break_stmt) => break_stmt.label.as_ref().map(Token::literal),
+            Stmt::Continue(continue_stmt) => continue_stmt.label.as_ref().map(Token::literal),
+            other => panic!("Expected break or continue, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn loop_control_takes_a_label_on_the_same_line() {
+        let ast = parse("fn main() { outer: loop { break outer; continue outer } }");
+        let body = loop_body(&ast);
+
+        assert_eq!(body.len(), 2);
+        assert_eq!(label(&body[0]), Some("outer".to_string()));
+        assert_eq!(label(&body[1]), Some("outer".to_string()));
+    }
+
+    #[test]
+    fn identifier_on_the_next_line_is_not_a_label() {
+        let ast = parse("fn main() { loop {\n break\n count = 1;\n } }");
+        let body = loop_body(&ast);
+
+        assert_eq!(body.len(), 2);
+        assert_eq!(label(&body[0]), None);
+        assert!(matches!(&body[1], Stmt::Expr(expr) if matches!(expr.as_ref(), Expr::Assign(_))));
+    }
+
+    #[test]
+    fn recovery_skips_the_braces_a_failed_statement_opened() {
+        let source = "
+            fn main() -> int {
+                let p = Point { x: 1, y: };
+                if x == { return 1; }
+                let q = 2;
+                return 0;
+            }
+        ";
+
+        assert_eq!(
+            errors(source),
+            ["Unexpected token: }", "Unexpected token: {"]
+        );
+    }
+
+    #[test]
+    fn recovery_leaves_the_brace_closing_the_block() {
+        let source = "
+            fn main() -> int {
+                let a = 1 +
+            }
+            fn other() -> int { return 1; }
+        ";
+
+        assert_eq!(errors(source), ["Unexpected token: }"]);
+    }
+
+    #[test]
+    fn unterminated_blocks_are_reported_at_the_end() {
+        let source = "fn main() -> int {\n let a = 1;\n if a == 1 {\n let b = ;";
+
+        assert_eq!(
+            errors(source),
+            ["Unexpected token: ;", "Expected }.", "Expected }."]
+        );
+    }
 }
@@ -1,7 +1,8 @@
 use clap::{
-    builder::{styling, PossibleValuesParser, Styles, TypedValueParser},
-    Args, Parser, Subcommand, ValueHint,
+    builder::{styling, Styles},
+    Args, Parser, Subcommand, ValueEnum,
 };
+use pulse_lang::emit::Emit;
 
 #[derive(Debug, Parser)]
 #[command(author, version, about, name = "pulse",
@@ -20,7 +21,28 @@ pub struct Cli {
 #[derive(Debug, Subcommand)]
 pub enum Commands {
     #[command(about = "Run a file")]
-    Run {},
+    Run {
+        #[command(flatten)]
+        options: BuildOptions,
+
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = Backend::default(),
+            help = "How to execute the program"
+        )]
+        backend: Backend,
+
+        #[arg(last = true, name = "ARGS")]
+        args: Vec<String>,
+    },
+
+    #[cfg(feature = "llvm")]
+    #[command(about = "Compile the project into build/")]
+    Build {
+        #[command(flatten)]
+        options: BuildOptions,
+    },
 
     #[command(about = "Initialize a new project")]
     Init {
@@ -28,3 +50,73 @@ pub enum Commands {
         name: Option<String>,
     },
 }
+
+#[derive(Debug, Args)]
+pub struct BuildOptions {
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        help = "Write intermediate output to build/"
+    )]
+    pub emit: Vec<Emit>,
+
+    #[arg(long, help = "Build with the release profile")]
+    pub release: bool,
+
+    #[arg(
+        short = 'O',
+        value_name = "LEVEL",
+        value_parser = clap::value_parser!(u8).range(0..=3),
+        help = "Optimization level (0-3), overrides the profile"
+    )]
+    pub opt_level: Option<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Backend {
+    /// JIT-compile with LLVM
+    #[cfg(feature = "llvm")]
+    Llvm,
+    /// Evaluate the AST with the tree-walking interpreter
+    Interp,
+}
+
+impl Default for Backend {
+    /// LLVM when it was compiled in, the interpreter otherwise.
+    fn default() -> Self {
+        #[cfg(feature = "llvm")]
+        return Backend::Llvm;
+
+        #[cfg(not(feature = "llvm"))]
+        return Backend::Interp;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_options(args: &[&str]) -> clap::error::Result<BuildOptions> {
+        let cli = Cli::try_parse_from(["pulse", "run"].iter().chain(args))?;
+        match cli.command {
+            Commands::Run { options, .. } => Ok(options),
+            other => panic!("Expected the run command, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_release_and_opt_level() {
+        let options = build_options(&["--release", "-O", "2"]).unwrap();
+
+        assert!(options.release);
+        assert_eq!(options.opt_level, Some(2));
+        assert_eq!(build_options(&["-O1"]).unwrap().opt_level, Some(1));
+        assert_eq!(build_options(&[]).unwrap().opt_level, None);
+    }
+
+    #[test]
+    fn rejects_opt_levels_above_3() {
+        assert!(build_options(&["-O", "4"]).is_err());
+    }
+}
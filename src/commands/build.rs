@@ -0,0 +1,55 @@
+use crate::cli::BuildOptions;
+use anyhow::Result;
+use inkwell::context::Context;
+use pulse_lang::{
+    diagnostic::print_diagnostic,
+    error::FAILURE_EXIT_CODE,
+    linker::{create_archive, link_executable},
+    llvm::target::host_target_machine,
+    project::{Project, ProjectType},
+};
+use std::fs;
+
+pub fn build_command(options: BuildOptions) -> Result<()> {
+    let mut project = Project::find_project()?;
+    project.configure(options.emit, options.release, options.opt_level)?;
+    let context = Context::create();
+
+    let mut codegen = match project.build_main(&context) {
+        Ok(codegen) => codegen,
+        Err(err) => {
+            print_diagnostic(err, Some(project.content));
+            std::process::exit(FAILURE_EXIT_CODE);
+        }
+    };
+
+    let build_dir = project.build_dir();
+    fs::create_dir_all(&build_dir)?;
+
+    let name = project.manifest.name.clone();
+    let object = build_dir.join(format!("{}.o", name));
+
+    if let Some(entry_point) = project.entry_point {
+        codegen.emit_entry_shim(entry_point)?;
+    }
+
+    let machine = host_target_machine(project.profile.opt_level)?;
+    codegen.write_object_file(&machine, &object)?;
+
+    let output = match project.project_type {
+        ProjectType::Binary => {
+            let output = build_dir.join(&name);
+            link_executable(&object, &output)?;
+            output
+        }
+        ProjectType::Library => {
+            let output = build_dir.join(format!("lib{}.a", name));
+            create_archive(&object, &output)?;
+            output
+        }
+    };
+
+    log::info!("Built {:?}", output);
+
+    Ok(())
+}
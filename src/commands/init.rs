@@ -1,5 +1,5 @@
-use crate::error::PulseError;
 use anyhow::{Context, Result};
+use pulse_lang::error::PulseError;
 use std::{fs, path::PathBuf};
 
 pub fn init_command(name: Option<String>) -> Result<()> {
@@ -13,15 +13,17 @@ pub fn init_command(name: Option<String>) -> Result<()> {
     fs::create_dir_all(path.join("src"))
         .with_context(|| format!("Failed to create project directories for {}", project_name))?;
 
+    let manifest = format!(
+        "[project]\nname = \"{}\"\nversion = \"0.1.0\"\n",
+        project_name
+    );
+
     let files: Vec<(&str, &str)> = vec![
         (
             "src/main.pulse",
-            "fn main() {\n    println(\"Hello, World!\");\n}\n",
-        ),
-        (
-            "pulse.toml",
-            "[project]\nname = \"pulse_project\"\nversion = \"0.1.0\"\n",
+            "use { println } from \"std::io\";\n\nfn main() {\n    println(\"Hello, World!\");\n}\n",
         ),
+        ("pulse.toml", &manifest),
         (".gitignore", "build/\n"),
     ];
 
@@ -1,2 +1,4 @@
+#[cfg(feature = "llvm")]
+pub mod build;
 pub mod init;
 pub mod run;
@@ -1,17 +1,136 @@
-use crate::{diagnostic::print_diagnostic, project::Project};
+use crate::cli::{Backend, BuildOptions};
 use anyhow::Result;
+#[cfg(feature = "llvm")]
+use inkwell::{context::Context, execution_engine::ExecutionEngine};
+use pulse_lang::{
+    diagnostic::print_diagnostic,
+    emit::Emit,
+    error::{PulseError::CannotRunLibrary, FAILURE_EXIT_CODE, RUNTIME_ERROR_EXIT_CODE},
+    interp,
+    project::{Project, ProjectType},
+};
+#[cfg(feature = "llvm")]
+use pulse_lang::{error::PulseError::CodegenError, project::EntryPoint};
 
-pub fn run_command() -> Result<()> {
+#[cfg(feature = "llvm")]
+type MainWithArg = unsafe extern "C" fn(i64) -> i64;
+#[cfg(feature = "llvm")]
+type MainWithArgVoid = unsafe extern "C" fn(i64);
+#[cfg(feature = "llvm")]
+type Main = unsafe extern "C" fn() -> i64;
+#[cfg(feature = "llvm")]
+type MainVoid = unsafe extern "C" fn();
+
+pub fn run_command(options: BuildOptions, backend: Backend, args: Vec<String>) -> Result<()> {
     let mut project = Project::find_project()?;
+    project.configure(options.emit, options.release, options.opt_level)?;
+
+    if project.project_type == ProjectType::Library {
+        print_diagnostic(CannotRunLibrary.into(), None);
+        std::process::exit(FAILURE_EXIT_CODE);
+    }
+
+    // `arg` receives the number of arguments passed after `--`
+    let arg = args.len() as i64;
+
+    let exit_code = match backend {
+        #[cfg(feature = "llvm")]
+        Backend::Llvm => run_llvm(&mut project, arg)?,
+        Backend::Interp => run_interp(&mut project, arg),
+    };
+
+    let Some(exit_code) = exit_code else {
+        std::process::exit(FAILURE_EXIT_CODE);
+    };
+
+    log::debug!("Program exited with code {}", exit_code);
+
+    std::process::exit(exit_code as i32);
+}
+
+/// Returns `None` when the program didn't compile, after printing the diagnostics.
+#[cfg(feature = "llvm")]
+fn run_llvm(project: &mut Project, arg: i64) -> Result<Option<i64>> {
+    let context = Context::create();
+
+    let codegen = match project.build_main(&context) {
+        Ok(codegen) => codegen,
+        Err(err) => {
+            print_diagnostic(err, Some(project.content.clone()));
+            return Ok(None);
+        }
+    };
+
+    log::debug!("Built main file");
 
-    match project.build_main() {
-        Ok(_) => {
-            log::debug!("Built main file");
-            Ok(())
+    let entry_point = project
+        .entry_point
+        .expect("Binary projects always have an entry point");
+
+    execute_main(&codegen.execution_engine, entry_point, arg).map(Some)
+}
+
+/// Returns `None` when the program didn't compile, after printing the diagnostics. A
+/// runtime error exits with the same status as a compiled program stopped by one.
+fn run_interp(project: &mut Project, arg: i64) -> Option<i64> {
+    for emit in [Emit::LlvmIr, Emit::Asm, Emit::Obj] {
+        if project.emit.contains(&emit) {
+            log::warn!("--emit={} is ignored by the interpreter", emit.name());
+        }
+    }
+
+    let ast = match project.analyze_main() {
+        Ok(ast) => ast,
+        Err(err) => {
+            print_diagnostic(err, Some(project.content.clone()));
+            return None;
         }
+    };
+
+    let entry_point = project
+        .entry_point
+        .expect("Binary projects always have an entry point");
+
+    match interp::run(ast, entry_point, arg) {
+        Ok(exit_code) => Some(exit_code),
         Err(err) => {
-            print_diagnostic(err, Some(project.content));
-            Ok(())
+            print_diagnostic(err, Some(project.content.clone()));
+            Some(RUNTIME_ERROR_EXIT_CODE.into())
         }
     }
 }
+
+#[cfg(feature = "llvm")]
+fn execute_main(engine: &ExecutionEngine, entry_point: EntryPoint, arg: i64) -> Result<i64> {
+    let lookup_error = |err| CodegenError(format!("Couldn't find 'main' in the module: {}", err));
+
+    // SAFETY: the signature is checked against the AST by `EntryPoint::from_ast`
+    let exit_code = unsafe {
+        match (entry_point.takes_arg, entry_point.returns_int) {
+            (true, true) => engine
+                .get_function::<MainWithArg>("main")
+                .map_err(lookup_error)?
+                .call(arg),
+            (true, false) => {
+                engine
+                    .get_function::<MainWithArgVoid>("main")
+                    .map_err(lookup_error)?
+                    .call(arg);
+                0
+            }
+            (false, true) => engine
+                .get_function::<Main>("main")
+                .map_err(lookup_error)?
+                .call(),
+            (false, false) => {
+                engine
+                    .get_function::<MainVoid>("main")
+                    .map_err(lookup_error)?
+                    .call();
+                0
+            }
+        }
+    };
+
+    Ok(exit_code)
+}
@@ -1,7 +1,7 @@
 use crate::{error::PulseError, lexer::span::TextSpan};
 use colored::Colorize;
 use log::Level;
-use std::io::{BufWriter, Stderr, Write};
+use std::io::{BufWriter, Write};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Diagnostic {
@@ -11,10 +11,12 @@ pub struct Diagnostic {
     pub location: Option<TextSpan>,
     pub hint: Option<String>,
     pub content: Option<String>,
+    /// The file `location` points into, when the project has more than one.
+    pub file: Option<String>,
 }
 
 impl Diagnostic {
-    pub fn log_pretty(&self, buff: &mut BufWriter<Stderr>) {
+    pub fn log_pretty(&self, buff: &mut impl Write) {
         writeln!(
             buff,
             "{}{}{}",
@@ -25,19 +27,36 @@ impl Diagnostic {
         .expect("Error writing level");
 
         if let Some(location) = &self.location {
-            if let Some(content) = &self.content {
-                let line = content
-                    .lines()
-                    .nth(location.start.line as usize)
-                    .expect("Error getting line");
+            if let Some(line) = self
+                .content
+                .as_ref()
+                .and_then(|content| content.lines().nth(location.start.line as usize))
+            {
                 let line_number = location.start.line;
                 let column = location.start.column;
                 let line_content = line.trim_end();
-                let decoration =
-                    "^".repeat(location.end.column as usize - location.start.column as usize);
+                // A span over several lines is underlined up to the end of its first line
+                let end_column = if location.end.line == location.start.line {
+                    location.end.column as usize
+                } else {
+                    line_content.chars().count()
+                };
+                let decoration = "^".repeat(end_column.saturating_sub(column as usize).max(1));
 
-                writeln!(buff, "{} {}:{}", "--->".cyan(), line_number, column)
-                    .expect("Error writing line number");
+                match &self.file {
+                    Some(file) => {
+                        writeln!(
+                            buff,
+                            "{} {}:{}:{}",
+                            "--->".cyan(),
+                            file,
+                            line_number,
+                            column
+                        )
+                    }
+                    None => writeln!(buff, "{} {}:{}", "--->".cyan(), line_number, column),
+                }
+                .expect("Error writing line number");
 
                 if line_number > 1 {
                     let line_before = format!("{} |", line_number - 1);
@@ -67,7 +86,7 @@ impl Diagnostic {
         self.print_hint(buff);
     }
 
-    pub fn print_hint(&self, buff: &mut BufWriter<Stderr>) {
+    pub fn print_hint(&self, buff: &mut impl Write) {
         if let Some(hint) = &self.hint {
             writeln!(buff, "{}{}", "Hint: ".bright_cyan(), hint.bright_cyan())
                 .expect("Error writing hint");
@@ -75,62 +94,201 @@ impl Diagnostic {
     }
 }
 
-pub fn print_diagnostic(err: anyhow::Error, content: Option<String>) {
-    let err = err.downcast_ref::<PulseError>().unwrap();
-
-    let err_str = err.to_string();
-    let diagnostic = match err {
-        PulseError::ProjectNotFound
-        | PulseError::ProjectAlreadyExists
-        | PulseError::InvalidProjectStructure
-        | PulseError::MultipleEntryPoints => Diagnostic {
-            title: err_str,
-            text: None,
-            level: Level::Error,
-            location: None,
-            hint: None,
-            content: None,
-        },
-        PulseError::Io(_) => Diagnostic {
-            title: "IO error".to_string(),
-            text: Some(err_str),
-            level: Level::Error,
-            location: None,
-            hint: None,
-            content: None,
-        },
-        PulseError::InvalidToken(_, span)
-        | PulseError::SemanticError(_, span)
-        | PulseError::UnexpectedToken(_, span) => Diagnostic {
-            title: err_str,
-            text: None,
-            level: Level::Error,
-            location: Some(span.clone()),
-            hint: None,
-            content,
-        },
-        PulseError::ExpectedToken(expected, hint, span) => Diagnostic {
-            title: format!("Expected {}", expected),
-            text: None,
-            level: Level::Error,
-            location: Some(span.clone()),
-            hint: Some(hint.clone()),
-            content,
-        },
-        PulseError::ResolverError(_) => Diagnostic {
-            title: err_str,
-            text: None,
-            level: Level::Error,
-            location: None,
-            hint: None,
-            content: None,
-        },
-        _ => {
-            log::error!("{:?}", err);
-            return;
+impl Diagnostic {
+    /// One diagnostic per compiler error carried by `err`, falling back to a plain
+    /// message for errors that don't come from the compiler.
+    pub fn from_error(err: &anyhow::Error, source: &str) -> Vec<Diagnostic> {
+        match err.downcast_ref::<PulseError>() {
+            Some(PulseError::Errors(errors)) => errors
+                .iter()
+                .map(|err| Diagnostic::from_pulse_error(err, Some(source.to_string())))
+                .collect(),
+            Some(PulseError::InModule(file, content, err)) => match err.as_ref() {
+                PulseError::Errors(errors) => errors
+                    .iter()
+                    .map(|err| Diagnostic::in_module(err, file, content))
+                    .collect(),
+                err => vec![Diagnostic::in_module(err, file, content)],
+            },
+            Some(err) => vec![Diagnostic::from_pulse_error(err, Some(source.to_string()))],
+            None => vec![Diagnostic {
+                title: err.to_string(),
+                text: None,
+                level: Level::Error,
+                location: None,
+                hint: None,
+                content: None,
+                file: None,
+            }],
+        }
+    }
+
+    fn in_module(err: &PulseError, file: &str, content: &str) -> Diagnostic {
+        Diagnostic {
+            file: Some(file.to_string()),
+            ..Diagnostic::from_pulse_error(err, Some(content.to_string()))
+        }
+    }
+
+    pub fn from_pulse_error(err: &PulseError, content: Option<String>) -> Diagnostic {
+        let err_str = err.to_string();
+        match err {
+            PulseError::InModule(file, content, err) => Diagnostic::in_module(err, file, content),
+            PulseError::ProjectNotFound
+            | PulseError::ProjectAlreadyExists
+            | PulseError::InvalidProjectStructure
+            | PulseError::MultipleEntryPoints
+            | PulseError::MissingEntryPoint
+            | PulseError::CannotRunLibrary => Diagnostic {
+                title: err_str,
+                text: None,
+                level: Level::Error,
+                location: None,
+                hint: None,
+                content: None,
+                file: None,
+            },
+            PulseError::Io(_) => Diagnostic {
+                title: "IO error".to_string(),
+                text: Some(err_str),
+                level: Level::Error,
+                location: None,
+                hint: None,
+                content: None,
+                file: None,
+            },
+            PulseError::InvalidToken(_, span)
+            | PulseError::SemanticError(_, span)
+            | PulseError::UnexpectedToken(_, span) => Diagnostic {
+                title: err_str,
+                text: None,
+                level: Level::Error,
+                location: Some(span.clone()),
+                hint: None,
+                content,
+                file: None,
+            },
+            PulseError::ExpectedToken(expected, hint, span) => Diagnostic {
+                title: format!("Expected {}", expected),
+                text: None,
+                level: Level::Error,
+                location: Some(span.clone()),
+                hint: Some(hint.clone()),
+                content,
+                file: None,
+            },
+            PulseError::RuntimeError(_, span) => Diagnostic {
+                title: err_str,
+                text: None,
+                level: Level::Error,
+                location: Some(span.clone()),
+                hint: Some("The program was stopped by the interpreter".to_string()),
+                content,
+                file: None,
+            },
+            PulseError::ResolverError(_)
+            | PulseError::CodegenError(_)
+            | PulseError::LinkerError(_)
+            | PulseError::ManifestError(_)
+            | PulseError::CircularImport(..)
+            | PulseError::Errors(_) => Diagnostic {
+                title: err_str,
+                text: None,
+                level: Level::Error,
+                location: None,
+                hint: None,
+                content: None,
+                file: None,
+            },
         }
+    }
+}
+
+pub fn print_diagnostic(err: anyhow::Error, content: Option<String>) {
+    let Some(err) = err.downcast_ref::<PulseError>() else {
+        log::error!("{:?}", err);
+        return;
     };
 
     let mut buff = BufWriter::new(std::io::stderr());
-    diagnostic.log_pretty(&mut buff);
+    log_error(err, content, None, &mut buff);
+}
+
+fn log_error(err: &PulseError, content: Option<String>, file: Option<&str>, buff: &mut impl Write) {
+    match err {
+        PulseError::InModule(file, content, err) => {
+            return log_error(err, Some(content.clone()), Some(file), buff)
+        }
+        // The cycle comes first, then each `use` statement in it
+        PulseError::CircularImport(_, imports) => {
+            Diagnostic::from_pulse_error(err, None).log_pretty(buff);
+            for import in imports {
+                if let PulseError::InModule(file, content, import) = import {
+                    Diagnostic {
+                        level: Level::Info,
+                        ..Diagnostic::in_module(import, file, content)
+                    }
+                    .log_pretty(buff);
+                }
+            }
+            return;
+        }
+        // Render every collected error, then the summary carried by `Errors` itself
+        PulseError::Errors(errors) => {
+            for err in errors {
+                log_error(err, content.clone(), file, buff);
+                writeln!(buff).expect("Error writing separator");
+            }
+        }
+        _ => {}
+    }
+
+    Diagnostic {
+        file: file.map(str::to_string),
+        ..Diagnostic::from_pulse_error(err, content)
+    }
+    .log_pretty(buff);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::position::Position;
+
+    /// The lines `log_pretty` prints for an error at `start..end` in `content`.
+    fn pretty(content: &str, start: Position, end: Position) -> Vec<String> {
+        colored::control::set_override(false);
+        let span = TextSpan::new(start, end, String::new());
+        let err = PulseError::SemanticError("Type mismatch".to_string(), span);
+
+        let mut buff = vec![];
+        Diagnostic::from_pulse_error(&err, Some(content.to_string())).log_pretty(&mut buff);
+        String::from_utf8(buff)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn spans_over_several_lines_are_underlined_to_the_end_of_the_first() {
+        let lines = pretty(
+            "fn main() {\n    let a = 1 +\n        \"s\";\n}",
+            Position::new(1, 12, 24),
+            Position::new(2, 11, 39),
+        );
+
+        assert_eq!(lines[3].trim(), "^^^");
+    }
+
+    #[test]
+    fn empty_spans_get_one_caret() {
+        let lines = pretty(
+            "fn main() {\n    let a = 1;\n",
+            Position::new(1, 14, 26),
+            Position::new(1, 14, 26),
+        );
+
+        assert_eq!(lines[3].trim(), "^");
+    }
 }
@@ -0,0 +1,50 @@
+/// Intermediate compiler output that can be written to `build/` with `--emit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Emit {
+    Tokens,
+    Ast,
+    Symbols,
+    LlvmIr,
+    Asm,
+    Obj,
+}
+
+impl Emit {
+    /// How the stage is spelled on the command line, `--emit=llvm-ir`.
+    pub fn name(&self) -> &str {
+        match self {
+            Emit::Tokens => "tokens",
+            Emit::Ast => "ast",
+            Emit::Symbols => "symbols",
+            Emit::LlvmIr => "llvm-ir",
+            Emit::Asm => "asm",
+            Emit::Obj => "obj",
+        }
+    }
+
+    pub fn extension(&self) -> &str {
+        match self {
+            Emit::Tokens => "tokens",
+            Emit::Ast => "ast",
+            Emit::Symbols => "symbols",
+            Emit::LlvmIr => "ll",
+            Emit::Asm => "s",
+            Emit::Obj => "o",
+        }
+    }
+}
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use super::*;
+    use clap::ValueEnum;
+
+    #[test]
+    fn names_match_the_command_line() {
+        for emit in Emit::value_variants() {
+            let value = emit.to_possible_value().unwrap();
+            assert_eq!(value.get_name(), emit.name());
+        }
+    }
+}
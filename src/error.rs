@@ -1,6 +1,12 @@
 use crate::lexer::span::TextSpan;
 use thiserror::Error;
 
+/// Exit status of a program stopped by a runtime error, the same on every backend.
+pub const RUNTIME_ERROR_EXIT_CODE: i32 = 101;
+
+/// Exit status of `pulse` when the program doesn't compile or the command fails.
+pub const FAILURE_EXIT_CODE: i32 = 1;
+
 #[derive(Error, Debug)]
 pub enum PulseError {
     #[error("Couldn't find pulse.toml in current directory or any parent directory")]
@@ -13,6 +19,10 @@ pub enum PulseError {
     InvalidProjectStructure,
     #[error("Found both src/main.pulse and src/lib.pulse. Only one is allowed")]
     MultipleEntryPoints,
+    #[error("No 'main' function found in src/main.pulse")]
+    MissingEntryPoint,
+    #[error("Library projects can't be run. Use src/main.pulse with a 'main' function")]
+    CannotRunLibrary,
     #[error("Invalid token: {0}")]
     InvalidToken(String, TextSpan),
     #[error("Expected {0}.")]
@@ -23,4 +33,20 @@ pub enum PulseError {
     SemanticError(String, TextSpan),
     #[error("Semantic error: {0}")]
     ResolverError(String),
+    #[error("{0}")]
+    RuntimeError(String, TextSpan),
+    #[error("Codegen error: {0}")]
+    CodegenError(String),
+    #[error("Linking failed: {0}")]
+    LinkerError(String),
+    #[error("Invalid pulse.toml: {0}")]
+    ManifestError(String),
+    /// The chain of modules importing each other, then the `use` statements forming it.
+    #[error("Circular import: {0}")]
+    CircularImport(String, Vec<PulseError>),
+    /// An error in one of the project's files, with the file's name and source.
+    #[error("{2}")]
+    InModule(String, String, Box<PulseError>),
+    #[error("Aborting due to {} previous error{}", .0.len(), if .0.len() == 1 { "" } else { "s" })]
+    Errors(Vec<PulseError>),
 }
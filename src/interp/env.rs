@@ -0,0 +1,80 @@
+use crate::interp::value::Value;
+use std::collections::HashMap;
+
+/// Runtime counterpart of `resolver::Scope`, holding the values bound in one block.
+#[derive(Debug, Default)]
+pub struct Environment {
+    values: HashMap<String, Value>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Environment {
+            values: HashMap::new(),
+        }
+    }
+
+    pub fn define(&mut self, name: String, value: Value) {
+        self.values.insert(name, value);
+    }
+
+    fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut Value> {
+        self.values.get_mut(name)
+    }
+}
+
+/// The environments of a single function call, innermost last.
+#[derive(Debug, Default)]
+pub struct Frame {
+    scopes: Vec<Environment>,
+}
+
+impl Frame {
+    pub fn new() -> Self {
+        Frame {
+            scopes: vec![Environment::new()],
+        }
+    }
+
+    pub fn begin_scope(&mut self) {
+        self.scopes.push(Environment::new());
+    }
+
+    pub fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    pub fn define(&mut self, name: String, value: Value) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.define(name, value);
+        }
+    }
+
+    /// Copies of every value in scope, what a closure created here captures.
+    pub fn visible(&self) -> Vec<(String, Value)> {
+        let mut visible: HashMap<&String, &Value> = HashMap::new();
+        for scope in &self.scopes {
+            visible.extend(&scope.values);
+        }
+
+        visible
+            .into_iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Value> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name))
+    }
+}
@@ -0,0 +1,1176 @@
+use crate::{
+    ast::ast::*,
+    error::PulseError::{MissingEntryPoint, RuntimeError},
+    interp::{env::Frame, value::Value},
+    lexer::token::{Token, TokenKind},
+    project::EntryPoint,
+    stdlib::StdFn,
+};
+use anyhow::Result;
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    rc::Rc,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+pub mod env;
+pub mod value;
+
+// Every Pulse call nests several Rust frames, so the interpreter runs on its own
+// thread whose stack comfortably fits `MAX_CALL_DEPTH` calls
+const MAX_CALL_DEPTH: usize = 10_000;
+const STACK_SIZE: usize = 512 * 1024 * 1024;
+
+type NativeFn = fn(&[Value]) -> Value;
+
+#[derive(Debug, Clone)]
+enum Callable {
+    Fn(Arc<Fn>),
+    Native { arity: usize, function: NativeFn },
+}
+
+/// Result of executing a statement: carry on with the next one, or unwind to the
+/// caller or to the (optionally labeled) enclosing loop.
+#[derive(Debug)]
+enum Flow {
+    Next,
+    Return(Value),
+    Break(Option<String>),
+    Continue(Option<String>),
+}
+
+/// Carries a `Flow` out of the expression it happened in, for a `return` or `break`
+/// inside a `match` arm. The enclosing block turns it back into a `Flow`.
+#[derive(Debug)]
+struct Escape(Flow);
+
+impl std::fmt::Display for Escape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} escaped its function", self.0)
+    }
+}
+
+impl std::error::Error for Escape {}
+
+/// Tree-walking interpreter evaluating a resolved `Ast` without LLVM.
+#[derive(Debug, Default)]
+pub struct Interpreter {
+    functions: HashMap<String, Callable>,
+    structs: HashMap<String, Rc<Struct>>,
+    /// The enum each variant constructor belongs to.
+    variants: HashMap<String, String>,
+    frames: Vec<Frame>,
+}
+
+/// Loads `ast` and runs its entry point on a dedicated interpreter thread.
+pub fn run(ast: Ast, entry_point: EntryPoint, arg: i64) -> Result<i64> {
+    let handle = thread::Builder::new()
+        .name("interp".to_string())
+        .stack_size(STACK_SIZE)
+        .spawn(move || {
+            let mut interpreter = Interpreter::new();
+            interpreter.load(&ast)?;
+            interpreter.run_main(entry_point, arg)
+        })?;
+
+    match handle.join() {
+        Ok(result) => result,
+        Err(panic) => std::panic::resume_unwind(panic),
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Interpreter {
+            functions: HashMap::new(),
+            structs: HashMap::new(),
+            variants: HashMap::new(),
+            frames: vec![],
+        }
+    }
+
+    /// Registers every top-level function, type and `use` so calls don't depend on
+    /// declaration order.
+    pub fn load(&mut self, ast: &Ast) -> Result<()> {
+        for stmt in &ast.stmts {
+            match stmt {
+                Stmt::Fn(fn_stmt) => {
+                    self.functions.insert(
+                        fn_stmt.name.clone(),
+                        Callable::Fn(Arc::new(fn_stmt.clone())),
+                    );
+                }
+                Stmt::Struct(struct_stmt) => {
+                    self.structs
+                        .insert(struct_stmt.name.literal(), Rc::new(struct_stmt.clone()));
+                }
+                Stmt::Enum(enum_stmt) => {
+                    for variant in &enum_stmt.variants {
+                        self.variants
+                            .insert(variant.ident.literal(), enum_stmt.name.literal());
+                    }
+                }
+                Stmt::Impl(impl_stmt) => {
+                    for method in &impl_stmt.methods {
+                        self.functions.insert(
+                            qualified_name(&impl_stmt.target.literal(), &method.name),
+                            Callable::Fn(Arc::new(method.clone())),
+                        );
+                    }
+                }
+                Stmt::Use(use_stmt) => self.load_use(use_stmt)?,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Calls `main`, passing `arg` when it takes one, and returns the exit code.
+    pub fn run_main(&mut self, entry_point: EntryPoint, arg: i64) -> Result<i64> {
+        let main = match self.functions.get("main") {
+            Some(Callable::Fn(main)) => main.clone(),
+            _ => return Err(MissingEntryPoint.into()),
+        };
+
+        let args = if entry_point.takes_arg {
+            vec![Value::Int(arg)]
+        } else {
+            vec![]
+        };
+
+        match self.call_fn(&main, vec![], args)? {
+            Value::Int(code) if entry_point.returns_int => Ok(code),
+            _ => Ok(0),
+        }
+    }
+
+    fn load_use(&mut self, use_stmt: &Use) -> Result<()> {
+        // Functions of the project's own modules are loaded along with the rest
+        if use_stmt.is_relative() {
+            return Ok(());
+        }
+
+        let module = use_stmt.module();
+        for item in &use_stmt.items {
+            let name = item.literal();
+            let Some(std_fn) = StdFn::find(&module, &name) else {
+                return Err(RuntimeError(
+                    format!("Cannot import '{}' from '{}'", name, module),
+                    item.span.clone(),
+                )
+                .into());
+            };
+            let function: NativeFn = match std_fn {
+                StdFn::Println => |args| {
+                    println!("{}", args[0]);
+                    Value::Void
+                },
+                StdFn::Eprintln => |args| {
+                    eprintln!("{}", args[0]);
+                    Value::Void
+                },
+                StdFn::Print => |args| {
+                    print!("{}", args[0]);
+                    io::stdout().flush().expect("Error flushing stdout");
+                    Value::Void
+                },
+                StdFn::ReadLine => |_| {
+                    let mut line = String::new();
+                    io::stdin()
+                        .read_line(&mut line)
+                        .expect("Error reading stdin");
+                    let line = line.strip_suffix('\n').unwrap_or(&line);
+                    Value::String(line.strip_suffix('\r').unwrap_or(line).to_string())
+                },
+            };
+            self.functions.insert(
+                name,
+                Callable::Native {
+                    arity: std_fn.params().len(),
+                    function,
+                },
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl Interpreter {
+    fn frame(&mut self) -> &mut Frame {
+        self.frames.last_mut().expect("No active call frame")
+    }
+
+    fn call(&mut self, call: &CallExpr) -> Result<Value> {
+        let mut args = vec![];
+        let (name, callee, captures) = match call.callee.as_ref() {
+            Expr::Variable(var) if var.ident == "len" => return self.len(call),
+            Expr::Variable(var) => {
+                if let Some(name) = self.variants.get(&var.ident).cloned() {
+                    let mut values = vec![];
+                    for arg in &call.args {
+                        values.push(self.eval(arg)?);
+                    }
+
+                    return Ok(Value::Enum {
+                        name,
+                        variant: var.ident.clone(),
+                        values,
+                    });
+                }
+
+                // Locals holding functions shadow the top-level ones
+                match self.frame().get(&var.ident).cloned() {
+                    Some(value) => {
+                        let (callee, captures) = function_value(value, &call.callee)?;
+                        (var.ident.clone(), callee, captures)
+                    }
+                    None => (var.ident.clone(), self.function(&var.ident, call)?, vec![]),
+                }
+            }
+            // `p.length()` calls `Point::length` with `p` as its first argument, unless
+            // there's no such method and `length` is a field holding a function
+            Expr::Member(member) => {
+                let receiver = self.eval(&member.object)?;
+                let type_name = match &receiver {
+                    Value::Struct { name, .. } | Value::Enum { name, .. } => name.clone(),
+                    other => {
+                        return Err(RuntimeError(
+                            format!(
+                                "No method '{}' on type {}",
+                                member.field.literal(),
+                                other.type_name()
+                            ),
+                            member.field.span.clone(),
+                        )
+                        .into())
+                    }
+                };
+
+                let name = qualified_name(&type_name, &member.field.literal());
+                if self.functions.contains_key(&name) {
+                    args.push(receiver);
+                    (name.clone(), self.function(&name, call)?, vec![])
+                } else {
+                    let value = field(receiver, &member.field)?;
+                    let (callee, captures) = function_value(value, &call.callee)?;
+                    (member.field.literal(), callee, captures)
+                }
+            }
+            Expr::Path(path) => {
+                let name = qualified_name(&path.type_name.literal(), &path.name.literal());
+                (name.clone(), self.function(&name, call)?, vec![])
+            }
+            other => {
+                let value = self.eval(other)?;
+                let (callee, captures) = function_value(value, other)?;
+                (other.span().literal, callee, captures)
+            }
+        };
+
+        let arity = match &callee {
+            Callable::Fn(fn_stmt) => fn_stmt.params.len() + fn_stmt.receiver.iter().count(),
+            Callable::Native { arity, .. } => *arity,
+        };
+        if arity != args.len() + call.args.len() {
+            return Err(RuntimeError(
+                format!(
+                    "Function '{}' expects {} arguments, found {}",
+                    name,
+                    arity,
+                    args.len() + call.args.len()
+                ),
+                call.token.span.clone(),
+            )
+            .into());
+        }
+
+        for arg in &call.args {
+            args.push(self.eval(arg)?);
+        }
+
+        match callee {
+            Callable::Fn(fn_stmt) => {
+                if self.frames.len() >= MAX_CALL_DEPTH {
+                    return Err(RuntimeError(
+                        format!("Stack overflow while calling '{}'", name),
+                        call.token.span.clone(),
+                    )
+                    .into());
+                }
+                self.call_fn(&fn_stmt, captures, args)
+            }
+            Callable::Native { function, .. } => Ok(function(&args)),
+        }
+    }
+
+    fn function(&self, name: &str, call: &CallExpr) -> Result<Callable> {
+        self.functions.get(name).cloned().ok_or_else(|| {
+            RuntimeError(
+                format!("Undefined function '{}'", name),
+                call.token.span.clone(),
+            )
+            .into()
+        })
+    }
+
+    /// The builtin `len`, counting the elements of an array or the bytes of a string.
+    fn len(&mut self, call: &CallExpr) -> Result<Value> {
+        let [arg] = call.args.as_slice() else {
+            return Err(RuntimeError(
+                format!(
+                    "Function 'len' expects 1 argument, found {}",
+                    call.args.len()
+                ),
+                call.token.span.clone(),
+            )
+            .into());
+        };
+
+        match self.eval(arg)? {
+            Value::Array(elements) => Ok(Value::Int(
+                elements.lock().expect("Array lock poisoned").len() as i64,
+            )),
+            Value::String(s) => Ok(Value::Int(s.len() as i64)),
+            other => Err(RuntimeError(
+                format!(
+                    "Function 'len' expects an array or a string, found {}",
+                    other.type_name()
+                ),
+                arg.span(),
+            )
+            .into()),
+        }
+    }
+
+    /// Calls `fn_stmt` in a new frame holding the variables a closure captured, then its
+    /// arguments.
+    fn call_fn(
+        &mut self,
+        fn_stmt: &Fn,
+        captures: Vec<(String, Value)>,
+        args: Vec<Value>,
+    ) -> Result<Value> {
+        let mut frame = Frame::new();
+        for (name, value) in captures {
+            frame.define(name, value);
+        }
+        let mut args = args.into_iter();
+        if let Some(receiver) = &fn_stmt.receiver {
+            let value = args.next().expect("Method called without a receiver");
+            frame.define(receiver.literal(), value);
+        }
+        for (param, value) in fn_stmt.params.iter().zip(args) {
+            frame.define(param.ident.literal(), value);
+        }
+
+        self.frames.push(frame);
+        let flow = self.exec_block(&fn_stmt.body);
+        self.frames.pop();
+
+        Ok(match flow? {
+            Flow::Return(value) => value,
+            _ => match &fn_stmt.return_type {
+                Some(ret) => Value::zero(&ret.type_name),
+                None => Value::Void,
+            },
+        })
+    }
+}
+
+impl Interpreter {
+    fn exec_block(&mut self, block: &Block) -> Result<Flow> {
+        self.frame().begin_scope();
+
+        let mut flow = Ok(Flow::Next);
+        for stmt in &block.stmts {
+            flow = self
+                .exec_stmt(stmt)
+                .or_else(|err| match err.downcast::<Escape>() {
+                    Ok(Escape(flow)) => Ok(flow),
+                    Err(err) => Err(err),
+                });
+            if !matches!(flow, Ok(Flow::Next)) {
+                break;
+            }
+        }
+
+        self.frame().end_scope();
+
+        flow
+    }
+
+    fn exec_stmt(&mut self, stmt: &Stmt) -> Result<Flow> {
+        match stmt {
+            Stmt::Expr(expr) => {
+                self.eval(expr)?;
+            }
+            Stmt::Let(let_stmt) => {
+                let value = self.eval(&let_stmt.initializer)?;
+                self.frame().define(let_stmt.ident.literal(), value);
+            }
+            Stmt::If(if_stmt) => return self.exec_if(if_stmt),
+            Stmt::Return(ret_stmt) => {
+                let value = match &ret_stmt.expr {
+                    Some(expr) => self.eval(expr)?,
+                    None => Value::Void,
+                };
+                return Ok(Flow::Return(value));
+            }
+            Stmt::Block(block) => return self.exec_block(block),
+            Stmt::While(while_stmt) => {
+                while self.eval_condition(&while_stmt.condition)? {
+                    if let Some(flow) = self.exec_iteration(&while_stmt.label, &while_stmt.body)? {
+                        return Ok(flow);
+                    }
+                }
+            }
+            Stmt::Loop(loop_stmt) => loop {
+                if let Some(flow) = self.exec_iteration(&loop_stmt.label, &loop_stmt.body)? {
+                    return Ok(flow);
+                }
+            },
+            Stmt::For(for_stmt) => return self.exec_for(for_stmt),
+            Stmt::Break(break_stmt) => {
+                return Ok(Flow::Break(
+                    break_stmt.label.as_ref().map(|label| label.literal()),
+                ))
+            }
+            Stmt::Continue(continue_stmt) => {
+                return Ok(Flow::Continue(
+                    continue_stmt.label.as_ref().map(|label| label.literal()),
+                ))
+            }
+            Stmt::Fn(fn_stmt) => {
+                return Err(RuntimeError(
+                    format!("Nested function '{}' is not supported", fn_stmt.name),
+                    fn_stmt.fn_token.span.clone(),
+                )
+                .into())
+            }
+            Stmt::Struct(struct_stmt) => {
+                return Err(RuntimeError(
+                    format!(
+                        "Nested struct '{}' is not supported",
+                        struct_stmt.name.literal()
+                    ),
+                    struct_stmt.name.span.clone(),
+                )
+                .into())
+            }
+            Stmt::Enum(enum_stmt) => {
+                return Err(RuntimeError(
+                    format!(
+                        "Nested enum '{}' is not supported",
+                        enum_stmt.name.literal()
+                    ),
+                    enum_stmt.name.span.clone(),
+                )
+                .into())
+            }
+            Stmt::Impl(impl_stmt) => {
+                return Err(RuntimeError(
+                    format!(
+                        "Nested impl for '{}' is not supported",
+                        impl_stmt.target.literal()
+                    ),
+                    impl_stmt.target.span.clone(),
+                )
+                .into())
+            }
+            Stmt::Interface(interface) => {
+                return Err(RuntimeError(
+                    format!(
+                        "Nested interface '{}' is not supported",
+                        interface.name.literal()
+                    ),
+                    interface.name.span.clone(),
+                )
+                .into())
+            }
+            Stmt::Use(use_stmt) => {
+                return Err(RuntimeError(
+                    "'use' is only allowed at the top level".to_string(),
+                    use_stmt.use_token.span.clone(),
+                )
+                .into())
+            }
+        }
+
+        Ok(Flow::Next)
+    }
+
+    fn exec_for(&mut self, for_stmt: &For) -> Result<Flow> {
+        let (start, end, inclusive) = match self.eval(&for_stmt.iterable)? {
+            Value::Range {
+                start,
+                end,
+                inclusive,
+            } => (start, end, inclusive),
+            other => {
+                return Err(RuntimeError(
+                    format!("Cannot iterate over a value of type {}", other.type_name()),
+                    for_stmt.iterable.span(),
+                )
+                .into())
+            }
+        };
+
+        let mut i = start;
+        while i < end || (inclusive && i == end) {
+            self.frame().begin_scope();
+            self.frame().define(for_stmt.ident.literal(), Value::Int(i));
+            let flow = self.exec_iteration(&for_stmt.label, &for_stmt.body);
+            self.frame().end_scope();
+
+            if let Some(flow) = flow? {
+                return Ok(flow);
+            }
+            // `0..=i64::MAX` must stop instead of wrapping around
+            match i.checked_add(1) {
+                Some(next) => i = next,
+                None => break,
+            }
+        }
+
+        Ok(Flow::Next)
+    }
+
+    /// Runs one pass over a loop body. `Some(flow)` means the loop is finished and
+    /// `flow` continues in the enclosing statement.
+    fn exec_iteration(&mut self, label: &Option<Token>, body: &Block) -> Result<Option<Flow>> {
+        let targets_this_loop = |target: &Option<String>| match target {
+            Some(target) => label
+                .as_ref()
+                .is_some_and(|label| label.literal() == *target),
+            None => true,
+        };
+
+        Ok(match self.exec_block(body)? {
+            Flow::Next => None,
+            Flow::Continue(target) if targets_this_loop(&target) => None,
+            Flow::Break(target) if targets_this_loop(&target) => Some(Flow::Next),
+            flow => Some(flow),
+        })
+    }
+
+    fn exec_if(&mut self, if_stmt: &If) -> Result<Flow> {
+        if self.eval_condition(&if_stmt.condition)? {
+            return self.exec_block(&if_stmt.then_block);
+        }
+
+        for else_if in &if_stmt.else_ifs {
+            if self.eval_condition(&else_if.condition)? {
+                return self.exec_block(&else_if.block);
+            }
+        }
+
+        match &if_stmt.else_block {
+            Some(else_block) => self.exec_block(&else_block.block),
+            None => Ok(Flow::Next),
+        }
+    }
+
+    fn eval_condition(&mut self, condition: &Expr) -> Result<bool> {
+        match self.eval(condition)? {
+            Value::Bool(value) => Ok(value),
+            other => Err(RuntimeError(
+                format!(
+                    "Condition must be of type bool, found {}",
+                    other.type_name()
+                ),
+                condition.span(),
+            )
+            .into()),
+        }
+    }
+}
+
+impl Interpreter {
+    fn eval(&mut self, expr: &Expr) -> Result<Value> {
+        match expr {
+            Expr::Literal(lit) => match &lit.value {
+                LiteralType::Int(i) => Ok(Value::Int(*i)),
+                LiteralType::Float(f) => Ok(Value::Float(*f)),
+                LiteralType::Bool(b) => Ok(Value::Bool(*b)),
+                LiteralType::String(s) => Ok(Value::String(s.clone())),
+                LiteralType::Null => Err(RuntimeError(
+                    "'null' is not supported".to_string(),
+                    lit.token.span.clone(),
+                )
+                .into()),
+            },
+            Expr::Variable(var) => {
+                if let Some(value) = self.frame().get(&var.ident) {
+                    return Ok(value.clone());
+                }
+
+                match self.variants.get(&var.ident) {
+                    Some(name) => Ok(Value::Enum {
+                        name: name.clone(),
+                        variant: var.ident.clone(),
+                        values: vec![],
+                    }),
+                    None => match self.functions.get(&var.ident) {
+                        Some(Callable::Fn(function)) => Ok(Value::Function {
+                            function: function.clone(),
+                            captures: vec![],
+                        }),
+                        Some(Callable::Native { .. }) => Err(RuntimeError(
+                            format!("Function '{}' cannot be used as a value", var.ident),
+                            var.token.span.clone(),
+                        )
+                        .into()),
+                        None => Err(RuntimeError(
+                            format!("Undefined variable '{}'", var.ident),
+                            var.token.span.clone(),
+                        )
+                        .into()),
+                    },
+                }
+            }
+            Expr::Binary(bin) => self.eval_binary(bin),
+            Expr::Unary(un) => self.eval_unary(un),
+            Expr::Logical(logical) => {
+                let operator = match logical.operator {
+                    LogicalOp::And => BinOpKind::And,
+                    LogicalOp::Or => BinOpKind::Or,
+                };
+                self.eval_short_circuit(&logical.left, operator, &logical.right)
+            }
+            Expr::Parenthesized(paren) => self.eval(&paren.expr),
+            Expr::Call(call) => self.call(call),
+            Expr::Range(range) => match (self.eval(&range.start)?, self.eval(&range.end)?) {
+                (Value::Int(start), Value::Int(end)) => Ok(Value::Range {
+                    start,
+                    end,
+                    inclusive: range.inclusive,
+                }),
+                (start, end) => Err(RuntimeError(
+                    format!(
+                        "Range bounds must be of type int, found {} and {}",
+                        start.type_name(),
+                        end.type_name()
+                    ),
+                    expr.span(),
+                )
+                .into()),
+            },
+            Expr::Assign(assign) => {
+                let value = self.eval(&assign.value)?;
+                self.assign(&assign.target, value)
+            }
+            Expr::StructLiteral(literal) => self.eval_struct_literal(literal),
+            Expr::Match(match_expr) => self.eval_match(match_expr),
+            Expr::ArrayLiteral(literal) => self.eval_array_literal(literal),
+            Expr::Index(index) => {
+                let (elements, i) = self.eval_element(index)?;
+                let element = elements.lock().expect("Array lock poisoned")[i].clone();
+
+                Ok(element)
+            }
+            Expr::Member(member) => {
+                let object = self.eval(&member.object)?;
+                field(object, &member.field)
+            }
+            Expr::Closure(closure) => Ok(Value::Function {
+                function: Arc::new(closure.function.as_ref().clone()),
+                captures: self.frame().visible(),
+            }),
+            Expr::Path(path) => Err(RuntimeError(
+                format!(
+                    "Function '{}' has to be called",
+                    qualified_name(&path.type_name.literal(), &path.name.literal())
+                ),
+                expr.span(),
+            )
+            .into()),
+        }
+    }
+
+    fn eval_match(&mut self, match_expr: &Match) -> Result<Value> {
+        let value = self.eval(&match_expr.scrutinee)?;
+
+        for arm in &match_expr.arms {
+            let Some(bindings) = match_pattern(&arm.pattern, &value) else {
+                continue;
+            };
+
+            self.frame().begin_scope();
+            for (name, value) in bindings {
+                self.frame().define(name, value);
+            }
+            let result = match &arm.body {
+                ArmBody::Expr(expr) => self.eval(expr),
+                ArmBody::Block(block) => match self.exec_block(block) {
+                    Ok(Flow::Next) => Ok(Value::Void),
+                    Ok(flow) => Err(Escape(flow).into()),
+                    Err(err) => Err(err),
+                },
+            };
+            self.frame().end_scope();
+
+            return result;
+        }
+
+        Err(RuntimeError(
+            format!("No match arm matches the value {}", value),
+            match_expr.match_token.span.clone(),
+        )
+        .into())
+    }
+
+    fn eval_struct_literal(&mut self, literal: &StructLiteral) -> Result<Value> {
+        let name = literal.name.literal();
+        let decl = self.structs.get(&name).cloned().ok_or_else(|| {
+            RuntimeError(
+                format!("Undefined struct '{}'", name),
+                literal.name.span.clone(),
+            )
+        })?;
+
+        // Evaluated in source order, then laid out in declaration order
+        let mut values = HashMap::new();
+        for init in &literal.fields {
+            values.insert(init.ident.literal(), self.eval(&init.value)?);
+        }
+
+        let mut fields = vec![];
+        for field in &decl.fields {
+            let field_name = field.ident.literal();
+            let value = values.remove(&field_name).ok_or_else(|| {
+                RuntimeError(
+                    format!(
+                        "Missing field '{}' in initializer of '{}'",
+                        field_name, name
+                    ),
+                    literal.name.span.clone(),
+                )
+            })?;
+            fields.push((field_name, value));
+        }
+
+        Ok(Value::Struct { name, fields })
+    }
+
+    fn eval_array_literal(&mut self, literal: &ArrayLiteral) -> Result<Value> {
+        let mut elements = vec![];
+        for element in &literal.elements {
+            elements.push(self.eval(element)?);
+        }
+
+        if let Some(count) = &literal.repeat {
+            let TokenKind::Integer(count) = count.kind else {
+                unreachable!("The parser only accepts integer lengths")
+            };
+            let value = elements.pop().unwrap_or(Value::Void);
+            elements = vec![value; count as usize];
+        }
+
+        Ok(Value::new_array(elements))
+    }
+
+    /// Evaluates `object[index]` down to the array and a checked position in it.
+    fn eval_element(&mut self, index: &Index) -> Result<(Arc<Mutex<Vec<Value>>>, usize)> {
+        let elements = match self.eval(&index.object)? {
+            Value::Array(elements) => elements,
+            other => {
+                return Err(RuntimeError(
+                    format!("Cannot index into a value of type {}", other.type_name()),
+                    index.object.span(),
+                )
+                .into())
+            }
+        };
+        let i = match self.eval(&index.index)? {
+            Value::Int(i) => i,
+            other => {
+                return Err(RuntimeError(
+                    format!(
+                        "Array index must be of type int, found {}",
+                        other.type_name()
+                    ),
+                    index.index.span(),
+                )
+                .into())
+            }
+        };
+
+        let len = elements.lock().expect("Array lock poisoned").len();
+        match usize::try_from(i) {
+            Ok(i) if i < len => Ok((elements, i)),
+            _ => Err(RuntimeError(
+                format!("Index {} out of bounds for array of length {}", i, len),
+                index.index.span(),
+            )
+            .into()),
+        }
+    }
+
+    /// Runs `update` on the storage an assignment target refers to: a variable, or a
+    /// field or element nested in one.
+    fn update_place(
+        &mut self,
+        target: &Expr,
+        update: &mut dyn FnMut(&mut Value) -> Result<()>,
+    ) -> Result<()> {
+        match target {
+            Expr::Variable(var) => match self.frame().get_mut(&var.ident) {
+                Some(slot) => update(slot),
+                None => Err(RuntimeError(
+                    format!("Undefined variable '{}'", var.ident),
+                    var.token.span.clone(),
+                )
+                .into()),
+            },
+            Expr::Member(member) => {
+                let field = member.field.literal();
+                let span = member.field.span.clone();
+                self.update_place(&member.object, &mut |object| match object {
+                    Value::Struct { fields, .. } => {
+                        match fields.iter_mut().find(|(name, _)| *name == field) {
+                            Some((_, slot)) => update(slot),
+                            None => Err(RuntimeError(
+                                format!("No field '{}' on this struct", field),
+                                span.clone(),
+                            )
+                            .into()),
+                        }
+                    }
+                    other => Err(RuntimeError(
+                        format!("Type {} has no field '{}'", other.type_name(), field),
+                        span.clone(),
+                    )
+                    .into()),
+                })
+            }
+            // Arrays are shared, so writing through the evaluated array is enough
+            Expr::Index(index) => {
+                let (array, i) = self.eval_element(index)?;
+                let mut elements = array.lock().expect("Array lock poisoned");
+                update(&mut elements[i])
+            }
+            _ => Err(RuntimeError(
+                format!("Cannot assign to '{}'", target.span().literal),
+                target.span(),
+            )
+            .into()),
+        }
+    }
+
+    fn assign(&mut self, target: &Expr, value: Value) -> Result<Value> {
+        self.update_place(target, &mut |slot| {
+            if slot.type_name() != value.type_name() {
+                return Err(RuntimeError(
+                    format!("Type mismatch in assignment to '{}'", target.span().literal),
+                    target.span(),
+                )
+                .into());
+            }
+            *slot = value.clone();
+
+            Ok(())
+        })?;
+
+        Ok(value)
+    }
+
+    fn eval_unary(&mut self, un: &Unary) -> Result<Value> {
+        Ok(match (un.operator.kind, self.eval(&un.expr)?) {
+            (UnOpKind::Minus, Value::Int(i)) => Value::Int(i.wrapping_neg()),
+            (UnOpKind::Minus, Value::Float(f)) => Value::Float(-f),
+            (UnOpKind::BitwiseNot, Value::Int(i)) => Value::Int(!i),
+            (UnOpKind::Not, Value::Bool(b)) => Value::Bool(!b),
+            (kind, value) => {
+                return Err(RuntimeError(
+                    format!(
+                        "Unary operator '{}' not supported for type {}",
+                        kind,
+                        value.type_name()
+                    ),
+                    un.operator.token.span.clone(),
+                )
+                .into())
+            }
+        })
+    }
+
+    fn eval_binary(&mut self, bin: &Binary) -> Result<Value> {
+        match bin.operator {
+            BinOpKind::And | BinOpKind::Or => {
+                return self.eval_short_circuit(&bin.left, bin.operator, &bin.right)
+            }
+            BinOpKind::PlusEquals | BinOpKind::MinusEquals => {
+                return self.eval_compound_assign(bin)
+            }
+            _ => {}
+        }
+
+        let left = self.eval(&bin.left)?;
+        let right = self.eval(&bin.right)?;
+
+        binary_op(bin.operator, left, right)
+            .map_err(|message| RuntimeError(message, bin.span()).into())
+    }
+
+    fn eval_short_circuit(
+        &mut self,
+        left: &Expr,
+        operator: BinOpKind,
+        right: &Expr,
+    ) -> Result<Value> {
+        let left = self.eval_condition(left)?;
+
+        let value = match operator {
+            BinOpKind::And => left && self.eval_condition(right)?,
+            _ => left || self.eval_condition(right)?,
+        };
+
+        Ok(Value::Bool(value))
+    }
+
+    fn eval_compound_assign(&mut self, bin: &Binary) -> Result<Value> {
+        if !matches!(
+            bin.left.as_ref(),
+            Expr::Variable(_) | Expr::Member(_) | Expr::Index(_)
+        ) {
+            return Err(RuntimeError(
+                "Left side of a compound assignment must be a variable, a field or an element"
+                    .to_string(),
+                bin.span(),
+            )
+            .into());
+        }
+
+        let current = self.eval(&bin.left)?;
+        let right = self.eval(&bin.right)?;
+        let operator = match bin.operator {
+            BinOpKind::PlusEquals => BinOpKind::Plus,
+            _ => BinOpKind::Minus,
+        };
+
+        let value = binary_op(operator, current, right)
+            .map_err(|message| RuntimeError(message, bin.span()))?;
+        self.assign(&bin.left, value)
+    }
+}
+
+/// The variables `pattern` binds when it matches `value`.
+fn match_pattern(pattern: &Pattern, value: &Value) -> Option<Vec<(String, Value)>> {
+    match (pattern, value) {
+        (Pattern::Wildcard(_), _) => Some(vec![]),
+        (Pattern::Literal(literal), value) => {
+            let matches = match (&literal.value, value) {
+                (LiteralType::Int(expected), Value::Int(int)) => expected == int,
+                (LiteralType::Bool(expected), Value::Bool(b)) => expected == b,
+                (LiteralType::String(expected), Value::String(s)) => expected == s,
+                _ => false,
+            };
+
+            matches.then(Vec::new)
+        }
+        (
+            Pattern::Variant { name, bindings },
+            Value::Enum {
+                variant, values, ..
+            },
+        ) if name.literal() == *variant => Some(
+            bindings
+                .iter()
+                .zip(values)
+                .filter(|(binding, _)| binding.literal() != "_")
+                .map(|(binding, value)| (binding.literal(), value.clone()))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Applies a non short-circuiting operator, with the same wrapping integer
+/// semantics as the LLVM backend. Errors are returned as plain messages for the
+/// caller to attach a span to.
+fn binary_op(operator: BinOpKind, left: Value, right: Value) -> Result<Value, String> {
+    let unsupported = |value: &Value| {
+        format!(
+            "Binary operator '{:?}' not supported for type {}",
+            operator,
+            value.type_name()
+        )
+    };
+
+    let value = match (&left, &right) {
+        (Value::Int(l), Value::Int(r)) => {
+            let (l, r) = (*l, *r);
+            match operator {
+                BinOpKind::Plus => Value::Int(l.wrapping_add(r)),
+                BinOpKind::Minus => Value::Int(l.wrapping_sub(r)),
+                BinOpKind::Multiply => Value::Int(l.wrapping_mul(r)),
+                BinOpKind::Divide | BinOpKind::Modulo if r == 0 => {
+                    return Err("Division by zero".to_string())
+                }
+                BinOpKind::Divide => Value::Int(l.wrapping_div(r)),
+                BinOpKind::Modulo => Value::Int(l.wrapping_rem(r)),
+                BinOpKind::Power => Value::Int(int_pow(l, r)),
+                BinOpKind::BitwiseAnd => Value::Int(l & r),
+                BinOpKind::BitwiseOr => Value::Int(l | r),
+                BinOpKind::BitwiseXor => Value::Int(l ^ r),
+                _ => compare(operator, &l, &r).ok_or_else(|| unsupported(&left))?,
+            }
+        }
+        (Value::Float(l), Value::Float(r)) => {
+            let (l, r) = (*l, *r);
+            match operator {
+                BinOpKind::Plus => Value::Float(l + r),
+                BinOpKind::Minus => Value::Float(l - r),
+                BinOpKind::Multiply => Value::Float(l * r),
+                BinOpKind::Divide => Value::Float(l / r),
+                BinOpKind::Modulo => Value::Float(l % r),
+                BinOpKind::Power => Value::Float(l.powf(r)),
+                _ => compare(operator, &l, &r).ok_or_else(|| unsupported(&left))?,
+            }
+        }
+        (Value::Bool(l), Value::Bool(r)) => match operator {
+            BinOpKind::BitwiseAnd => Value::Bool(l & r),
+            BinOpKind::BitwiseOr => Value::Bool(l | r),
+            BinOpKind::BitwiseXor => Value::Bool(l ^ r),
+            _ => compare(operator, l, r).ok_or_else(|| unsupported(&left))?,
+        },
+        (Value::String(l), Value::String(r)) => {
+            compare(operator, l, r).ok_or_else(|| unsupported(&left))?
+        }
+        _ => {
+            return Err(format!(
+                "Type mismatch in binary operation '{:?}': {} and {}",
+                operator,
+                left.type_name(),
+                right.type_name()
+            ))
+        }
+    };
+
+    Ok(value)
+}
+
+fn compare<T: PartialOrd>(operator: BinOpKind, l: &T, r: &T) -> Option<Value> {
+    let result = match operator {
+        BinOpKind::Equals | BinOpKind::EqualsEquals => l == r,
+        BinOpKind::NotEquals | BinOpKind::BangEquals => l != r,
+        BinOpKind::LessThan => l < r,
+        BinOpKind::LessThanOrEqual => l <= r,
+        BinOpKind::GreaterThan => l > r,
+        BinOpKind::GreaterThanOrEqual => l >= r,
+        _ => return None,
+    };
+
+    Some(Value::Bool(result))
+}
+
+/// Exponentiation by squaring, negative exponents yield 0 like `pulse.ipow`.
+fn int_pow(base: i64, exp: i64) -> i64 {
+    if exp < 0 {
+        return 0;
+    }
+
+    let (mut result, mut acc, mut n) = (1i64, base, exp);
+    while n > 0 {
+        if n & 1 != 0 {
+            result = result.wrapping_mul(acc);
+        }
+        acc = acc.wrapping_mul(acc);
+        n >>= 1;
+    }
+
+    result
+}
+
+/// The field `name` of a struct value.
+fn field(object: Value, name: &Token) -> Result<Value> {
+    let field = name.literal();
+    match object {
+        Value::Struct { fields, .. } => fields
+            .into_iter()
+            .find(|(name, _)| *name == field)
+            .map(|(_, value)| value)
+            .ok_or_else(|| {
+                RuntimeError(
+                    format!("No field '{}' on this struct", field),
+                    name.span.clone(),
+                )
+                .into()
+            }),
+        other => Err(RuntimeError(
+            format!("Type {} has no field '{}'", other.type_name(), field),
+            name.span.clone(),
+        )
+        .into()),
+    }
+}
+
+/// The function a `Value::Function` calls and the variables it captured.
+fn function_value(value: Value, callee: &Expr) -> Result<(Callable, Vec<(String, Value)>)> {
+    match value {
+        Value::Function { function, captures } => Ok((Callable::Fn(function), captures)),
+        other => Err(RuntimeError(
+            format!("Cannot call a value of type {}", other.type_name()),
+            callee.span(),
+        )
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::parser::Parser, lexer::Lexer};
+
+    /// The exit code of the program in `source`, called with `arg`.
+    fn run_source(source: &str, arg: i64) -> Result<i64> {
+        let tokens = Lexer::from_source(source.to_string()).lex()?;
+        let ast = Parser::new(tokens).parse()?;
+        let entry_point = EntryPoint::from_ast(&ast)?;
+
+        run(ast, entry_point, arg)
+    }
+
+    #[test]
+    fn calls_functions_declared_in_any_order() {
+        let source = "
+            fn main(arg: int) -> int {
+                return fact(arg) + classify(-2.5) + (2 ** 10 + 3) % 7;
+            }
+            fn fact(n: int) -> int {
+                if n <= 1 { return 1; }
+                return n * fact(n - 1);
+            }
+            fn classify(x: float) -> int {
+                if x < 0.0 {
+                    return -1;
+                } else if x == 0.0 {
+                    return 0;
+                }
+                return 1;
+            }
+        ";
+
+        assert_eq!(run_source(source, 5).unwrap(), 120 - 1 + 1027 % 7);
+    }
+
+    #[test]
+    fn main_without_a_return_value_exits_with_0() {
+        assert_eq!(run_source("fn main() { let x = 1; }", 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn deep_recursion_is_a_runtime_error() {
+        let source = "
+            fn down(n: int) -> int { return down(n + 1); }
+            fn main() -> int { return down(0); }
+        ";
+
+        let err = run_source(source, 0).unwrap_err();
+        assert_eq!(err.to_string(), "Stack overflow while calling 'down'");
+    }
+}
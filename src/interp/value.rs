@@ -0,0 +1,125 @@
+use crate::ast::ast::{Fn, TypeName};
+use std::{
+    fmt::{Display, Formatter},
+    sync::{Arc, Mutex},
+};
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    Range {
+        start: i64,
+        end: i64,
+        inclusive: bool,
+    },
+    /// Fields are kept in declaration order.
+    Struct {
+        name: String,
+        fields: Vec<(String, Value)>,
+    },
+    Enum {
+        name: String,
+        variant: String,
+        values: Vec<Value>,
+    },
+    /// Shared like the heap buffer behind an LLVM array, so copies see each other's writes.
+    /// Behind a `Mutex` only because errors carrying values have to be `Send`.
+    Array(Arc<Mutex<Vec<Value>>>),
+    /// A function used as a value. Closures carry copies of the variables that were in
+    /// scope where they were created.
+    Function {
+        function: Arc<Fn>,
+        captures: Vec<(String, Value)>,
+    },
+    Void,
+}
+
+impl Value {
+    /// The value a function of this return type yields when it falls off its end,
+    /// matching the zero the LLVM backend returns.
+    pub fn zero(type_name: &TypeName) -> Value {
+        let name = match type_name {
+            TypeName::Named(name) => name,
+            TypeName::Array { .. } => return Value::new_array(vec![]),
+            TypeName::Generic { .. } | TypeName::Function { .. } => return Value::Void,
+        };
+
+        match name.literal().as_str() {
+            "int" => Value::Int(0),
+            "float" => Value::Float(0.0),
+            "bool" => Value::Bool(false),
+            "string" => Value::String(String::new()),
+            _ => Value::Void,
+        }
+    }
+
+    pub fn new_array(elements: Vec<Value>) -> Value {
+        Value::Array(Arc::new(Mutex::new(elements)))
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::Bool(_) => "bool",
+            Value::String(_) => "string",
+            Value::Range { .. } => "range",
+            Value::Struct { .. } => "struct",
+            Value::Enum { .. } => "enum",
+            Value::Array(_) => "array",
+            Value::Function { .. } => "function",
+            Value::Void => "void",
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(float) => write!(f, "{}", float),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::String(s) => write!(f, "{}", s),
+            Value::Range {
+                start,
+                end,
+                inclusive,
+            } => write!(f, "{}..{}{}", start, if *inclusive { "=" } else { "" }, end),
+            Value::Struct { name, fields } => {
+                write!(f, "{} {{ ", name)?;
+                for (index, (field, value)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", field, value)?;
+                }
+                write!(f, " }}")
+            }
+            Value::Enum {
+                variant, values, ..
+            } => {
+                write!(f, "{}", variant)?;
+                if !values.is_empty() {
+                    let values: Vec<String> =
+                        values.iter().map(|value| value.to_string()).collect();
+                    write!(f, "({})", values.join(", "))?;
+                }
+                Ok(())
+            }
+            Value::Array(elements) => {
+                let elements: Vec<String> = elements
+                    .lock()
+                    .expect("Array lock poisoned")
+                    .iter()
+                    .map(|element| element.to_string())
+                    .collect();
+                write!(f, "[{}]", elements.join(", "))
+            }
+            Value::Function { function, .. } => write!(f, "fn {}", function.name),
+            Value::Void => write!(f, "void"),
+        }
+    }
+}
@@ -31,7 +31,7 @@ impl Lexer {
 impl Lexer {
     pub fn lex(&mut self) -> Result<Vec<Token>> {
         while let Some(token) = self.next_token()? {
-            if token.kind == TokenKind::Whitespace {
+            if token.kind == TokenKind::Whitespace || token.kind == TokenKind::Comment {
                 continue;
             }
             if token.kind == TokenKind::EOF {
@@ -79,7 +79,7 @@ impl Lexer {
     }
 
     pub fn is_number_start(&self, c: char) -> bool {
-        c.is_digit(10)
+        c.is_ascii_digit()
     }
 
     pub fn peek(&self) -> Option<char> {
@@ -155,6 +155,7 @@ impl Lexer {
                     "false" => TokenKind::False,
                     "null" => TokenKind::Null,
                     "while" => TokenKind::While,
+                    "loop" => TokenKind::Loop,
                     "for" => TokenKind::For,
                     "in" => TokenKind::In,
                     "break" => TokenKind::Break,
@@ -162,6 +163,11 @@ impl Lexer {
                     "use" => TokenKind::Use,
                     "export" => TokenKind::Export,
                     "from" => TokenKind::From,
+                    "struct" => TokenKind::Struct,
+                    "enum" => TokenKind::Enum,
+                    "match" => TokenKind::Match,
+                    "impl" => TokenKind::Impl,
+                    "interface" => TokenKind::Interface,
 
                     _ => TokenKind::Identifier,
                 }
@@ -174,8 +180,24 @@ impl Lexer {
                     '[' => TokenKind::LeftBracket,
                     ']' => TokenKind::RightBracket,
                     ',' => TokenKind::Comma,
-                    '.' => TokenKind::Dot,
-                    ':' => TokenKind::Colon,
+                    '.' => {
+                        if self.match_next('.') {
+                            if self.match_next('=') {
+                                TokenKind::DotDotEquals
+                            } else {
+                                TokenKind::DotDot
+                            }
+                        } else {
+                            TokenKind::Dot
+                        }
+                    }
+                    ':' => {
+                        if self.match_next(':') {
+                            TokenKind::ColonColon
+                        } else {
+                            TokenKind::Colon
+                        }
+                    }
                     ';' => TokenKind::Semicolon,
                     '/' => {
                         if self.match_next('/') {
@@ -191,7 +213,6 @@ impl Lexer {
                         }
                     }
                     '+' => {
-                        self.consume();
                         if self.match_next('+') {
                             TokenKind::Increment
                         } else if self.match_next('=') {
@@ -201,7 +222,6 @@ impl Lexer {
                         }
                     }
                     '-' => {
-                        self.consume();
                         if self.match_next('-') {
                             TokenKind::Decrement
                         } else if self.match_next('=') {
@@ -213,7 +233,6 @@ impl Lexer {
                         }
                     }
                     '*' => {
-                        self.consume();
                         if self.match_next('*') {
                             TokenKind::DoubleAsterisk
                         } else {
@@ -222,8 +241,8 @@ impl Lexer {
                     }
                     '%' => TokenKind::Percent,
                     '^' => TokenKind::Caret,
+                    '~' => TokenKind::Tilde,
                     '!' => {
-                        self.consume();
                         if self.match_next('=') {
                             TokenKind::BangEquals
                         } else {
@@ -231,15 +250,15 @@ impl Lexer {
                         }
                     }
                     '=' => {
-                        self.consume();
                         if self.match_next('=') {
                             TokenKind::EqualsEquals
+                        } else if self.match_next('>') {
+                            TokenKind::FatArrow
                         } else {
                             TokenKind::Equals
                         }
                     }
                     '<' => {
-                        self.consume();
                         if self.match_next('=') {
                             TokenKind::LessThanEquals
                         } else {
@@ -247,7 +266,6 @@ impl Lexer {
                         }
                     }
                     '>' => {
-                        self.consume();
                         if self.match_next('=') {
                             TokenKind::GreaterThanEquals
                         } else {
@@ -255,7 +273,6 @@ impl Lexer {
                         }
                     }
                     '&' => {
-                        self.consume();
                         if self.match_next('&') {
                             TokenKind::And
                         } else {
@@ -263,7 +280,6 @@ impl Lexer {
                         }
                     }
                     '|' => {
-                        self.consume();
                         if self.match_next('|') {
                             TokenKind::Or
                         } else {
@@ -291,15 +307,19 @@ impl Lexer {
                 TextSpan::new(start_pos, end_pos, literal),
             )))
         } else {
-            Ok(None)
+            Ok(Some(Token::new(
+                TokenKind::EOF,
+                TextSpan::new(self.position, self.position, String::new()),
+            )))
         }
     }
 
+    // Checks the character after the current one and consumes the current one on a match,
+    // so the caller's final consume() takes the second character of the operator
     pub fn match_next(&mut self, ch: char) -> bool {
-        if let Some(c) = self.current() {
-            if c == ch {
-                return true;
-            }
+        if self.peek() == Some(ch) {
+            self.consume();
+            return true;
         }
         false
     }
@@ -325,9 +345,10 @@ impl Lexer {
         let mut number_type = NumberType::Integer;
 
         while let Some(c) = self.current() {
-            if c.is_digit(10) {
+            if c.is_ascii_digit() {
                 number.push(c);
-            } else if c == '.' {
+            } else if c == '.' && number_type == NumberType::Integer && self.peek() != Some('.') {
+                // `0..n` is a range, not the float `0.` followed by `.n`
                 number.push(c);
                 number_type = NumberType::Float;
             } else {
@@ -340,8 +361,96 @@ impl Lexer {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum NumberType {
     Integer,
     Float,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The kinds of the tokens of `source`, without the EOF token ending every source.
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        let tokens = Lexer::from_source(source.to_string()).lex().unwrap();
+        let mut kinds: Vec<TokenKind> = tokens.into_iter().map(|token| token.kind).collect();
+        assert_eq!(kinds.pop(), Some(TokenKind::EOF));
+        kinds
+    }
+
+    #[test]
+    fn lexes_compound_operators() {
+        use TokenKind::*;
+
+        assert_eq!(
+            kinds("a++ b-- c += 1 d -= 2 e ** 3 f != g ~h"),
+            [
+                Identifier,
+                Increment,
+                Identifier,
+                Decrement,
+                Identifier,
+                PlusEquals,
+                Integer(1),
+                Identifier,
+                MinusEquals,
+                Integer(2),
+                Identifier,
+                DoubleAsterisk,
+                Integer(3),
+                Identifier,
+                BangEquals,
+                Identifier,
+                Tilde,
+                Identifier,
+            ]
+        );
+    }
+
+    #[test]
+    fn lexes_loop_keywords() {
+        use TokenKind::*;
+
+        assert_eq!(
+            kinds("while loop for in break continue inner"),
+            [While, Loop, For, In, Break, Continue, Identifier]
+        );
+    }
+
+    #[test]
+    fn integers_before_dots_start_ranges() {
+        use TokenKind::*;
+
+        assert_eq!(
+            kinds("0..n 1..=3 1.5 p.x"),
+            [
+                Integer(0),
+                DotDot,
+                Identifier,
+                Integer(1),
+                DotDotEquals,
+                Integer(3),
+                Float(1.5),
+                Identifier,
+                Dot,
+                Identifier,
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_comments() {
+        assert_eq!(
+            kinds("let x = 1; // the answer\nx"),
+            [
+                TokenKind::Let,
+                TokenKind::Identifier,
+                TokenKind::Equals,
+                TokenKind::Integer(1),
+                TokenKind::Semicolon,
+                TokenKind::Identifier,
+            ]
+        );
+    }
+}
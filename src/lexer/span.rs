@@ -20,7 +20,7 @@ impl TextSpan {
         if spans.is_empty() {
             panic!("Cannot combine empty spans")
         }
-        spans.sort_by(|a, b| a.start.index.cmp(&b.start.index));
+        spans.sort_by_key(|span| span.start.index);
 
         let start = spans.first().unwrap().start;
         let end = spans.last().unwrap().end;
@@ -43,9 +43,13 @@ impl Display for TokenKind {
             TokenKind::RightBracket => write!(f, "]"),
             TokenKind::Comma => write!(f, ","),
             TokenKind::Dot => write!(f, "."),
+            TokenKind::DotDot => write!(f, ".."),
+            TokenKind::DotDotEquals => write!(f, "..="),
             TokenKind::Colon => write!(f, ":"),
+            TokenKind::ColonColon => write!(f, "::"),
             TokenKind::Semicolon => write!(f, ";"),
             TokenKind::Arrow => write!(f, "->"),
+            TokenKind::FatArrow => write!(f, "=>"),
             TokenKind::Identifier => write!(f, "Identifier"),
             TokenKind::String(s) => write!(f, "{}", s),
             TokenKind::Float(r) => write!(f, "{}", r),
@@ -55,6 +59,7 @@ impl Display for TokenKind {
             TokenKind::If => write!(f, "if"),
             TokenKind::Else => write!(f, "else"),
             TokenKind::While => write!(f, "while"),
+            TokenKind::Loop => write!(f, "loop"),
             TokenKind::For => write!(f, "for"),
             TokenKind::In => write!(f, "in"),
             TokenKind::Return => write!(f, "return"),
@@ -63,6 +68,11 @@ impl Display for TokenKind {
             TokenKind::Use => write!(f, "use"),
             TokenKind::From => write!(f, "from"),
             TokenKind::Export => write!(f, "export"),
+            TokenKind::Struct => write!(f, "struct"),
+            TokenKind::Enum => write!(f, "enum"),
+            TokenKind::Match => write!(f, "match"),
+            TokenKind::Impl => write!(f, "impl"),
+            TokenKind::Interface => write!(f, "interface"),
             TokenKind::True => write!(f, "true"),
             TokenKind::False => write!(f, "false"),
             TokenKind::Null => write!(f, "null"),
@@ -109,9 +119,13 @@ pub enum TokenKind {
     RightBracket,
     Comma,
     Dot,
+    DotDot,       // ..
+    DotDotEquals, // ..=
     Colon,
+    ColonColon, // ::
     Semicolon,
     Arrow,
+    FatArrow, // =>
 
     // Literals
     Identifier,
@@ -125,6 +139,7 @@ pub enum TokenKind {
     If,
     Else,
     While,
+    Loop,
     For,
     In,
     Return,
@@ -133,6 +148,11 @@ pub enum TokenKind {
     Use,
     Export,
     From,
+    Struct,
+    Enum,
+    Match,
+    Impl,
+    Interface,
 
     True,
     False,
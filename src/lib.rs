@@ -0,0 +1,57 @@
+use crate::{
+    ast::{ast::Ast, parser::Parser},
+    diagnostic::Diagnostic,
+    lexer::{token::Token, Lexer},
+    resolver::Resolver,
+};
+
+pub mod ast;
+pub mod diagnostic;
+pub mod emit;
+pub mod error;
+pub mod fs;
+pub mod interp;
+pub mod lexer;
+#[cfg(feature = "llvm")]
+pub mod linker;
+pub mod manifest;
+pub mod module;
+pub mod profile;
+pub mod project;
+pub mod resolver;
+pub mod stdlib;
+#[cfg(feature = "llvm")]
+pub mod llvm {
+    pub mod debug;
+    pub mod ir;
+    pub mod target;
+}
+
+/// Output of the frontend for a single source file.
+#[derive(Debug)]
+pub struct CompiledUnit {
+    pub tokens: Vec<Token>,
+    pub ast: Ast,
+    pub resolver: Resolver,
+}
+
+/// Lexes, parses and resolves `source` without touching the file system.
+pub fn compile_source(source: &str) -> Result<CompiledUnit, Vec<Diagnostic>> {
+    let into_diagnostics = |err: anyhow::Error| Diagnostic::from_error(&err, source);
+
+    let tokens = Lexer::from_source(source.to_string())
+        .lex()
+        .map_err(into_diagnostics)?;
+    let ast = Parser::new(tokens.clone())
+        .parse()
+        .map_err(into_diagnostics)?;
+
+    let mut resolver = Resolver::new();
+    resolver.resolve_ast(&ast).map_err(into_diagnostics)?;
+
+    Ok(CompiledUnit {
+        tokens,
+        ast,
+        resolver,
+    })
+}
@@ -0,0 +1,39 @@
+use crate::error::PulseError::LinkerError;
+use anyhow::Result;
+use std::{path::Path, process::Command};
+
+pub fn link_executable(object: &Path, output: &Path) -> Result<()> {
+    log::debug!("Linking {:?} into {:?}", object, output);
+
+    let mut command = Command::new("cc");
+    command.arg(object).arg("-o").arg(output).arg("-lm");
+
+    run(command, "cc")
+}
+
+pub fn create_archive(object: &Path, output: &Path) -> Result<()> {
+    log::debug!("Archiving {:?} into {:?}", object, output);
+
+    let mut command = Command::new("ar");
+    command.arg("rcs").arg(output).arg(object);
+
+    run(command, "ar")
+}
+
+fn run(mut command: Command, program: &str) -> Result<()> {
+    let output = command
+        .output()
+        .map_err(|err| LinkerError(format!("Couldn't run '{}': {}", program, err)))?;
+
+    if !output.status.success() {
+        return Err(LinkerError(format!(
+            "'{}' exited with {}\n{}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        ))
+        .into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,324 @@
+use crate::{
+    ast::ast::{Fn, GetSpan, Stmt},
+    lexer::{position::Position, token::Token},
+    llvm::ir::CodeGen,
+};
+use inkwell::{
+    debug_info::{
+        debug_metadata_version, AsDIScope, DICompileUnit, DIFile, DIFlags, DIFlagsConstants,
+        DIScope, DISubprogram, DIType, DWARFEmissionKind, DWARFSourceLanguage, DebugInfoBuilder,
+    },
+    module::FlagBehavior,
+    types::BasicTypeEnum,
+    values::{FunctionValue, PointerValue},
+    AddressSpace,
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+// DWARF base type encodings
+const DW_ATE_BOOLEAN: u32 = 0x02;
+const DW_ATE_FLOAT: u32 = 0x04;
+const DW_ATE_SIGNED: u32 = 0x05;
+const DW_ATE_SIGNED_CHAR: u32 = 0x06;
+
+pub struct DebugInfo<'ctx> {
+    builder: DebugInfoBuilder<'ctx>,
+    compile_unit: DICompileUnit<'ctx>,
+    /// The file of the module declaring each top-level function and type, by name.
+    files: HashMap<String, DIFile<'ctx>>,
+    /// The file of the function being compiled.
+    file: DIFile<'ctx>,
+    subprogram: Option<DISubprogram<'ctx>>,
+}
+
+impl<'ctx> DebugInfo<'ctx> {
+    fn file(&self) -> DIFile<'ctx> {
+        self.file
+    }
+
+    fn scope(&self) -> DIScope<'ctx> {
+        match self.subprogram {
+            Some(subprogram) => subprogram.as_debug_info_scope(),
+            None => self.compile_unit.as_debug_info_scope(),
+        }
+    }
+
+    /// The DWARF description of a value of type `ty`, `None` for aggregates like structs,
+    /// enums, arrays and closures, which have none yet.
+    fn basic_type(&self, ty: BasicTypeEnum<'ctx>) -> Option<DIType<'ctx>> {
+        let basic = |name: &str, bits: u64, encoding: u32| {
+            self.builder
+                .create_basic_type(name, bits, encoding, DIFlags::PUBLIC)
+                .expect("Basic types have names")
+                .as_type()
+        };
+
+        let ty = match ty {
+            BasicTypeEnum::IntType(int) => match int.get_bit_width() {
+                1 => basic("bool", 8, DW_ATE_BOOLEAN),
+                64 => basic("int", 64, DW_ATE_SIGNED),
+                _ => return None,
+            },
+            BasicTypeEnum::FloatType(_) => basic("float", 64, DW_ATE_FLOAT),
+            BasicTypeEnum::PointerType(_) => self
+                .builder
+                .create_pointer_type(
+                    "string",
+                    basic("char", 8, DW_ATE_SIGNED_CHAR),
+                    64,
+                    0,
+                    AddressSpace::default(),
+                )
+                .as_type(),
+            BasicTypeEnum::ArrayType(_)
+            | BasicTypeEnum::StructType(_)
+            | BasicTypeEnum::VectorType(_) => return None,
+        };
+
+        Some(ty)
+    }
+}
+
+fn file_and_directory(source: &Path) -> (String, String) {
+    let filename = source
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let directory = source
+        .parent()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    (filename, directory)
+}
+
+// Source positions are zero based, DWARF lines and columns start at 1
+fn line(position: Position) -> u32 {
+    position.line + 1
+}
+
+fn column(position: Position) -> u32 {
+    position.column + 1
+}
+
+fn stmt_position(stmt: &Stmt) -> Option<Position> {
+    match stmt {
+        Stmt::Expr(expr) => Some(expr.span().start),
+        Stmt::Let(let_stmt) => Some(let_stmt.ident.span.start),
+        Stmt::If(if_stmt) => Some(if_stmt.if_token.span.start),
+        Stmt::Return(ret_stmt) => Some(ret_stmt.return_token.span.start),
+        Stmt::Fn(fn_stmt) => Some(fn_stmt.fn_token.span.start),
+        Stmt::Use(use_stmt) => Some(use_stmt.use_token.span.start),
+        Stmt::Impl(impl_stmt) => Some(impl_stmt.impl_token.span.start),
+        Stmt::Interface(interface) => Some(interface.interface_token.span.start),
+        Stmt::Block(block) => block.stmts.first().and_then(stmt_position),
+        Stmt::While(while_stmt) => Some(while_stmt.while_token.span.start),
+        Stmt::For(for_stmt) => Some(for_stmt.for_token.span.start),
+        Stmt::Loop(loop_stmt) => Some(loop_stmt.loop_token.span.start),
+        Stmt::Break(break_stmt) => Some(break_stmt.break_token.span.start),
+        Stmt::Continue(continue_stmt) => Some(continue_stmt.continue_token.span.start),
+        Stmt::Struct(struct_stmt) => Some(struct_stmt.struct_token.span.start),
+        Stmt::Enum(enum_stmt) => Some(enum_stmt.enum_token.span.start),
+    }
+}
+
+impl<'ctx> CodeGen<'ctx> {
+    /// Describes the program to debuggers. `sources` tells which file declares each
+    /// top-level function and type, `source` is the main file.
+    pub fn enable_debug_info(
+        &mut self,
+        source: &Path,
+        sources: &HashMap<String, PathBuf>,
+        is_optimized: bool,
+    ) {
+        let (filename, directory) = file_and_directory(source);
+        let (builder, compile_unit) = self.module.create_debug_info_builder(
+            true,
+            DWARFSourceLanguage::C,
+            &filename,
+            &directory,
+            concat!("pulse ", env!("CARGO_PKG_VERSION")),
+            is_optimized,
+            "",
+            0,
+            "",
+            DWARFEmissionKind::Full,
+            0,
+            false,
+            false,
+            "",
+            "",
+        );
+
+        self.module.add_basic_value_flag(
+            "Debug Info Version",
+            FlagBehavior::Warning,
+            self.context
+                .i32_type()
+                .const_int(debug_metadata_version() as u64, false),
+        );
+        self.module.add_basic_value_flag(
+            "Dwarf Version",
+            FlagBehavior::Warning,
+            self.context.i32_type().const_int(4, false),
+        );
+
+        let files = sources
+            .iter()
+            .map(|(name, path)| {
+                let (filename, directory) = file_and_directory(path);
+                (name.clone(), builder.create_file(&filename, &directory))
+            })
+            .collect();
+        self.debug_info = Some(DebugInfo {
+            builder,
+            file: compile_unit.get_file(),
+            compile_unit,
+            files,
+            subprogram: None,
+        });
+    }
+
+    pub(crate) fn debug_enter_fn(&mut self, fn_stmt: &Fn, function: FunctionValue<'ctx>) {
+        let Some(debug) = &mut self.debug_info else {
+            return;
+        };
+        if let Some(file) = debug.files.get(&self.declaration) {
+            debug.file = *file;
+        }
+
+        let return_type = function
+            .get_type()
+            .get_return_type()
+            .map(|ty| debug.basic_type(ty));
+        let param_types: Option<Vec<DIType>> = function
+            .get_type()
+            .get_param_types()
+            .into_iter()
+            .map(|ty| debug.basic_type(ty))
+            .collect();
+        // Signatures with a value that has no description are left without types
+        let (return_type, param_types) = match (return_type, param_types) {
+            (Some(None), _) | (_, None) => (None, vec![]),
+            (return_type, Some(param_types)) => (return_type.flatten(), param_types),
+        };
+
+        let subroutine_type = debug.builder.create_subroutine_type(
+            debug.file(),
+            return_type,
+            &param_types,
+            DIFlags::PUBLIC,
+        );
+
+        let position = fn_stmt.fn_token.span.start;
+        let name = function.get_name().to_string_lossy();
+        let subprogram = debug.builder.create_function(
+            debug.file().as_debug_info_scope(),
+            &name,
+            None,
+            debug.file(),
+            line(position),
+            subroutine_type,
+            !fn_stmt.exported,
+            true,
+            line(position),
+            DIFlags::PUBLIC,
+            false,
+        );
+        function.set_subprogram(subprogram);
+        debug.subprogram = Some(subprogram);
+
+        self.debug_set_location(position);
+    }
+
+    pub(crate) fn debug_exit_fn(&mut self) {
+        if let Some(debug) = &mut self.debug_info {
+            debug.subprogram = None;
+            self.builder.unset_current_debug_location();
+        }
+    }
+
+    pub(crate) fn debug_stmt(&self, stmt: &Stmt) {
+        if let Some(position) = stmt_position(stmt) {
+            self.debug_set_location(position);
+        }
+    }
+
+    fn debug_set_location(&self, position: Position) {
+        let Some(debug) = &self.debug_info else {
+            return;
+        };
+
+        let location = debug.builder.create_debug_location(
+            self.context,
+            line(position),
+            column(position),
+            debug.scope(),
+            None,
+        );
+        self.builder.set_current_debug_location(location);
+    }
+
+    /// Describes a `Let` (`arg_no == None`) or a `FnParam` stored at `ptr`.
+    pub(crate) fn debug_declare_variable(
+        &self,
+        ident: &Token,
+        arg_no: Option<u32>,
+        ptr: PointerValue<'ctx>,
+        ty: BasicTypeEnum<'ctx>,
+    ) {
+        let Some(debug) = &self.debug_info else {
+            return;
+        };
+        let Some(block) = self.builder.get_insert_block() else {
+            return;
+        };
+        let Some(di_type) = debug.basic_type(ty) else {
+            return;
+        };
+
+        let position = ident.span.start;
+        let variable = match arg_no {
+            Some(arg_no) => debug.builder.create_parameter_variable(
+                debug.scope(),
+                &ident.literal(),
+                arg_no,
+                debug.file(),
+                line(position),
+                di_type,
+                true,
+                DIFlags::ZERO,
+            ),
+            None => debug.builder.create_auto_variable(
+                debug.scope(),
+                &ident.literal(),
+                debug.file(),
+                line(position),
+                di_type,
+                true,
+                DIFlags::ZERO,
+                0,
+            ),
+        };
+        let location = debug.builder.create_debug_location(
+            self.context,
+            line(position),
+            column(position),
+            debug.scope(),
+            None,
+        );
+
+        debug
+            .builder
+            .insert_declare_at_end(ptr, Some(variable), None, location, block);
+    }
+
+    pub(crate) fn debug_finalize(&self) {
+        if let Some(debug) = &self.debug_info {
+            debug.builder.finalize();
+        }
+    }
+}
@@ -1,10 +1,2857 @@
+use crate::{
+    ast::ast::*,
+    error::{
+        PulseError::{CodegenError, MissingEntryPoint},
+        RUNTIME_ERROR_EXIT_CODE,
+    },
+    lexer::{
+        span::TextSpan,
+        token::{Token, TokenKind},
+    },
+    llvm::{debug::DebugInfo, target::optimization_level},
+    project::EntryPoint,
+    stdlib::StdFn,
+};
+use anyhow::Result;
 use inkwell::{
-    builder::Builder, context::Context, execution_engine::ExecutionEngine, module::Module,
+    basic_block::BasicBlock,
+    builder::Builder,
+    context::Context,
+    execution_engine::ExecutionEngine,
+    intrinsics::Intrinsic,
+    module::{Linkage, Module},
+    types::{
+        BasicMetadataTypeEnum, BasicType, BasicTypeEnum, FunctionType as LLVMFunctionType,
+        StructType,
+    },
+    values::{BasicMetadataValueEnum, BasicValueEnum, FunctionValue, IntValue, PointerValue},
+    AddressSpace, FloatPredicate, IntPredicate,
 };
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy)]
+struct Local<'ctx> {
+    ptr: PointerValue<'ctx>,
+    ty: BasicTypeEnum<'ctx>,
+}
+
+/// The LLVM type of a declared struct and its field names, in declaration order.
+#[derive(Debug, Clone)]
+struct StructLayout<'ctx> {
+    ty: StructType<'ctx>,
+    fields: Vec<String>,
+}
+
+/// An enum is a `{ tag, fields... }` struct where every variant gets its own fields
+/// after the tag instead of sharing storage, trading size for not needing casts.
+#[derive(Debug, Clone)]
+struct EnumLayout<'ctx> {
+    ty: StructType<'ctx>,
+    /// Indexed by tag.
+    variants: Vec<VariantLayout>,
+}
+
+#[derive(Debug, Clone)]
+struct VariantLayout {
+    name: String,
+    /// Where the payload values live in the enum struct.
+    fields: Vec<u32>,
+}
+
+/// A closure or an instance of a generic function, compiled once the function needing
+/// it is done so the builder never has to leave a half-built function.
+#[derive(Debug, Clone)]
+struct PendingFn<'ctx> {
+    function: Fn,
+    value: FunctionValue<'ctx>,
+    /// What the type parameters in scope of the body stand for.
+    type_args: HashMap<String, BasicTypeEnum<'ctx>>,
+    env: Option<ClosureEnv<'ctx>>,
+    /// The top-level function or type the body was written in.
+    declaration: String,
+}
+
+/// The variables a closure captured, stored in this order in its environment struct.
+#[derive(Debug, Clone)]
+struct ClosureEnv<'ctx> {
+    captures: Vec<(String, BasicTypeEnum<'ctx>)>,
+    ty: StructType<'ctx>,
+}
+
+/// Where `break` and `continue` jump to inside a loop.
+#[derive(Debug, Clone)]
+struct LoopTarget<'ctx> {
+    label: Option<String>,
+    break_bb: BasicBlock<'ctx>,
+    continue_bb: BasicBlock<'ctx>,
+}
 
 pub struct CodeGen<'ctx> {
-    context: &'ctx Context,
-    module: Module<'ctx>,
-    builder: Builder<'ctx>,
-    execution_engine: ExecutionEngine<'ctx>,
+    pub context: &'ctx Context,
+    pub module: Module<'ctx>,
+    pub builder: Builder<'ctx>,
+    pub execution_engine: ExecutionEngine<'ctx>,
+    pub(crate) debug_info: Option<DebugInfo<'ctx>>,
+    scopes: Vec<HashMap<String, Local<'ctx>>>,
+    current_fn: Option<FunctionValue<'ctx>>,
+    loops: Vec<LoopTarget<'ctx>>,
+    structs: HashMap<String, StructLayout<'ctx>>,
+    enums: HashMap<String, EnumLayout<'ctx>>,
+    /// The enum each variant constructor belongs to.
+    variants: HashMap<String, String>,
+    /// Element types of the array types created so far, keyed by their struct name.
+    arrays: HashMap<String, BasicTypeEnum<'ctx>>,
+    /// Signatures of the function types created so far, keyed by their struct name.
+    closures: HashMap<String, LLVMFunctionType<'ctx>>,
+    pending_fns: Vec<PendingFn<'ctx>>,
+    /// The top-level function, or type of the `impl`, whose code is being compiled. Debug
+    /// info tells the file of the module declaring it from its name.
+    pub(crate) declaration: String,
+    /// Generic functions and structs, instantiated for the type arguments they're used
+    /// with.
+    generic_fns: HashMap<String, Fn>,
+    generic_structs: HashMap<String, Struct>,
+    /// Type arguments of the struct instances created so far, keyed by their struct name.
+    struct_args: HashMap<String, Vec<BasicTypeEnum<'ctx>>>,
+    /// What the type parameters stand for in the instance being compiled.
+    type_args: HashMap<String, BasicTypeEnum<'ctx>>,
+}
+
+impl<'ctx> CodeGen<'ctx> {
+    pub fn new(context: &'ctx Context, name: &str, opt_level: u8) -> Result<Self> {
+        let module = context.create_module(name);
+        let execution_engine = module
+            .create_jit_execution_engine(optimization_level(opt_level))
+            .map_err(|err| CodegenError(err.to_string()))?;
+
+        Ok(Self {
+            context,
+            module,
+            builder: context.create_builder(),
+            execution_engine,
+            debug_info: None,
+            scopes: vec![],
+            current_fn: None,
+            loops: vec![],
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            variants: HashMap::new(),
+            arrays: HashMap::new(),
+            closures: HashMap::new(),
+            pending_fns: vec![],
+            declaration: String::new(),
+            generic_fns: HashMap::new(),
+            generic_structs: HashMap::new(),
+            struct_args: HashMap::new(),
+            type_args: HashMap::new(),
+        })
+    }
+}
+
+impl<'ctx> CodeGen<'ctx> {
+    pub fn compile(&mut self, ast: &Ast) -> Result<()> {
+        // Types are named first so fields, payloads and signatures can refer to any of them
+        for stmt in &ast.stmts {
+            match stmt {
+                Stmt::Struct(struct_stmt) if !struct_stmt.type_params.is_empty() => {
+                    self.generic_structs
+                        .insert(struct_stmt.name.literal(), struct_stmt.clone());
+                }
+                Stmt::Struct(struct_stmt) => self.declare_struct(struct_stmt),
+                Stmt::Enum(enum_stmt) => self.declare_enum(enum_stmt),
+                _ => {}
+            }
+        }
+        for stmt in &ast.stmts {
+            match stmt {
+                Stmt::Struct(struct_stmt) if struct_stmt.type_params.is_empty() => {
+                    self.define_struct(struct_stmt)?
+                }
+                Stmt::Enum(enum_stmt) => self.define_enum(enum_stmt)?,
+                _ => {}
+            }
+        }
+
+        // Declare every function up front so calls don't depend on declaration order
+        for stmt in &ast.stmts {
+            match stmt {
+                Stmt::Fn(fn_stmt) if !fn_stmt.type_params.is_empty() => {
+                    self.generic_fns
+                        .insert(fn_stmt.name.clone(), fn_stmt.clone());
+                }
+                Stmt::Fn(fn_stmt) => {
+                    self.declare_fn(fn_stmt, None)?;
+                }
+                Stmt::Impl(impl_stmt) => {
+                    for method in &impl_stmt.methods {
+                        self.declare_fn(method, Some(&impl_stmt.target))?;
+                    }
+                }
+                Stmt::Use(use_stmt) => self.declare_use(use_stmt)?,
+                _ => {}
+            }
+        }
+
+        for stmt in &ast.stmts {
+            match stmt {
+                Stmt::Fn(fn_stmt) if fn_stmt.type_params.is_empty() => {
+                    self.compile_fn(fn_stmt, None)?
+                }
+                Stmt::Impl(impl_stmt) => {
+                    for method in &impl_stmt.methods {
+                        self.compile_fn(method, Some(&impl_stmt.target))?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        // Compiling a closure or an instance may queue more of them
+        while let Some(pending) = self.pending_fns.pop() {
+            self.compile_pending_fn(pending)?;
+        }
+
+        self.debug_finalize();
+
+        self.module
+            .verify()
+            .map_err(|err| CodegenError(err.to_string()))?;
+
+        Ok(())
+    }
+
+    pub fn print_ir(&self) -> String {
+        self.module.print_to_string().to_string()
+    }
+
+    /// Renames the Pulse `main` and adds a C `main(argc, argv)` calling it, so native
+    /// executables receive the same `arg` (number of arguments) as `pulse run`.
+    pub fn emit_entry_shim(&mut self, entry_point: EntryPoint) -> Result<()> {
+        let pulse_main = self.module.get_function("main").ok_or(MissingEntryPoint)?;
+        pulse_main.as_global_value().set_name("pulse.main");
+        pulse_main.set_linkage(Linkage::Internal);
+
+        let i32_type = self.context.i32_type();
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let main = self.module.add_function(
+            "main",
+            i32_type.fn_type(&[i32_type.into(), ptr_type.into()], false),
+            None,
+        );
+
+        let entry = self.context.append_basic_block(main, "entry");
+        self.builder.position_at_end(entry);
+
+        let mut args: Vec<BasicMetadataValueEnum> = vec![];
+        if entry_point.takes_arg {
+            let argc = main.get_nth_param(0).unwrap().into_int_value();
+            let count = self
+                .builder
+                .build_int_sub(argc, i32_type.const_int(1, false), "count")?;
+            let arg = self
+                .builder
+                .build_int_s_extend(count, self.context.i64_type(), "arg")?;
+            args.push(arg.into());
+        }
+
+        let result = self.builder.build_call(pulse_main, &args, "result")?;
+
+        let exit_code = match result.try_as_basic_value().left() {
+            Some(value) if entry_point.returns_int => {
+                self.builder
+                    .build_int_truncate(value.into_int_value(), i32_type, "exit_code")?
+            }
+            _ => i32_type.const_zero(),
+        };
+        self.builder.build_return(Some(&exit_code))?;
+
+        Ok(())
+    }
+
+    fn map_type(&mut self, type_name: &TypeName) -> Result<Option<BasicTypeEnum<'ctx>>> {
+        let type_token = match type_name {
+            TypeName::Named(token) => token,
+            TypeName::Generic { name, args, .. } => {
+                let mut arg_types = vec![];
+                for arg in args {
+                    arg_types.push(self.map_type(arg)?.ok_or_else(|| {
+                        CodegenError("Type arguments cannot be void".to_string())
+                    })?);
+                }
+                return Ok(Some(
+                    self.struct_instance(&name.literal(), arg_types)?.into(),
+                ));
+            }
+            TypeName::Array { element, .. } => {
+                let element = self
+                    .map_type(element)?
+                    .ok_or_else(|| CodegenError("Arrays cannot hold void values".to_string()))?;
+                return Ok(Some(self.array_type(element).into()));
+            }
+            TypeName::Function {
+                params,
+                return_type,
+                ..
+            } => {
+                let mut param_types: Vec<BasicMetadataTypeEnum> =
+                    vec![self.context.ptr_type(AddressSpace::default()).into()];
+                for param in params {
+                    let param = self.map_type(param)?.ok_or_else(|| {
+                        CodegenError("Parameters cannot be of type void".to_string())
+                    })?;
+                    param_types.push(param.into());
+                }
+                let return_type = match return_type {
+                    Some(return_type) => self.map_type(return_type)?,
+                    None => None,
+                };
+                let signature = match return_type {
+                    Some(ty) => ty.fn_type(&param_types, false),
+                    None => self.context.void_type().fn_type(&param_types, false),
+                };
+
+                return Ok(Some(self.closure_type(signature).into()));
+            }
+        };
+
+        if let Some(ty) = self.type_args.get(&type_token.literal()) {
+            return Ok(Some(*ty));
+        }
+        match type_token.literal().as_str() {
+            "int" => Ok(Some(self.context.i64_type().into())),
+            "float" => Ok(Some(self.context.f64_type().into())),
+            "bool" => Ok(Some(self.context.bool_type().into())),
+            "string" => Ok(Some(self.context.ptr_type(AddressSpace::default()).into())),
+            "void" => Ok(None),
+            other => match (self.structs.get(other), self.enums.get(other)) {
+                (Some(layout), _) => Ok(Some(layout.ty.into())),
+                (_, Some(layout)) => Ok(Some(layout.ty.into())),
+                _ => Err(CodegenError(format!("Unknown type '{}'", other)).into()),
+            },
+        }
+    }
+
+    /// `{ start, end, inclusive }`, the value of a range expression.
+    fn range_type(&self) -> StructType<'ctx> {
+        let int_type = self.context.i64_type();
+        self.context.struct_type(
+            &[
+                int_type.into(),
+                int_type.into(),
+                self.context.bool_type().into(),
+            ],
+            false,
+        )
+    }
+
+    /// `{ data, len }`, where `data` points to `len` elements on the heap. Every element
+    /// type gets its own named struct so indexing can tell which type to load.
+    fn array_type(&mut self, element: BasicTypeEnum<'ctx>) -> StructType<'ctx> {
+        let name = format!("array.{}", type_key(element));
+        if let Some(ty) = self.context.get_struct_type(&name) {
+            return ty;
+        }
+
+        let ty = self.context.opaque_struct_type(&name);
+        ty.set_body(
+            &[
+                self.context.ptr_type(AddressSpace::default()).into(),
+                self.context.i64_type().into(),
+            ],
+            false,
+        );
+        self.arrays.insert(name, element);
+
+        ty
+    }
+
+    /// The element type of `ty` if it is one of the array types.
+    fn array_element(&self, ty: BasicTypeEnum<'ctx>) -> Option<BasicTypeEnum<'ctx>> {
+        let BasicTypeEnum::StructType(ty) = ty else {
+            return None;
+        };
+        ty.get_name()
+            .and_then(|name| self.arrays.get(name.to_str().ok()?))
+            .copied()
+    }
+
+    /// `{ function, env }`, a function value. `function` takes the `env` pointer to the
+    /// captured variables before its own parameters. Like arrays, every signature gets
+    /// its own named struct so calls can tell which function type to use.
+    fn closure_type(&mut self, signature: LLVMFunctionType<'ctx>) -> StructType<'ctx> {
+        let name = format!("closure.{}", signature.print_to_string().to_string());
+        if let Some(ty) = self.context.get_struct_type(&name) {
+            return ty;
+        }
+
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let ty = self.context.opaque_struct_type(&name);
+        ty.set_body(&[ptr_type.into(), ptr_type.into()], false);
+        self.closures.insert(name, signature);
+
+        ty
+    }
+
+    /// The signature of the function behind `ty` if it is one of the closure types.
+    fn closure_signature(&self, ty: BasicTypeEnum<'ctx>) -> Option<LLVMFunctionType<'ctx>> {
+        let BasicTypeEnum::StructType(ty) = ty else {
+            return None;
+        };
+        ty.get_name()
+            .and_then(|name| self.closures.get(name.to_str().ok()?))
+            .copied()
+    }
+
+    /// `signature` with the `env` pointer of a closure in front of its parameters.
+    fn with_env(&self, signature: LLVMFunctionType<'ctx>) -> LLVMFunctionType<'ctx> {
+        let mut params: Vec<BasicMetadataTypeEnum> =
+            vec![self.context.ptr_type(AddressSpace::default()).into()];
+        params.extend(
+            signature
+                .get_param_types()
+                .into_iter()
+                .map(BasicMetadataTypeEnum::from),
+        );
+
+        match signature.get_return_type() {
+            Some(ty) => ty.fn_type(&params, false),
+            None => self.context.void_type().fn_type(&params, false),
+        }
+    }
+
+    /// The signature of `fn_stmt`, taking a value of its `impl` type `owner` first when it
+    /// has a receiver.
+    fn fn_type(&mut self, fn_stmt: &Fn, owner: Option<&Token>) -> Result<LLVMFunctionType<'ctx>> {
+        let mut params: Vec<BasicMetadataTypeEnum> = vec![];
+        if let (Some(_), Some(owner)) = (&fn_stmt.receiver, owner) {
+            if let Some(ty) = self.map_type(&TypeName::Named(owner.clone()))? {
+                params.push(ty.into());
+            }
+        }
+        for param in &fn_stmt.params {
+            match self.map_type(&param.type_annotation.type_name)? {
+                Some(ty) => params.push(ty.into()),
+                None => {
+                    return Err(CodegenError(format!(
+                        "Parameter '{}' cannot be of type void",
+                        param.ident.literal()
+                    ))
+                    .into())
+                }
+            }
+        }
+
+        let return_type = match &fn_stmt.return_type {
+            Some(ret) => self.map_type(&ret.type_name)?,
+            None => None,
+        };
+
+        Ok(match return_type {
+            Some(ty) => ty.fn_type(&params, false),
+            None => self.context.void_type().fn_type(&params, false),
+        })
+    }
+
+    fn declare_struct(&mut self, struct_stmt: &Struct) {
+        let name = struct_stmt.name.literal();
+        let layout = StructLayout {
+            ty: self.context.opaque_struct_type(&name),
+            fields: struct_stmt
+                .fields
+                .iter()
+                .map(|field| field.ident.literal())
+                .collect(),
+        };
+        self.structs.insert(name, layout);
+    }
+
+    /// The instance of the generic struct `name` for `args`, like `Pair<i64, ptr>`. It's
+    /// registered with the declared structs so fields are found by name.
+    fn struct_instance(
+        &mut self,
+        name: &str,
+        args: Vec<BasicTypeEnum<'ctx>>,
+    ) -> Result<StructType<'ctx>> {
+        let struct_stmt =
+            self.generic_structs.get(name).cloned().ok_or_else(|| {
+                CodegenError(format!("Type '{}' doesn't take type arguments", name))
+            })?;
+        let arg_names: Vec<String> = args.iter().map(|arg| type_key(*arg)).collect();
+        let instance = format!("{}<{}>", name, arg_names.join(", "));
+        if let Some(layout) = self.structs.get(&instance) {
+            return Ok(layout.ty);
+        }
+
+        let type_args = struct_stmt
+            .type_params
+            .iter()
+            .map(|param| param.name.literal())
+            .zip(args.iter().copied())
+            .collect();
+        let ty = self.context.opaque_struct_type(&instance);
+        let layout = StructLayout {
+            ty,
+            fields: struct_stmt
+                .fields
+                .iter()
+                .map(|field| field.ident.literal())
+                .collect(),
+        };
+        self.structs.insert(instance.clone(), layout);
+        self.struct_args.insert(instance, args);
+
+        let enclosing_type_args = std::mem::replace(&mut self.type_args, type_args);
+        let field_types = self.field_types(&struct_stmt);
+        self.type_args = enclosing_type_args;
+        ty.set_body(&field_types?, false);
+
+        Ok(ty)
+    }
+
+    fn define_struct(&mut self, struct_stmt: &Struct) -> Result<()> {
+        let field_types = self.field_types(struct_stmt)?;
+        self.structs[&struct_stmt.name.literal()]
+            .ty
+            .set_body(&field_types, false);
+
+        Ok(())
+    }
+
+    fn field_types(&mut self, struct_stmt: &Struct) -> Result<Vec<BasicTypeEnum<'ctx>>> {
+        let mut field_types = vec![];
+        for field in &struct_stmt.fields {
+            let ty = self
+                .map_type(&field.type_annotation.type_name)?
+                .ok_or_else(|| {
+                    CodegenError(format!(
+                        "Field '{}' cannot be of type void",
+                        field.ident.literal()
+                    ))
+                })?;
+            field_types.push(ty);
+        }
+
+        Ok(field_types)
+    }
+
+    fn declare_enum(&mut self, enum_stmt: &Enum) {
+        let name = enum_stmt.name.literal();
+
+        let mut next_index = 1;
+        let mut variants = vec![];
+        for variant in &enum_stmt.variants {
+            let count = variant.fields.len() as u32;
+            variants.push(VariantLayout {
+                name: variant.ident.literal(),
+                fields: (next_index..next_index + count).collect(),
+            });
+            next_index += count;
+
+            self.variants.insert(variant.ident.literal(), name.clone());
+        }
+
+        let layout = EnumLayout {
+            ty: self.context.opaque_struct_type(&name),
+            variants,
+        };
+        self.enums.insert(name, layout);
+    }
+
+    fn define_enum(&mut self, enum_stmt: &Enum) -> Result<()> {
+        let mut field_types = vec![self.context.i64_type().into()];
+        for variant in &enum_stmt.variants {
+            for type_name in &variant.fields {
+                let ty = self.map_type(type_name)?.ok_or_else(|| {
+                    CodegenError(format!(
+                        "Variant '{}' cannot hold a void value",
+                        variant.ident.literal()
+                    ))
+                })?;
+                field_types.push(ty);
+            }
+        }
+
+        self.enums[&enum_stmt.name.literal()]
+            .ty
+            .set_body(&field_types, false);
+
+        Ok(())
+    }
+
+    /// The enum type `variant` belongs to, its tag and its layout.
+    fn variant_layout(&self, variant: &str) -> Option<(StructType<'ctx>, u64, &VariantLayout)> {
+        let layout = self.enums.get(self.variants.get(variant)?)?;
+        let tag = layout
+            .variants
+            .iter()
+            .position(|existing| existing.name == variant)?;
+
+        Some((layout.ty, tag as u64, &layout.variants[tag]))
+    }
+
+    /// The layout of `ty`, which must be one of the declared structs.
+    fn struct_layout(&self, ty: StructType<'ctx>) -> Result<&StructLayout<'ctx>> {
+        ty.get_name()
+            .and_then(|name| self.structs.get(name.to_str().ok()?))
+            .ok_or_else(|| CodegenError("Value is not a struct".into()).into())
+    }
+
+    fn field_index(&self, ty: StructType<'ctx>, field: &Token) -> Result<u32> {
+        let name = field.literal();
+        self.struct_layout(ty)?
+            .fields
+            .iter()
+            .position(|existing| *existing == name)
+            .map(|index| index as u32)
+            .ok_or_else(|| CodegenError(format!("No field '{}' on this struct", name)).into())
+    }
+
+    /// Declares a free function, or a function of the `impl` block for `owner` under its
+    /// qualified name.
+    fn declare_fn(&mut self, fn_stmt: &Fn, owner: Option<&Token>) -> Result<FunctionValue<'ctx>> {
+        let fn_type = self.fn_type(fn_stmt, owner)?;
+        // `main` stays visible so it can be looked up by the JIT and the linker
+        let linkage = if owner.is_none() && (fn_stmt.exported || fn_stmt.name == "main") {
+            None
+        } else {
+            Some(Linkage::Internal)
+        };
+        let function = self
+            .module
+            .add_function(&fn_name(fn_stmt, owner), fn_type, linkage);
+
+        let names = fn_stmt
+            .receiver
+            .iter()
+            .chain(fn_stmt.params.iter().map(|param| &param.ident));
+        for (name, value) in names.zip(function.get_param_iter()) {
+            value.set_name(&name.literal());
+        }
+
+        Ok(function)
+    }
+
+    fn declare_use(&mut self, use_stmt: &Use) -> Result<()> {
+        // Functions of the project's own modules are compiled along with the rest
+        if use_stmt.is_relative() {
+            return Ok(());
+        }
+
+        let module = use_stmt.module();
+        for item in &use_stmt.items {
+            let name = item.literal();
+            match StdFn::find(&module, &name) {
+                Some(StdFn::Println) => self.define_print_fn("println", 1, "%s\n")?,
+                Some(StdFn::Eprintln) => self.define_print_fn("eprintln", 2, "%s\n")?,
+                Some(StdFn::Print) => self.define_print_fn("print", 1, "%s")?,
+                Some(StdFn::ReadLine) => self.define_read_line()?,
+                None => {
+                    return Err(
+                        CodegenError(format!("Cannot import '{}' from '{}'", name, module)).into(),
+                    )
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Defines `name(string)` as a thin wrapper around libc's `dprintf`.
+    fn define_print_fn(&mut self, name: &str, fd: u64, format: &str) -> Result<()> {
+        if self.module.get_function(name).is_some() {
+            return Ok(());
+        }
+
+        let i32_type = self.context.i32_type();
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+
+        let dprintf = self.declare_external(
+            "dprintf",
+            i32_type.fn_type(&[i32_type.into(), ptr_type.into()], true),
+        );
+
+        let function = self.module.add_function(
+            name,
+            self.context.void_type().fn_type(&[ptr_type.into()], false),
+            Some(Linkage::Internal),
+        );
+        let entry = self.context.append_basic_block(function, "entry");
+        let previous_block = self.builder.get_insert_block();
+        self.builder.position_at_end(entry);
+
+        let format = self.builder.build_global_string_ptr(format, "fmt")?;
+        let message = function.get_nth_param(0).expect("print takes one argument");
+        self.builder.build_call(
+            dprintf,
+            &[
+                i32_type.const_int(fd, false).into(),
+                format.as_pointer_value().into(),
+                message.into(),
+            ],
+            "",
+        )?;
+        self.builder.build_return(None)?;
+
+        if let Some(block) = previous_block {
+            self.builder.position_at_end(block);
+        }
+
+        Ok(())
+    }
+
+    /// Defines `read_line()` on top of libc's `getline`, returning a copy of the line
+    /// without its line ending, or an empty string at the end of the input. The stream
+    /// and the buffer `getline` reads into are kept in globals and reused across calls.
+    fn define_read_line(&mut self) -> Result<()> {
+        if self.module.get_function("read_line").is_some() {
+            return Ok(());
+        }
+
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+
+        let fdopen = self.declare_external(
+            "fdopen",
+            ptr_type.fn_type(&[i32_type.into(), ptr_type.into()], false),
+        );
+        let getline = self.declare_external(
+            "getline",
+            i64_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into()], false),
+        );
+        let strndup = self.declare_external(
+            "strndup",
+            ptr_type.fn_type(&[ptr_type.into(), i64_type.into()], false),
+        );
+        let internal_global = |name: &str, ty: BasicTypeEnum<'ctx>| {
+            let global = self.module.add_global(ty, None, name);
+            global.set_linkage(Linkage::Internal);
+            global.set_initializer(&ty.const_zero());
+            global.as_pointer_value()
+        };
+        let stream = internal_global("pulse.stdin", ptr_type.into());
+        let buffer = internal_global("pulse.line", ptr_type.into());
+        let capacity = internal_global("pulse.line_capacity", i64_type.into());
+
+        let function = self.module.add_function(
+            "read_line",
+            ptr_type.fn_type(&[], false),
+            Some(Linkage::Internal),
+        );
+        let entry = self.context.append_basic_block(function, "entry");
+        let open_block = self.context.append_basic_block(function, "open");
+        let read_block = self.context.append_basic_block(function, "read");
+        let at_end_block = self.context.append_basic_block(function, "at_end");
+        let line_block = self.context.append_basic_block(function, "line");
+        let previous_block = self.builder.get_insert_block();
+
+        self.builder.position_at_end(entry);
+        let opened = self
+            .builder
+            .build_load(ptr_type, stream, "stdin")?
+            .into_pointer_value();
+        let is_open = self.builder.build_is_not_null(opened, "is_open")?;
+        self.builder
+            .build_conditional_branch(is_open, read_block, open_block)?;
+
+        self.builder.position_at_end(open_block);
+        let mode = self.builder.build_global_string_ptr("r", "mode")?;
+        let opened = self
+            .builder
+            .build_call(
+                fdopen,
+                &[i32_type.const_zero().into(), mode.as_pointer_value().into()],
+                "stdin",
+            )?
+            .try_as_basic_value()
+            .left()
+            .expect("fdopen returns a value");
+        self.builder.build_store(stream, opened)?;
+        self.builder.build_unconditional_branch(read_block)?;
+
+        self.builder.position_at_end(read_block);
+        let opened = self.builder.build_load(ptr_type, stream, "stdin")?;
+        let read = self
+            .builder
+            .build_call(
+                getline,
+                &[buffer.into(), capacity.into(), opened.into()],
+                "read",
+            )?
+            .try_as_basic_value()
+            .left()
+            .expect("getline returns a value")
+            .into_int_value();
+        let at_end = self.builder.build_int_compare(
+            IntPredicate::SLT,
+            read,
+            i64_type.const_int(1, false),
+            "at_end",
+        )?;
+        self.builder
+            .build_conditional_branch(at_end, at_end_block, line_block)?;
+
+        self.builder.position_at_end(at_end_block);
+        let empty = self.builder.build_global_string_ptr("", "empty")?;
+        self.builder.build_return(Some(&empty.as_pointer_value()))?;
+
+        self.builder.position_at_end(line_block);
+        let line = self
+            .builder
+            .build_load(ptr_type, buffer, "line")?
+            .into_pointer_value();
+        let length = self.strip_line_end(line, read, b'\n')?;
+        let length = self.strip_line_end(line, length, b'\r')?;
+        let copy = self
+            .builder
+            .build_call(strndup, &[line.into(), length.into()], "copy")?
+            .try_as_basic_value()
+            .left()
+            .expect("strndup returns a value");
+        self.builder.build_return(Some(&copy))?;
+
+        if let Some(block) = previous_block {
+            self.builder.position_at_end(block);
+        }
+
+        Ok(())
+    }
+
+    /// `length` minus one if the character of `line` before `length` is `end`.
+    fn strip_line_end(
+        &self,
+        line: PointerValue<'ctx>,
+        length: IntValue<'ctx>,
+        end: u8,
+    ) -> Result<IntValue<'ctx>> {
+        let i8_type = self.context.i8_type();
+        let i64_type = self.context.i64_type();
+        let one = i64_type.const_int(1, false);
+
+        let not_empty = self.builder.build_int_compare(
+            IntPredicate::SGT,
+            length,
+            i64_type.const_zero(),
+            "not_empty",
+        )?;
+        let last = self.builder.build_int_sub(length, one, "last")?;
+        let last = self
+            .builder
+            .build_select(not_empty, last, i64_type.const_zero(), "last")?
+            .into_int_value();
+        // SAFETY: `last` is an index into the line, or 0 which is its terminator at worst
+        let last = unsafe {
+            self.builder
+                .build_in_bounds_gep(i8_type, line, &[last], "last")?
+        };
+        let last = self
+            .builder
+            .build_load(i8_type, last, "last")?
+            .into_int_value();
+        let is_end = self.builder.build_int_compare(
+            IntPredicate::EQ,
+            last,
+            i8_type.const_int(end as u64, false),
+            "is_end",
+        )?;
+        let strip = self.builder.build_and(not_empty, is_end, "strip")?;
+        let stripped = self.builder.build_int_sub(length, one, "stripped")?;
+
+        Ok(self
+            .builder
+            .build_select(strip, stripped, length, "length")?
+            .into_int_value())
+    }
+
+    fn compile_fn(&mut self, fn_stmt: &Fn, owner: Option<&Token>) -> Result<()> {
+        let function = self
+            .module
+            .get_function(&fn_name(fn_stmt, owner))
+            .expect("Function should have been declared");
+
+        self.declaration = match owner {
+            Some(owner) => owner.literal(),
+            None => fn_stmt.name.clone(),
+        };
+        self.compile_fn_body(fn_stmt, function, None)
+    }
+
+    fn compile_pending_fn(&mut self, pending: PendingFn<'ctx>) -> Result<()> {
+        let enclosing_type_args = std::mem::replace(&mut self.type_args, pending.type_args);
+        self.declaration = pending.declaration;
+        let compiled = self.compile_fn_body(&pending.function, pending.value, pending.env.as_ref());
+        self.type_args = enclosing_type_args;
+        compiled
+    }
+
+    /// Compiles the body of `function`. A closure first copies the variables it captured
+    /// out of the `env` it's passed before its parameters.
+    fn compile_fn_body(
+        &mut self,
+        fn_stmt: &Fn,
+        function: FunctionValue<'ctx>,
+        closure: Option<&ClosureEnv<'ctx>>,
+    ) -> Result<()> {
+        let entry = self.context.append_basic_block(function, "entry");
+        self.builder.position_at_end(entry);
+        self.current_fn = Some(function);
+        self.scopes.push(HashMap::new());
+        self.debug_enter_fn(fn_stmt, function);
+
+        let mut values = function.get_param_iter();
+        if let Some(closure) = closure {
+            let env = values
+                .next()
+                .expect("Closures take their environment first")
+                .into_pointer_value();
+            for (index, (name, ty)) in closure.captures.iter().enumerate() {
+                let field = self
+                    .builder
+                    .build_struct_gep(closure.ty, env, index as u32, name)?;
+                let value = self.builder.build_load(*ty, field, name)?;
+                let ptr = self.build_entry_alloca(*ty, name)?;
+                self.builder.build_store(ptr, value)?;
+                self.define_local(name.clone(), ptr, *ty);
+            }
+        }
+
+        let first_arg = u32::from(closure.is_some()) + 1;
+        let idents = fn_stmt
+            .receiver
+            .iter()
+            .chain(fn_stmt.params.iter().map(|param| &param.ident));
+        for (index, (ident, value)) in idents.zip(values).enumerate() {
+            let name = ident.literal();
+            let ptr = self.build_entry_alloca(value.get_type(), &name)?;
+            self.builder.build_store(ptr, value)?;
+            self.debug_declare_variable(
+                ident,
+                Some(first_arg + index as u32),
+                ptr,
+                value.get_type(),
+            );
+            self.define_local(name, ptr, value.get_type());
+        }
+
+        self.compile_block(&fn_stmt.body)?;
+
+        if !self.is_terminated() {
+            match function.get_type().get_return_type() {
+                Some(ty) => self.builder.build_return(Some(&ty.const_zero()))?,
+                None => self.builder.build_return(None)?,
+            };
+        }
+
+        self.scopes.pop();
+        self.current_fn = None;
+        self.debug_exit_fn();
+
+        Ok(())
+    }
+
+    /// Declares the C library function `name` unless the module already did.
+    fn declare_external(&self, name: &str, fn_type: LLVMFunctionType<'ctx>) -> FunctionValue<'ctx> {
+        self.module.get_function(name).unwrap_or_else(|| {
+            self.module
+                .add_function(name, fn_type, Some(Linkage::External))
+        })
+    }
+
+    fn current_fn(&self) -> FunctionValue<'ctx> {
+        self.current_fn.expect("Not inside a function")
+    }
+
+    fn is_terminated(&self) -> bool {
+        self.builder
+            .get_insert_block()
+            .and_then(|block| block.get_terminator())
+            .is_some()
+    }
+
+    fn build_entry_alloca(
+        &self,
+        ty: BasicTypeEnum<'ctx>,
+        name: &str,
+    ) -> Result<PointerValue<'ctx>> {
+        let builder = self.context.create_builder();
+        let entry = self
+            .current_fn()
+            .get_first_basic_block()
+            .expect("Function has no entry block");
+
+        match entry.get_first_instruction() {
+            Some(instruction) => builder.position_before(&instruction),
+            None => builder.position_at_end(entry),
+        }
+
+        Ok(builder.build_alloca(ty, name)?)
+    }
+
+    fn define_local(&mut self, name: String, ptr: PointerValue<'ctx>, ty: BasicTypeEnum<'ctx>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name, Local { ptr, ty });
+        }
+    }
+
+    fn resolve_local(&self, name: &str) -> Result<Local<'ctx>> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+            .ok_or_else(|| CodegenError(format!("Undefined variable '{}'", name)).into())
+    }
+}
+
+impl<'ctx> CodeGen<'ctx> {
+    fn compile_block(&mut self, block: &Block) -> Result<()> {
+        self.scopes.push(HashMap::new());
+
+        for stmt in &block.stmts {
+            // Anything after a terminator is unreachable
+            if self.is_terminated() {
+                break;
+            }
+            self.compile_stmt(stmt)?;
+        }
+
+        self.scopes.pop();
+
+        Ok(())
+    }
+
+    fn compile_stmt(&mut self, stmt: &Stmt) -> Result<()> {
+        self.debug_stmt(stmt);
+
+        match stmt {
+            Stmt::Expr(expr) => {
+                self.compile_expr(expr)?;
+            }
+            Stmt::Let(let_stmt) => self.compile_let(let_stmt)?,
+            Stmt::If(if_stmt) => self.compile_if(if_stmt)?,
+            Stmt::Return(ret_stmt) => self.compile_return(ret_stmt)?,
+            Stmt::Block(block) => self.compile_block(block)?,
+            Stmt::While(while_stmt) => self.compile_while(while_stmt)?,
+            Stmt::Loop(loop_stmt) => self.compile_loop(loop_stmt)?,
+            Stmt::For(for_stmt) => self.compile_for(for_stmt)?,
+            Stmt::Break(break_stmt) => {
+                let target = self.loop_target(&break_stmt.label)?.break_bb;
+                self.builder.build_unconditional_branch(target)?;
+            }
+            Stmt::Continue(continue_stmt) => {
+                let target = self.loop_target(&continue_stmt.label)?.continue_bb;
+                self.builder.build_unconditional_branch(target)?;
+            }
+            Stmt::Fn(fn_stmt) => {
+                return Err(CodegenError(format!(
+                    "Nested function '{}' is not supported",
+                    fn_stmt.name
+                ))
+                .into())
+            }
+            Stmt::Struct(struct_stmt) => {
+                return Err(CodegenError(format!(
+                    "Nested struct '{}' is not supported",
+                    struct_stmt.name.literal()
+                ))
+                .into())
+            }
+            Stmt::Enum(enum_stmt) => {
+                return Err(CodegenError(format!(
+                    "Nested enum '{}' is not supported",
+                    enum_stmt.name.literal()
+                ))
+                .into())
+            }
+            Stmt::Impl(impl_stmt) => {
+                return Err(CodegenError(format!(
+                    "Nested impl for '{}' is not supported",
+                    impl_stmt.target.literal()
+                ))
+                .into())
+            }
+            Stmt::Interface(interface) => {
+                return Err(CodegenError(format!(
+                    "Nested interface '{}' is not supported",
+                    interface.name.literal()
+                ))
+                .into())
+            }
+            Stmt::Use(_) => {
+                return Err(CodegenError("'use' is only allowed at the top level".into()).into())
+            }
+        }
+
+        Ok(())
+    }
+
+    fn compile_let(&mut self, let_stmt: &Let) -> Result<()> {
+        let name = let_stmt.ident.literal();
+        let annotated = match &let_stmt.type_annotation {
+            Some(annotation) => Some(self.map_type(&annotation.type_name)?.ok_or_else(|| {
+                CodegenError(format!("Variable '{}' cannot be of type void", name))
+            })?),
+            None => None,
+        };
+
+        // An empty `[]` has no element type of its own, it takes the annotated one
+        let value = match (annotated, let_stmt.initializer.as_ref()) {
+            (Some(ty), Expr::ArrayLiteral(literal)) if literal.elements.is_empty() => {
+                ty.const_zero()
+            }
+            _ => self.compile_value(&let_stmt.initializer)?,
+        };
+        let ty = annotated.unwrap_or(value.get_type());
+
+        if ty != value.get_type() {
+            return Err(CodegenError(format!("Type mismatch in declaration of '{}'", name)).into());
+        }
+
+        let ptr = self.build_entry_alloca(ty, &name)?;
+        self.builder.build_store(ptr, value)?;
+        self.debug_declare_variable(&let_stmt.ident, None, ptr, ty);
+        self.define_local(name, ptr, ty);
+
+        Ok(())
+    }
+
+    fn compile_if(&mut self, if_stmt: &If) -> Result<()> {
+        let function = self.current_fn();
+        let merge_bb = self.context.append_basic_block(function, "if.end");
+
+        let mut branches = vec![(&if_stmt.condition, &if_stmt.then_block)];
+        branches.extend(
+            if_stmt
+                .else_ifs
+                .iter()
+                .map(|else_if| (&else_if.condition, &else_if.block)),
+        );
+
+        for (condition, block) in branches {
+            let condition = self.compile_condition(condition)?;
+            let then_bb = self.context.insert_basic_block_after(
+                self.builder.get_insert_block().expect("No insert block"),
+                "if.then",
+            );
+            let next_bb = self.context.insert_basic_block_after(then_bb, "if.else");
+            self.builder
+                .build_conditional_branch(condition, then_bb, next_bb)?;
+
+            self.builder.position_at_end(then_bb);
+            self.compile_block(block)?;
+            self.branch_if_open(merge_bb)?;
+
+            self.builder.position_at_end(next_bb);
+        }
+
+        if let Some(else_block) = &if_stmt.else_block {
+            self.compile_block(&else_block.block)?;
+        }
+        self.branch_if_open(merge_bb)?;
+
+        merge_bb
+            .move_after(self.builder.get_insert_block().expect("No insert block"))
+            .ok();
+        self.builder.position_at_end(merge_bb);
+
+        Ok(())
+    }
+
+    fn compile_while(&mut self, while_stmt: &While) -> Result<()> {
+        let function = self.current_fn();
+        let cond_bb = self.context.append_basic_block(function, "while.cond");
+        let body_bb = self.context.append_basic_block(function, "while.body");
+        let end_bb = self.context.append_basic_block(function, "while.end");
+
+        self.builder.build_unconditional_branch(cond_bb)?;
+
+        self.builder.position_at_end(cond_bb);
+        let condition = self.compile_condition(&while_stmt.condition)?;
+        self.builder
+            .build_conditional_branch(condition, body_bb, end_bb)?;
+
+        self.builder.position_at_end(body_bb);
+        self.compile_loop_body(&while_stmt.label, &while_stmt.body, end_bb, cond_bb)?;
+
+        self.builder.position_at_end(end_bb);
+
+        Ok(())
+    }
+
+    fn compile_loop(&mut self, loop_stmt: &Loop) -> Result<()> {
+        let function = self.current_fn();
+        let body_bb = self.context.append_basic_block(function, "loop.body");
+        let end_bb = self.context.append_basic_block(function, "loop.end");
+
+        self.builder.build_unconditional_branch(body_bb)?;
+
+        self.builder.position_at_end(body_bb);
+        self.compile_loop_body(&loop_stmt.label, &loop_stmt.body, end_bb, body_bb)?;
+
+        // Only reachable through `break`, otherwise the block stays without predecessors
+        self.builder.position_at_end(end_bb);
+
+        Ok(())
+    }
+
+    fn compile_for(&mut self, for_stmt: &For) -> Result<()> {
+        let range = match self.compile_value(&for_stmt.iterable)? {
+            BasicValueEnum::StructValue(range) if range.get_type() == self.range_type() => range,
+            _ => {
+                return Err(CodegenError("'for' loops can only iterate over ranges".into()).into())
+            }
+        };
+        let start = self.builder.build_extract_value(range, 0, "for.start")?;
+        let end = self
+            .builder
+            .build_extract_value(range, 1, "for.end")?
+            .into_int_value();
+        let inclusive = self
+            .builder
+            .build_extract_value(range, 2, "for.inclusive")?
+            .into_int_value();
+
+        let function = self.current_fn();
+        let cond_bb = self.context.append_basic_block(function, "for.cond");
+        let body_bb = self.context.append_basic_block(function, "for.body");
+        let step_bb = self.context.append_basic_block(function, "for.step");
+        let end_bb = self.context.append_basic_block(function, "for.end");
+
+        let int_type = self.context.i64_type();
+        let counter = self.build_entry_alloca(int_type.into(), "for.counter")?;
+        self.builder.build_store(counter, start)?;
+        self.builder.build_unconditional_branch(cond_bb)?;
+
+        self.builder.position_at_end(cond_bb);
+        let i = self
+            .builder
+            .build_load(int_type, counter, "i")?
+            .into_int_value();
+        let below = self
+            .builder
+            .build_int_compare(IntPredicate::SLT, i, end, "for.below")?;
+        let at_most = self
+            .builder
+            .build_int_compare(IntPredicate::SLE, i, end, "for.at_most")?;
+        let condition = self
+            .builder
+            .build_select(inclusive, at_most, below, "for.cond")?
+            .into_int_value();
+        self.builder
+            .build_conditional_branch(condition, body_bb, end_bb)?;
+
+        self.builder.position_at_end(body_bb);
+        self.scopes.push(HashMap::new());
+        let name = for_stmt.ident.literal();
+        let ptr = self.build_entry_alloca(int_type.into(), &name)?;
+        self.builder.build_store(ptr, i)?;
+        self.debug_declare_variable(&for_stmt.ident, None, ptr, int_type.into());
+        self.define_local(name, ptr, int_type.into());
+        let result = self.compile_loop_body(&for_stmt.label, &for_stmt.body, end_bb, step_bb);
+        self.scopes.pop();
+        result?;
+
+        // Stop after the last value of an inclusive range so `..=` never wraps around
+        self.builder.position_at_end(step_bb);
+        let i = self
+            .builder
+            .build_load(int_type, counter, "i")?
+            .into_int_value();
+        let is_end = self
+            .builder
+            .build_int_compare(IntPredicate::EQ, i, end, "for.is_end")?;
+        let done = self.builder.build_and(inclusive, is_end, "for.done")?;
+        let next = self
+            .builder
+            .build_int_add(i, int_type.const_int(1, false), "for.next")?;
+        self.builder.build_store(counter, next)?;
+        self.builder
+            .build_conditional_branch(done, end_bb, cond_bb)?;
+
+        self.builder.position_at_end(end_bb);
+
+        Ok(())
+    }
+
+    /// Compiles `body` with `break`/`continue` jumping to the given blocks, then loops
+    /// back to `continue_bb` unless the body already left.
+    fn compile_loop_body(
+        &mut self,
+        label: &Option<Token>,
+        body: &Block,
+        break_bb: BasicBlock<'ctx>,
+        continue_bb: BasicBlock<'ctx>,
+    ) -> Result<()> {
+        self.loops.push(LoopTarget {
+            label: label.as_ref().map(|label| label.literal()),
+            break_bb,
+            continue_bb,
+        });
+        let result = self.compile_block(body);
+        self.loops.pop();
+        result?;
+
+        self.branch_if_open(continue_bb)
+    }
+
+    fn loop_target(&self, label: &Option<Token>) -> Result<&LoopTarget<'ctx>> {
+        let target = match label {
+            Some(label) => {
+                let name = label.literal();
+                self.loops
+                    .iter()
+                    .rev()
+                    .find(|target| target.label.as_ref() == Some(&name))
+            }
+            None => self.loops.last(),
+        };
+
+        target.ok_or_else(|| CodegenError("'break' or 'continue' outside of a loop".into()).into())
+    }
+
+    fn branch_if_open(&self, target: BasicBlock<'ctx>) -> Result<()> {
+        if !self.is_terminated() {
+            self.builder.build_unconditional_branch(target)?;
+        }
+
+        Ok(())
+    }
+
+    fn compile_condition(&mut self, condition: &Expr) -> Result<IntValue<'ctx>> {
+        match self.compile_value(condition)? {
+            BasicValueEnum::IntValue(value) if value.get_type().get_bit_width() == 1 => Ok(value),
+            _ => Err(CodegenError("Condition must be of type bool".into()).into()),
+        }
+    }
+
+    fn compile_return(&mut self, ret_stmt: &Return) -> Result<()> {
+        match &ret_stmt.expr {
+            Some(expr) => {
+                let value = self.compile_value(expr)?;
+                self.builder.build_return(Some(&value))?;
+            }
+            None => {
+                self.builder.build_return(None)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'ctx> CodeGen<'ctx> {
+    fn compile_value(&mut self, expr: &Expr) -> Result<BasicValueEnum<'ctx>> {
+        self.compile_expr(expr)?
+            .ok_or_else(|| CodegenError("Expression does not produce a value".into()).into())
+    }
+
+    fn compile_expr(&mut self, expr: &Expr) -> Result<Option<BasicValueEnum<'ctx>>> {
+        let value: BasicValueEnum = match expr {
+            Expr::Literal(lit) => match &lit.value {
+                LiteralType::Int(i) => self.context.i64_type().const_int(*i as u64, true).into(),
+                LiteralType::Float(f) => self.context.f64_type().const_float(*f).into(),
+                LiteralType::Bool(b) => self.context.bool_type().const_int(*b as u64, false).into(),
+                LiteralType::String(s) => self
+                    .builder
+                    .build_global_string_ptr(s, "str")?
+                    .as_pointer_value()
+                    .into(),
+                LiteralType::Null => {
+                    return Err(CodegenError("'null' is not supported".into()).into())
+                }
+            },
+            Expr::Variable(var) => match self.resolve_local(&var.ident) {
+                Ok(local) => self.builder.build_load(local.ty, local.ptr, &var.ident)?,
+                Err(_) if self.variants.contains_key(&var.ident) => {
+                    self.build_variant(&var.ident, vec![])?
+                }
+                Err(err) => match self.module.get_function(&var.ident) {
+                    Some(function) => self.build_function_value(function)?,
+                    None => return Err(err),
+                },
+            },
+            Expr::Binary(bin) => return self.compile_binary(bin).map(Some),
+            Expr::Unary(un) => self.compile_unary(un)?,
+            Expr::Logical(logical) => {
+                let operator = match logical.operator {
+                    LogicalOp::And => BinOpKind::And,
+                    LogicalOp::Or => BinOpKind::Or,
+                };
+                self.compile_short_circuit(&logical.left, operator, &logical.right)?
+            }
+            Expr::Parenthesized(paren) => return self.compile_expr(&paren.expr),
+            Expr::Call(call) => return self.compile_call(call),
+            Expr::Range(range) => {
+                let start = self.compile_value(&range.start)?;
+                let end = self.compile_value(&range.end)?;
+                let inclusive = self
+                    .context
+                    .bool_type()
+                    .const_int(range.inclusive as u64, false);
+
+                let mut value = self.range_type().get_undef();
+                for (index, field) in [start, end, inclusive.into()].into_iter().enumerate() {
+                    value = self
+                        .builder
+                        .build_insert_value(value, field, index as u32, "range")?
+                        .into_struct_value();
+                }
+                value.into()
+            }
+            Expr::Assign(assign) => {
+                let place = self.compile_place(&assign.target)?;
+                let value = self.compile_value(&assign.value)?;
+                if value.get_type() != place.ty {
+                    return Err(CodegenError(format!(
+                        "Type mismatch in assignment to '{}'",
+                        assign.target.span().literal
+                    ))
+                    .into());
+                }
+                self.builder.build_store(place.ptr, value)?;
+                value
+            }
+            Expr::StructLiteral(literal) => self.compile_struct_literal(literal)?,
+            Expr::Match(match_expr) => return self.compile_match(match_expr),
+            Expr::ArrayLiteral(literal) => self.compile_array_literal(literal)?,
+            Expr::Index(index) => {
+                let element = self.compile_element(index)?;
+                self.builder
+                    .build_load(element.ty, element.ptr, "element")?
+            }
+            Expr::Member(member) => {
+                let object = match self.compile_value(&member.object)? {
+                    BasicValueEnum::StructValue(object) => object,
+                    _ => {
+                        return Err(CodegenError(format!(
+                            "Cannot access field '{}' on a non-struct value",
+                            member.field.literal()
+                        ))
+                        .into())
+                    }
+                };
+                let index = self.field_index(object.get_type(), &member.field)?;
+                self.builder
+                    .build_extract_value(object, index, &member.field.literal())?
+            }
+            Expr::Closure(closure) => self.compile_closure(closure)?,
+            Expr::Path(path) => {
+                return Err(CodegenError(format!(
+                    "Function '{}' has to be called",
+                    qualified_name(&path.type_name.literal(), &path.name.literal())
+                ))
+                .into())
+            }
+        };
+
+        Ok(Some(value))
+    }
+
+    fn compile_struct_literal(&mut self, literal: &StructLiteral) -> Result<BasicValueEnum<'ctx>> {
+        let name = literal.name.literal();
+
+        // Evaluated in source order, inserted at their declared position
+        let mut fields = vec![];
+        for init in &literal.fields {
+            fields.push(self.compile_value(&init.value)?);
+        }
+
+        let ty = match self.generic_structs.get(&name).cloned() {
+            // The type arguments of a generic struct come from its field values
+            Some(struct_stmt) => {
+                let mut bindings = HashMap::new();
+                for (init, field) in literal.fields.iter().zip(&fields) {
+                    let declared = struct_stmt
+                        .fields
+                        .iter()
+                        .find(|declared| declared.ident.literal() == init.ident.literal());
+                    if let Some(declared) = declared {
+                        self.bind_type_args(
+                            &declared.type_annotation.type_name,
+                            field.get_type(),
+                            &struct_stmt.type_params,
+                            &mut bindings,
+                        );
+                    }
+                }
+                let args = type_args(&struct_stmt.type_params, &bindings, &name)?;
+                self.struct_instance(&name, args)?
+            }
+            None => self
+                .structs
+                .get(&name)
+                .map(|layout| layout.ty)
+                .ok_or_else(|| CodegenError(format!("Undefined struct '{}'", name)))?,
+        };
+
+        let mut value = ty.get_undef();
+        for (init, field) in literal.fields.iter().zip(fields) {
+            let index = self.field_index(ty, &init.ident)?;
+            if ty.get_field_type_at_index(index) != Some(field.get_type()) {
+                return Err(CodegenError(format!(
+                    "Type mismatch in field '{}' of '{}'",
+                    init.ident.literal(),
+                    name
+                ))
+                .into());
+            }
+
+            value = self
+                .builder
+                .build_insert_value(value, field, index, &name)?
+                .into_struct_value();
+        }
+
+        Ok(value.into())
+    }
+
+    /// Binds the type parameters `type_name` mentions to the parts of `ty` they stand
+    /// for, keeping the first binding of each.
+    fn bind_type_args(
+        &self,
+        type_name: &TypeName,
+        ty: BasicTypeEnum<'ctx>,
+        type_params: &[GenericParam],
+        bindings: &mut HashMap<String, BasicTypeEnum<'ctx>>,
+    ) {
+        match type_name {
+            TypeName::Named(name) => {
+                let name = name.literal();
+                if type_params.iter().any(|param| param.name.literal() == name) {
+                    bindings.entry(name).or_insert(ty);
+                }
+            }
+            TypeName::Array { element, .. } => {
+                if let Some(element_ty) = self.array_element(ty) {
+                    self.bind_type_args(element, element_ty, type_params, bindings);
+                }
+            }
+            TypeName::Generic { args, .. } => {
+                let instance_args = match ty {
+                    BasicTypeEnum::StructType(ty) => ty
+                        .get_name()
+                        .and_then(|name| self.struct_args.get(name.to_str().ok()?)),
+                    _ => None,
+                };
+                for (arg, arg_ty) in args.iter().zip(instance_args.into_iter().flatten()) {
+                    self.bind_type_args(arg, *arg_ty, type_params, bindings);
+                }
+            }
+            TypeName::Function {
+                params,
+                return_type,
+                ..
+            } => {
+                let Some(signature) = self.closure_signature(ty) else {
+                    return;
+                };
+                // The first parameter is the `env` of the closure
+                for (param, param_ty) in params
+                    .iter()
+                    .zip(signature.get_param_types().into_iter().skip(1))
+                {
+                    self.bind_type_args(param, param_ty, type_params, bindings);
+                }
+                if let (Some(return_type), Some(return_ty)) =
+                    (return_type, signature.get_return_type())
+                {
+                    self.bind_type_args(return_type, return_ty, type_params, bindings);
+                }
+            }
+        }
+    }
+
+    fn compile_array_literal(&mut self, literal: &ArrayLiteral) -> Result<BasicValueEnum<'ctx>> {
+        let mut elements = vec![];
+        for element in &literal.elements {
+            elements.push(self.compile_value(element)?);
+        }
+        let Some(first) = elements.first() else {
+            return Err(CodegenError(
+                "Cannot infer the element type of an empty array".to_string(),
+            )
+            .into());
+        };
+        let element_type = first.get_type();
+        if elements
+            .iter()
+            .any(|value| value.get_type() != element_type)
+        {
+            return Err(CodegenError("Array elements must have the same type".into()).into());
+        }
+
+        let int_type = self.context.i64_type();
+        let len = match &literal.repeat {
+            Some(count) => match count.kind {
+                TokenKind::Integer(count) => int_type.const_int(count as u64, false),
+                _ => return Err(CodegenError("Array length must be an integer".into()).into()),
+            },
+            None => int_type.const_int(elements.len() as u64, false),
+        };
+        let data = self.build_array_alloc(element_type, len)?;
+
+        match &literal.repeat {
+            Some(_) => self.build_array_fill(element_type, data, len, elements[0])?,
+            None => {
+                for (index, value) in elements.into_iter().enumerate() {
+                    let index = int_type.const_int(index as u64, false);
+                    // SAFETY: `index` is below the length `data` was allocated with
+                    let ptr = unsafe {
+                        self.builder
+                            .build_in_bounds_gep(element_type, data, &[index], "element")?
+                    };
+                    self.builder.build_store(ptr, value)?;
+                }
+            }
+        }
+
+        let array_type = self.array_type(element_type);
+        let mut value = self
+            .builder
+            .build_insert_value(array_type.get_undef(), data, 0, "array")?
+            .into_struct_value();
+        value = self
+            .builder
+            .build_insert_value(value, len, 1, "array")?
+            .into_struct_value();
+
+        Ok(value.into())
+    }
+
+    /// Allocates room for `len` elements with `malloc`. Arrays are never freed yet.
+    fn build_array_alloc(
+        &mut self,
+        element_type: BasicTypeEnum<'ctx>,
+        len: IntValue<'ctx>,
+    ) -> Result<PointerValue<'ctx>> {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let int_type = self.context.i64_type();
+        let malloc = self.declare_external("malloc", ptr_type.fn_type(&[int_type.into()], false));
+
+        let element_size = element_type
+            .size_of()
+            .ok_or_else(|| CodegenError("Array elements must have a size".into()))?;
+        let size = self.builder.build_int_mul(len, element_size, "size")?;
+
+        Ok(self
+            .builder
+            .build_call(malloc, &[size.into()], "data")?
+            .try_as_basic_value()
+            .left()
+            .expect("malloc returns a value")
+            .into_pointer_value())
+    }
+
+    /// Stores `value` into each of the `len` elements at `data`, for `[value; len]`.
+    fn build_array_fill(
+        &mut self,
+        element_type: BasicTypeEnum<'ctx>,
+        data: PointerValue<'ctx>,
+        len: IntValue<'ctx>,
+        value: BasicValueEnum<'ctx>,
+    ) -> Result<()> {
+        let function = self.current_fn();
+        let entry_bb = self.builder.get_insert_block().expect("No insert block");
+        let cond_bb = self.context.append_basic_block(function, "fill.cond");
+        let body_bb = self.context.append_basic_block(function, "fill.body");
+        let end_bb = self.context.append_basic_block(function, "fill.end");
+
+        let int_type = self.context.i64_type();
+        self.builder.build_unconditional_branch(cond_bb)?;
+
+        self.builder.position_at_end(cond_bb);
+        let i = self.builder.build_phi(int_type, "i")?;
+        let i_value = i.as_basic_value().into_int_value();
+        let below =
+            self.builder
+                .build_int_compare(IntPredicate::ULT, i_value, len, "fill.below")?;
+        self.builder
+            .build_conditional_branch(below, body_bb, end_bb)?;
+
+        self.builder.position_at_end(body_bb);
+        // SAFETY: `i` is below the length `data` was allocated with
+        let ptr = unsafe {
+            self.builder
+                .build_in_bounds_gep(element_type, data, &[i_value], "element")?
+        };
+        self.builder.build_store(ptr, value)?;
+        let next =
+            self.builder
+                .build_int_add(i_value, int_type.const_int(1, false), "fill.next")?;
+        self.builder.build_unconditional_branch(cond_bb)?;
+
+        i.add_incoming(&[(&int_type.const_zero(), entry_bb), (&next, body_bb)]);
+
+        self.builder.position_at_end(end_bb);
+
+        Ok(())
+    }
+
+    /// The address of `object[index]`, after checking that the index is in bounds.
+    fn compile_element(&mut self, index: &Index) -> Result<Local<'ctx>> {
+        let BasicValueEnum::StructValue(array) = self.compile_value(&index.object)? else {
+            return Err(CodegenError("Cannot index into a non-array value".into()).into());
+        };
+        let element_type = self
+            .array_element(array.get_type().into())
+            .ok_or_else(|| CodegenError("Cannot index into a non-array value".into()))?;
+        let BasicValueEnum::IntValue(i) = self.compile_value(&index.index)? else {
+            return Err(CodegenError("Array index must be of type int".into()).into());
+        };
+
+        let data = self
+            .builder
+            .build_extract_value(array, 0, "data")?
+            .into_pointer_value();
+        let len = self
+            .builder
+            .build_extract_value(array, 1, "len")?
+            .into_int_value();
+        self.build_bounds_check(i, len, &index.index.span())?;
+
+        // SAFETY: the bounds check above stopped the program unless `i < len`
+        let ptr = unsafe {
+            self.builder
+                .build_in_bounds_gep(element_type, data, &[i], "element")?
+        };
+
+        Ok(Local {
+            ptr,
+            ty: element_type,
+        })
+    }
+
+    /// Stops the program with an error pointing at `span` unless `0 <= index < len`.
+    /// Comparing unsigned also catches negative indices.
+    fn build_bounds_check(
+        &mut self,
+        index: IntValue<'ctx>,
+        len: IntValue<'ctx>,
+        span: &TextSpan,
+    ) -> Result<()> {
+        let function = self.current_fn();
+        let fail_bb = self.context.append_basic_block(function, "bounds.fail");
+        let ok_bb = self.context.append_basic_block(function, "bounds.ok");
+
+        let in_bounds =
+            self.builder
+                .build_int_compare(IntPredicate::ULT, index, len, "in_bounds")?;
+        self.builder
+            .build_conditional_branch(in_bounds, ok_bb, fail_bb)?;
+
+        self.builder.position_at_end(fail_bb);
+        let panic = match self.module.get_function("pulse.index_out_of_bounds") {
+            Some(panic) => panic,
+            None => self.define_index_out_of_bounds()?,
+        };
+        let location = format!("{}:{}", span.start.line, span.start.column);
+        let location = self
+            .builder
+            .build_global_string_ptr(&location, "location")?;
+        self.builder.build_call(
+            panic,
+            &[index.into(), len.into(), location.as_pointer_value().into()],
+            "",
+        )?;
+        self.builder.build_unreachable()?;
+
+        self.builder.position_at_end(ok_bb);
+
+        Ok(())
+    }
+
+    /// Prints the same error as the interpreter to stderr and exits with
+    /// `RUNTIME_ERROR_EXIT_CODE`.
+    fn define_index_out_of_bounds(&mut self) -> Result<FunctionValue<'ctx>> {
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+
+        let dprintf = self.declare_external(
+            "dprintf",
+            i32_type.fn_type(&[i32_type.into(), ptr_type.into()], true),
+        );
+        let exit = self.declare_external(
+            "exit",
+            self.context.void_type().fn_type(&[i32_type.into()], false),
+        );
+
+        let function = self.module.add_function(
+            "pulse.index_out_of_bounds",
+            self.context
+                .void_type()
+                .fn_type(&[i64_type.into(), i64_type.into(), ptr_type.into()], false),
+            Some(Linkage::Internal),
+        );
+        let builder = self.context.create_builder();
+        let entry = self.context.append_basic_block(function, "entry");
+        builder.position_at_end(entry);
+
+        let format = builder.build_global_string_ptr(
+            "error: Index %lld out of bounds for array of length %lld\n---> %s\n",
+            "fmt",
+        )?;
+        let index = function.get_nth_param(0).unwrap();
+        let len = function.get_nth_param(1).unwrap();
+        let location = function.get_nth_param(2).unwrap();
+        builder.build_call(
+            dprintf,
+            &[
+                i32_type.const_int(2, false).into(),
+                format.as_pointer_value().into(),
+                index.into(),
+                len.into(),
+                location.into(),
+            ],
+            "",
+        )?;
+        builder.build_call(
+            exit,
+            &[i32_type
+                .const_int(RUNTIME_ERROR_EXIT_CODE as u64, false)
+                .into()],
+            "",
+        )?;
+        builder.build_unreachable()?;
+
+        Ok(function)
+    }
+
+    fn build_variant(
+        &mut self,
+        variant: &str,
+        values: Vec<BasicValueEnum<'ctx>>,
+    ) -> Result<BasicValueEnum<'ctx>> {
+        let (ty, tag, layout) = self
+            .variant_layout(variant)
+            .ok_or_else(|| CodegenError(format!("Undefined variant '{}'", variant)))?;
+        let fields = layout.fields.clone();
+
+        if fields.len() != values.len() {
+            return Err(CodegenError(format!(
+                "Variant '{}' holds {} value(s), found {}",
+                variant,
+                fields.len(),
+                values.len()
+            ))
+            .into());
+        }
+
+        let tag = self.context.i64_type().const_int(tag, false);
+        let mut value = self
+            .builder
+            .build_insert_value(ty.get_undef(), tag, 0, variant)?
+            .into_struct_value();
+        for (index, field) in fields.into_iter().zip(values) {
+            if ty.get_field_type_at_index(index) != Some(field.get_type()) {
+                return Err(
+                    CodegenError(format!("Type mismatch in value of '{}'", variant)).into(),
+                );
+            }
+
+            value = self
+                .builder
+                .build_insert_value(value, field, index, variant)?
+                .into_struct_value();
+        }
+
+        Ok(value.into())
+    }
+
+    /// Tests the arms in order, each one binding its pattern variables in a new scope.
+    /// Arms that produce a value meet in a phi, so the `match` only has a value when all
+    /// of the arms that don't leave early produce one.
+    fn compile_match(&mut self, match_expr: &Match) -> Result<Option<BasicValueEnum<'ctx>>> {
+        let scrutinee = self.compile_value(&match_expr.scrutinee)?;
+
+        let function = self.current_fn();
+        let end_bb = self.context.append_basic_block(function, "match.end");
+
+        let mut incoming = vec![];
+        let mut produces_value = true;
+        for arm in &match_expr.arms {
+            let arm_bb = self.context.append_basic_block(function, "match.arm");
+            let next_bb = self.context.append_basic_block(function, "match.next");
+
+            let matches = self.compile_pattern_test(&arm.pattern, scrutinee)?;
+            self.builder
+                .build_conditional_branch(matches, arm_bb, next_bb)?;
+
+            self.builder.position_at_end(arm_bb);
+            self.scopes.push(HashMap::new());
+            self.bind_pattern(&arm.pattern, scrutinee)?;
+            let value = match &arm.body {
+                ArmBody::Expr(expr) => self.compile_expr(expr),
+                ArmBody::Block(block) => self.compile_block(block).map(|_| None),
+            };
+            self.scopes.pop();
+            let value = value?;
+
+            if !self.is_terminated() {
+                match value {
+                    Some(value) => incoming.push((
+                        value,
+                        self.builder.get_insert_block().expect("No insert block"),
+                    )),
+                    None => produces_value = false,
+                }
+                self.builder.build_unconditional_branch(end_bb)?;
+            }
+
+            self.builder.position_at_end(next_bb);
+        }
+
+        // No arm matched, which the resolver's exhaustiveness check rules out
+        self.builder.build_unreachable()?;
+
+        end_bb
+            .move_after(self.builder.get_insert_block().expect("No insert block"))
+            .ok();
+        self.builder.position_at_end(end_bb);
+
+        let Some((first, _)) = incoming.first() else {
+            return Ok(None);
+        };
+        let ty = first.get_type();
+        if !produces_value || incoming.iter().any(|(value, _)| value.get_type() != ty) {
+            return Ok(None);
+        }
+
+        let phi = self.builder.build_phi(ty, "match")?;
+        for (value, block) in &incoming {
+            phi.add_incoming(&[(value, *block)]);
+        }
+
+        Ok(Some(phi.as_basic_value()))
+    }
+
+    fn compile_pattern_test(
+        &mut self,
+        pattern: &Pattern,
+        scrutinee: BasicValueEnum<'ctx>,
+    ) -> Result<IntValue<'ctx>> {
+        match pattern {
+            Pattern::Wildcard(_) => Ok(self.context.bool_type().const_int(1, false)),
+            Pattern::Literal(literal) => {
+                let expected = self.compile_value(&Expr::Literal(literal.clone()))?;
+                Ok(self
+                    .build_binary_op(BinOpKind::Equals, scrutinee, expected)?
+                    .into_int_value())
+            }
+            Pattern::Variant { name, .. } => {
+                let (_, tag, _) = self.variant_layout(&name.literal()).ok_or_else(|| {
+                    CodegenError(format!("Undefined variant '{}'", name.literal()))
+                })?;
+                let BasicValueEnum::StructValue(scrutinee) = scrutinee else {
+                    return Err(CodegenError("Only enums can match a variant".into()).into());
+                };
+
+                let actual = self
+                    .builder
+                    .build_extract_value(scrutinee, 0, "tag")?
+                    .into_int_value();
+                let expected = self.context.i64_type().const_int(tag, false);
+                Ok(self.builder.build_int_compare(
+                    IntPredicate::EQ,
+                    actual,
+                    expected,
+                    "is_variant",
+                )?)
+            }
+        }
+    }
+
+    fn bind_pattern(&mut self, pattern: &Pattern, scrutinee: BasicValueEnum<'ctx>) -> Result<()> {
+        let (Pattern::Variant { name, bindings }, BasicValueEnum::StructValue(scrutinee)) =
+            (pattern, scrutinee)
+        else {
+            return Ok(());
+        };
+        let fields = match self.variant_layout(&name.literal()) {
+            Some((_, _, layout)) => layout.fields.clone(),
+            None => return Ok(()),
+        };
+
+        for (binding, index) in bindings.iter().zip(fields) {
+            let binding_name = binding.literal();
+            if binding_name == "_" {
+                continue;
+            }
+
+            let value = self
+                .builder
+                .build_extract_value(scrutinee, index, &binding_name)?;
+            let ptr = self.build_entry_alloca(value.get_type(), &binding_name)?;
+            self.builder.build_store(ptr, value)?;
+            self.debug_declare_variable(binding, None, ptr, value.get_type());
+            self.define_local(binding_name, ptr, value.get_type());
+        }
+
+        Ok(())
+    }
+
+    /// The storage an assignment target refers to, a variable or a field or element
+    /// nested in one.
+    fn compile_place(&mut self, target: &Expr) -> Result<Local<'ctx>> {
+        match target {
+            Expr::Variable(var) => self.resolve_local(&var.ident),
+            Expr::Member(member) => {
+                let object = self.compile_place(&member.object)?;
+                let BasicTypeEnum::StructType(ty) = object.ty else {
+                    return Err(CodegenError(format!(
+                        "Cannot access field '{}' on a non-struct value",
+                        member.field.literal()
+                    ))
+                    .into());
+                };
+
+                let index = self.field_index(ty, &member.field)?;
+                let ptr = self.builder.build_struct_gep(
+                    ty,
+                    object.ptr,
+                    index,
+                    &member.field.literal(),
+                )?;
+                let field_ty = ty
+                    .get_field_type_at_index(index)
+                    .expect("Field index comes from the layout");
+
+                Ok(Local { ptr, ty: field_ty })
+            }
+            Expr::Index(index) => self.compile_element(index),
+            _ => Err(CodegenError(format!("Cannot assign to '{}'", target.span().literal)).into()),
+        }
+    }
+
+    fn compile_call(&mut self, call: &CallExpr) -> Result<Option<BasicValueEnum<'ctx>>> {
+        let mut args: Vec<BasicMetadataValueEnum> = vec![];
+        let name = match call.callee.as_ref() {
+            Expr::Variable(var) if var.ident == "len" => return self.compile_len(call).map(Some),
+            Expr::Variable(var) if self.variants.contains_key(&var.ident) => {
+                let mut values = vec![];
+                for arg in &call.args {
+                    values.push(self.compile_value(arg)?);
+                }
+
+                return self.build_variant(&var.ident, values).map(Some);
+            }
+            // Locals holding functions shadow the top-level ones
+            Expr::Variable(var) if self.resolve_local(&var.ident).is_ok() => {
+                let callee = self.compile_value(&call.callee)?;
+                return self.build_closure_call(callee, call);
+            }
+            Expr::Variable(var) if self.generic_fns.contains_key(&var.ident) => {
+                let mut values = vec![];
+                for arg in &call.args {
+                    values.push(self.compile_value(arg)?);
+                }
+
+                let function = self.fn_instance(&var.ident, &values)?;
+                let args: Vec<BasicMetadataValueEnum> =
+                    values.into_iter().map(|value| value.into()).collect();
+                let call_site = self.builder.build_call(function, &args, "call")?;
+                return Ok(call_site.try_as_basic_value().left());
+            }
+            Expr::Variable(var) => var.ident.clone(),
+            // `p.length()` calls `Point::length` with `p` as its first argument, unless
+            // there's no such method and `length` is a field holding a function
+            Expr::Member(member) => {
+                let receiver = self.compile_value(&member.object)?;
+                let type_name = match receiver {
+                    BasicValueEnum::StructValue(value) => value
+                        .get_type()
+                        .get_name()
+                        .and_then(|name| name.to_str().ok())
+                        .map(str::to_string),
+                    _ => None,
+                }
+                .ok_or_else(|| {
+                    CodegenError(format!(
+                        "No method '{}' on this type",
+                        member.field.literal()
+                    ))
+                })?;
+                let name = qualified_name(&type_name, &member.field.literal());
+                if self.module.get_function(&name).is_none() {
+                    let object = receiver.into_struct_value();
+                    let index = self.field_index(object.get_type(), &member.field)?;
+                    let callee =
+                        self.builder
+                            .build_extract_value(object, index, &member.field.literal())?;
+                    return self.build_closure_call(callee, call);
+                }
+                args.push(receiver.into());
+                name
+            }
+            Expr::Path(path) => qualified_name(&path.type_name.literal(), &path.name.literal()),
+            other => {
+                let callee = self.compile_value(other)?;
+                return self.build_closure_call(callee, call);
+            }
+        };
+
+        let function = self
+            .module
+            .get_function(&name)
+            .ok_or_else(|| CodegenError(format!("Undefined function '{}'", name)))?;
+
+        if function.count_params() as usize != args.len() + call.args.len() {
+            return Err(CodegenError(format!(
+                "Function '{}' expects {} arguments, found {}",
+                name,
+                function.count_params(),
+                args.len() + call.args.len()
+            ))
+            .into());
+        }
+
+        for arg in &call.args {
+            args.push(self.compile_value(arg)?.into());
+        }
+
+        let call_site = self.builder.build_call(function, &args, "call")?;
+
+        Ok(call_site.try_as_basic_value().left())
+    }
+
+    /// The instance of the generic function `name` for the types of `args`, like
+    /// `max<i64>`. It's declared the first time it's needed and compiled later.
+    fn fn_instance(
+        &mut self,
+        name: &str,
+        args: &[BasicValueEnum<'ctx>],
+    ) -> Result<FunctionValue<'ctx>> {
+        let fn_stmt = self.generic_fns[name].clone();
+        if fn_stmt.params.len() != args.len() {
+            return Err(CodegenError(format!(
+                "Function '{}' expects {} arguments, found {}",
+                name,
+                fn_stmt.params.len(),
+                args.len()
+            ))
+            .into());
+        }
+
+        let mut bindings = HashMap::new();
+        for (param, arg) in fn_stmt.params.iter().zip(args) {
+            self.bind_type_args(
+                &param.type_annotation.type_name,
+                arg.get_type(),
+                &fn_stmt.type_params,
+                &mut bindings,
+            );
+        }
+        let arg_names: Vec<String> = type_args(&fn_stmt.type_params, &bindings, name)?
+            .iter()
+            .map(|arg| type_key(*arg))
+            .collect();
+        let instance = format!("{}<{}>", name, arg_names.join(", "));
+        if let Some(function) = self.module.get_function(&instance) {
+            return Ok(function);
+        }
+
+        let enclosing_type_args = std::mem::replace(&mut self.type_args, bindings.clone());
+        let fn_type = self.fn_type(&fn_stmt, None);
+        self.type_args = enclosing_type_args;
+
+        let function = self
+            .module
+            .add_function(&instance, fn_type?, Some(Linkage::Internal));
+        for (param, value) in fn_stmt.params.iter().zip(function.get_param_iter()) {
+            value.set_name(&param.ident.literal());
+        }
+        self.pending_fns.push(PendingFn {
+            function: fn_stmt,
+            value: function,
+            type_args: bindings,
+            env: None,
+            declaration: name.to_string(),
+        });
+
+        Ok(function)
+    }
+
+    /// Calls a function value, passing its `env` before the arguments.
+    fn build_closure_call(
+        &mut self,
+        callee: BasicValueEnum<'ctx>,
+        call: &CallExpr,
+    ) -> Result<Option<BasicValueEnum<'ctx>>> {
+        let signature = self
+            .closure_signature(callee.get_type())
+            .ok_or_else(|| CodegenError("Cannot call a value of this type".to_string()))?;
+        let expected = signature.count_param_types() as usize - 1;
+        if expected != call.args.len() {
+            return Err(CodegenError(format!(
+                "Function '{}' expects {} arguments, found {}",
+                call.token.literal(),
+                expected,
+                call.args.len()
+            ))
+            .into());
+        }
+
+        let closure = callee.into_struct_value();
+        let function = self
+            .builder
+            .build_extract_value(closure, 0, "function")?
+            .into_pointer_value();
+        let env = self.builder.build_extract_value(closure, 1, "env")?;
+
+        let mut args: Vec<BasicMetadataValueEnum> = vec![env.into()];
+        for arg in &call.args {
+            args.push(self.compile_value(arg)?.into());
+        }
+
+        let call_site = self
+            .builder
+            .build_indirect_call(signature, function, &args, "call")?;
+
+        Ok(call_site.try_as_basic_value().left())
+    }
+
+    /// Creates the function value of a closure, copying every variable in scope into a
+    /// heap allocated environment. The body is compiled after the current function.
+    fn compile_closure(&mut self, closure: &Closure) -> Result<BasicValueEnum<'ctx>> {
+        let signature = self.fn_type(&closure.function, None)?;
+        let value = self.module.add_function(
+            &closure.function.name,
+            self.with_env(signature),
+            Some(Linkage::Internal),
+        );
+
+        // Inner scopes shadow outer ones
+        let mut visible: HashMap<&String, Local<'ctx>> = HashMap::new();
+        for scope in &self.scopes {
+            visible.extend(scope.iter().map(|(name, local)| (name, *local)));
+        }
+        let mut captures: Vec<(String, Local<'ctx>)> = visible
+            .into_iter()
+            .map(|(name, local)| (name.clone(), local))
+            .collect();
+        captures.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let field_types: Vec<BasicTypeEnum> = captures.iter().map(|(_, local)| local.ty).collect();
+        let env_type = self.context.struct_type(&field_types, false);
+        let env = if captures.is_empty() {
+            self.context.ptr_type(AddressSpace::default()).const_null()
+        } else {
+            let one = self.context.i64_type().const_int(1, false);
+            self.build_array_alloc(env_type.into(), one)?
+        };
+        for (index, (name, local)) in captures.iter().enumerate() {
+            let value = self.builder.build_load(local.ty, local.ptr, name)?;
+            let field = self
+                .builder
+                .build_struct_gep(env_type, env, index as u32, name)?;
+            self.builder.build_store(field, value)?;
+        }
+
+        self.pending_fns.push(PendingFn {
+            function: closure.function.as_ref().clone(),
+            value,
+            type_args: self.type_args.clone(),
+            env: Some(ClosureEnv {
+                captures: captures
+                    .into_iter()
+                    .map(|(name, local)| (name, local.ty))
+                    .collect(),
+                ty: env_type,
+            }),
+            declaration: self.declaration.clone(),
+        });
+
+        self.build_closure(value, env)
+    }
+
+    /// The function value of the top-level `function`, calling it through a thunk that
+    /// takes and ignores the `env` every function value is called with.
+    fn build_function_value(
+        &mut self,
+        function: FunctionValue<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>> {
+        let name = format!("{}.closure", function.get_name().to_string_lossy());
+        let thunk = match self.module.get_function(&name) {
+            Some(thunk) => thunk,
+            None => self.define_thunk(&name, function)?,
+        };
+
+        let env = self.context.ptr_type(AddressSpace::default()).const_null();
+        self.build_closure(thunk, env)
+    }
+
+    fn define_thunk(
+        &mut self,
+        name: &str,
+        function: FunctionValue<'ctx>,
+    ) -> Result<FunctionValue<'ctx>> {
+        let thunk = self.module.add_function(
+            name,
+            self.with_env(function.get_type()),
+            Some(Linkage::Internal),
+        );
+        let builder = self.context.create_builder();
+        let entry = self.context.append_basic_block(thunk, "entry");
+        builder.position_at_end(entry);
+
+        let args: Vec<BasicMetadataValueEnum> = thunk
+            .get_param_iter()
+            .skip(1)
+            .map(|arg| arg.into())
+            .collect();
+        let call_site = builder.build_call(function, &args, "call")?;
+        match call_site.try_as_basic_value().left() {
+            Some(value) => builder.build_return(Some(&value))?,
+            None => builder.build_return(None)?,
+        };
+
+        Ok(thunk)
+    }
+
+    /// `{ function, env }` for a function taking `env` first.
+    fn build_closure(
+        &mut self,
+        function: FunctionValue<'ctx>,
+        env: PointerValue<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>> {
+        let ty = self.closure_type(function.get_type());
+        let value = self.builder.build_insert_value(
+            ty.get_undef(),
+            function.as_global_value().as_pointer_value(),
+            0,
+            "closure",
+        )?;
+        let value = self
+            .builder
+            .build_insert_value(value, env, 1, "closure")?
+            .into_struct_value();
+
+        Ok(value.into())
+    }
+
+    /// The builtin `len`, reading the length of an array or calling `strlen`.
+    fn compile_len(&mut self, call: &CallExpr) -> Result<BasicValueEnum<'ctx>> {
+        let [arg] = call.args.as_slice() else {
+            return Err(CodegenError(format!(
+                "Function 'len' expects 1 argument, found {}",
+                call.args.len()
+            ))
+            .into());
+        };
+
+        match self.compile_value(arg)? {
+            BasicValueEnum::StructValue(array)
+                if self.array_element(array.get_type().into()).is_some() =>
+            {
+                Ok(self.builder.build_extract_value(array, 1, "len")?)
+            }
+            BasicValueEnum::PointerValue(string) => {
+                let ptr_type = self.context.ptr_type(AddressSpace::default());
+                let strlen = self.declare_external(
+                    "strlen",
+                    self.context.i64_type().fn_type(&[ptr_type.into()], false),
+                );
+
+                Ok(self
+                    .builder
+                    .build_call(strlen, &[string.into()], "len")?
+                    .try_as_basic_value()
+                    .left()
+                    .expect("strlen returns a value"))
+            }
+            _ => {
+                Err(CodegenError("Function 'len' expects an array or a string".to_string()).into())
+            }
+        }
+    }
+
+    fn compile_unary(&mut self, un: &Unary) -> Result<BasicValueEnum<'ctx>> {
+        let value = self.compile_value(&un.expr)?;
+
+        Ok(match (un.operator.kind, value) {
+            (UnOpKind::Minus, BasicValueEnum::IntValue(int)) => {
+                self.builder.build_int_neg(int, "neg")?.into()
+            }
+            (UnOpKind::Minus, BasicValueEnum::FloatValue(float)) => {
+                self.builder.build_float_neg(float, "neg")?.into()
+            }
+            // Bools are `i1`, so flipping every bit negates them
+            (UnOpKind::BitwiseNot | UnOpKind::Not, BasicValueEnum::IntValue(int)) => {
+                self.builder.build_not(int, "not")?.into()
+            }
+            (kind, _) => {
+                return Err(CodegenError(format!(
+                    "Unary operator '{}' not supported for this operand",
+                    kind
+                ))
+                .into())
+            }
+        })
+    }
+
+    fn compile_binary(&mut self, bin: &Binary) -> Result<BasicValueEnum<'ctx>> {
+        match bin.operator {
+            BinOpKind::And | BinOpKind::Or => {
+                return self.compile_short_circuit(&bin.left, bin.operator, &bin.right)
+            }
+            BinOpKind::PlusEquals | BinOpKind::MinusEquals => {
+                return self.compile_compound_assign(bin)
+            }
+            _ => {}
+        }
+
+        let left = self.compile_value(&bin.left)?;
+        let mut right = self.compile_value(&bin.right)?;
+        if let (
+            BinOpKind::Divide | BinOpKind::Modulo,
+            BasicValueEnum::IntValue(l),
+            BasicValueEnum::IntValue(r),
+        ) = (bin.operator, left, right)
+        {
+            right = self.build_divisor_check(l, r, &bin.span())?.into();
+        }
+
+        self.build_binary_op(bin.operator, left, right)
+    }
+
+    /// Stops the program with an error pointing at `span` when `divisor` is zero. Returns
+    /// the divisor to use: 1 for `i64::MIN / -1`, which wraps to `i64::MIN` like in the
+    /// interpreter instead of trapping.
+    fn build_divisor_check(
+        &mut self,
+        dividend: IntValue<'ctx>,
+        divisor: IntValue<'ctx>,
+        span: &TextSpan,
+    ) -> Result<IntValue<'ctx>> {
+        let int_type = divisor.get_type();
+        let function = self.current_fn();
+        let fail_bb = self.context.append_basic_block(function, "div.fail");
+        let ok_bb = self.context.append_basic_block(function, "div.ok");
+
+        let is_zero = self.builder.build_int_compare(
+            IntPredicate::EQ,
+            divisor,
+            int_type.const_zero(),
+            "is_zero",
+        )?;
+        self.builder
+            .build_conditional_branch(is_zero, fail_bb, ok_bb)?;
+
+        self.builder.position_at_end(fail_bb);
+        let panic = match self.module.get_function("pulse.division_by_zero") {
+            Some(panic) => panic,
+            None => self.define_division_by_zero()?,
+        };
+        let location = format!("{}:{}", span.start.line, span.start.column);
+        let location = self
+            .builder
+            .build_global_string_ptr(&location, "location")?;
+        self.builder
+            .build_call(panic, &[location.as_pointer_value().into()], "")?;
+        self.builder.build_unreachable()?;
+
+        self.builder.position_at_end(ok_bb);
+        let is_min = self.builder.build_int_compare(
+            IntPredicate::EQ,
+            dividend,
+            int_type.const_int(i64::MIN as u64, false),
+            "is_min",
+        )?;
+        let is_minus_one = self.builder.build_int_compare(
+            IntPredicate::EQ,
+            divisor,
+            int_type.const_all_ones(),
+            "is_minus_one",
+        )?;
+        let overflows = self.builder.build_and(is_min, is_minus_one, "overflows")?;
+        let divisor = self
+            .builder
+            .build_select(overflows, int_type.const_int(1, false), divisor, "divisor")?
+            .into_int_value();
+
+        Ok(divisor)
+    }
+
+    /// Prints the same error as the interpreter to stderr and exits with
+    /// `RUNTIME_ERROR_EXIT_CODE`.
+    fn define_division_by_zero(&mut self) -> Result<FunctionValue<'ctx>> {
+        let i32_type = self.context.i32_type();
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+
+        let dprintf = self.declare_external(
+            "dprintf",
+            i32_type.fn_type(&[i32_type.into(), ptr_type.into()], true),
+        );
+        let exit = self.declare_external(
+            "exit",
+            self.context.void_type().fn_type(&[i32_type.into()], false),
+        );
+
+        let function = self.module.add_function(
+            "pulse.division_by_zero",
+            self.context.void_type().fn_type(&[ptr_type.into()], false),
+            Some(Linkage::Internal),
+        );
+        let builder = self.context.create_builder();
+        let entry = self.context.append_basic_block(function, "entry");
+        builder.position_at_end(entry);
+
+        let format =
+            builder.build_global_string_ptr("error: Division by zero\n---> %s\n", "fmt")?;
+        let location = function.get_nth_param(0).unwrap();
+        builder.build_call(
+            dprintf,
+            &[
+                i32_type.const_int(2, false).into(),
+                format.as_pointer_value().into(),
+                location.into(),
+            ],
+            "",
+        )?;
+        builder.build_call(
+            exit,
+            &[i32_type
+                .const_int(RUNTIME_ERROR_EXIT_CODE as u64, false)
+                .into()],
+            "",
+        )?;
+        builder.build_unreachable()?;
+
+        Ok(function)
+    }
+
+    fn build_binary_op(
+        &mut self,
+        operator: BinOpKind,
+        left: BasicValueEnum<'ctx>,
+        right: BasicValueEnum<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>> {
+        let builder = &self.builder;
+
+        let value: BasicValueEnum = match (left, right) {
+            (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r))
+                if l.get_type() == r.get_type() =>
+            {
+                // bools are compared as unsigned so that `true > false`
+                let signed = l.get_type().get_bit_width() > 1;
+                let predicate = |signed_pred, unsigned_pred| {
+                    if signed {
+                        signed_pred
+                    } else {
+                        unsigned_pred
+                    }
+                };
+
+                match operator {
+                    BinOpKind::Plus => builder.build_int_add(l, r, "add")?.into(),
+                    BinOpKind::Minus => builder.build_int_sub(l, r, "sub")?.into(),
+                    BinOpKind::Multiply => builder.build_int_mul(l, r, "mul")?.into(),
+                    BinOpKind::Divide => builder.build_int_signed_div(l, r, "div")?.into(),
+                    BinOpKind::Modulo => builder.build_int_signed_rem(l, r, "rem")?.into(),
+                    BinOpKind::Power => self.build_int_pow(l, r)?.into(),
+                    BinOpKind::BitwiseAnd => builder.build_and(l, r, "and")?.into(),
+                    BinOpKind::BitwiseOr => builder.build_or(l, r, "or")?.into(),
+                    BinOpKind::BitwiseXor => builder.build_xor(l, r, "xor")?.into(),
+                    BinOpKind::Equals | BinOpKind::EqualsEquals => builder
+                        .build_int_compare(IntPredicate::EQ, l, r, "eq")?
+                        .into(),
+                    BinOpKind::NotEquals | BinOpKind::BangEquals => builder
+                        .build_int_compare(IntPredicate::NE, l, r, "ne")?
+                        .into(),
+                    BinOpKind::LessThan => builder
+                        .build_int_compare(
+                            predicate(IntPredicate::SLT, IntPredicate::ULT),
+                            l,
+                            r,
+                            "lt",
+                        )?
+                        .into(),
+                    BinOpKind::LessThanOrEqual => builder
+                        .build_int_compare(
+                            predicate(IntPredicate::SLE, IntPredicate::ULE),
+                            l,
+                            r,
+                            "le",
+                        )?
+                        .into(),
+                    BinOpKind::GreaterThan => builder
+                        .build_int_compare(
+                            predicate(IntPredicate::SGT, IntPredicate::UGT),
+                            l,
+                            r,
+                            "gt",
+                        )?
+                        .into(),
+                    BinOpKind::GreaterThanOrEqual => builder
+                        .build_int_compare(
+                            predicate(IntPredicate::SGE, IntPredicate::UGE),
+                            l,
+                            r,
+                            "ge",
+                        )?
+                        .into(),
+                    _ => return Err(self.unsupported_binary(operator)),
+                }
+            }
+            (BasicValueEnum::FloatValue(l), BasicValueEnum::FloatValue(r)) => match operator {
+                BinOpKind::Plus => builder.build_float_add(l, r, "add")?.into(),
+                BinOpKind::Minus => builder.build_float_sub(l, r, "sub")?.into(),
+                BinOpKind::Multiply => builder.build_float_mul(l, r, "mul")?.into(),
+                BinOpKind::Divide => builder.build_float_div(l, r, "div")?.into(),
+                BinOpKind::Modulo => builder.build_float_rem(l, r, "rem")?.into(),
+                BinOpKind::Power => {
+                    let pow = Intrinsic::find("llvm.pow")
+                        .and_then(|pow| pow.get_declaration(&self.module, &[l.get_type().into()]))
+                        .ok_or_else(|| CodegenError("Missing llvm.pow intrinsic".into()))?;
+                    builder
+                        .build_call(pow, &[l.into(), r.into()], "pow")?
+                        .try_as_basic_value()
+                        .left()
+                        .expect("llvm.pow returns a value")
+                }
+                BinOpKind::Equals | BinOpKind::EqualsEquals => builder
+                    .build_float_compare(FloatPredicate::OEQ, l, r, "eq")?
+                    .into(),
+                BinOpKind::NotEquals | BinOpKind::BangEquals => builder
+                    .build_float_compare(FloatPredicate::UNE, l, r, "ne")?
+                    .into(),
+                BinOpKind::LessThan => builder
+                    .build_float_compare(FloatPredicate::OLT, l, r, "lt")?
+                    .into(),
+                BinOpKind::LessThanOrEqual => builder
+                    .build_float_compare(FloatPredicate::OLE, l, r, "le")?
+                    .into(),
+                BinOpKind::GreaterThan => builder
+                    .build_float_compare(FloatPredicate::OGT, l, r, "gt")?
+                    .into(),
+                BinOpKind::GreaterThanOrEqual => builder
+                    .build_float_compare(FloatPredicate::OGE, l, r, "ge")?
+                    .into(),
+                _ => return Err(self.unsupported_binary(operator)),
+            },
+            (BasicValueEnum::PointerValue(l), BasicValueEnum::PointerValue(r)) => {
+                let predicate = match operator {
+                    BinOpKind::Equals | BinOpKind::EqualsEquals => IntPredicate::EQ,
+                    BinOpKind::NotEquals | BinOpKind::BangEquals => IntPredicate::NE,
+                    BinOpKind::LessThan => IntPredicate::SLT,
+                    BinOpKind::LessThanOrEqual => IntPredicate::SLE,
+                    BinOpKind::GreaterThan => IntPredicate::SGT,
+                    BinOpKind::GreaterThanOrEqual => IntPredicate::SGE,
+                    _ => return Err(self.unsupported_binary(operator)),
+                };
+                let ordering = self.build_strcmp(l, r)?;
+                let zero = self.context.i32_type().const_zero();
+                self.builder
+                    .build_int_compare(predicate, ordering, zero, "strcmp")?
+                    .into()
+            }
+            _ => {
+                return Err(CodegenError(format!(
+                    "Type mismatch in binary operation '{:?}'",
+                    operator
+                ))
+                .into())
+            }
+        };
+
+        Ok(value)
+    }
+
+    fn unsupported_binary(&self, operator: BinOpKind) -> anyhow::Error {
+        CodegenError(format!(
+            "Binary operator '{:?}' not supported for these operands",
+            operator
+        ))
+        .into()
+    }
+
+    fn compile_short_circuit(
+        &mut self,
+        left: &Expr,
+        operator: BinOpKind,
+        right: &Expr,
+    ) -> Result<BasicValueEnum<'ctx>> {
+        let function = self.current_fn();
+        let left = self.compile_condition(left)?;
+        let left_bb = self.builder.get_insert_block().expect("No insert block");
+
+        let rhs_bb = self.context.append_basic_block(function, "logic.rhs");
+        let end_bb = self.context.append_basic_block(function, "logic.end");
+
+        match operator {
+            BinOpKind::And => self
+                .builder
+                .build_conditional_branch(left, rhs_bb, end_bb)?,
+            _ => self
+                .builder
+                .build_conditional_branch(left, end_bb, rhs_bb)?,
+        };
+
+        self.builder.position_at_end(rhs_bb);
+        let right = self.compile_condition(right)?;
+        let right_bb = self.builder.get_insert_block().expect("No insert block");
+        self.builder.build_unconditional_branch(end_bb)?;
+
+        self.builder.position_at_end(end_bb);
+        let phi = self.builder.build_phi(self.context.bool_type(), "logic")?;
+        phi.add_incoming(&[(&left, left_bb), (&right, right_bb)]);
+
+        Ok(phi.as_basic_value())
+    }
+
+    fn compile_compound_assign(&mut self, bin: &Binary) -> Result<BasicValueEnum<'ctx>> {
+        let place = self.compile_place(&bin.left)?;
+        let current = self.builder.build_load(place.ty, place.ptr, "current")?;
+        let right = self.compile_value(&bin.right)?;
+        let operator = match bin.operator {
+            BinOpKind::PlusEquals => BinOpKind::Plus,
+            _ => BinOpKind::Minus,
+        };
+
+        let value = self.build_binary_op(operator, current, right)?;
+        self.builder.build_store(place.ptr, value)?;
+
+        Ok(value)
+    }
+
+    fn build_strcmp(
+        &mut self,
+        left: PointerValue<'ctx>,
+        right: PointerValue<'ctx>,
+    ) -> Result<IntValue<'ctx>> {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let strcmp = self.declare_external(
+            "strcmp",
+            self.context
+                .i32_type()
+                .fn_type(&[ptr_type.into(), ptr_type.into()], false),
+        );
+
+        Ok(self
+            .builder
+            .build_call(strcmp, &[left.into(), right.into()], "strcmp")?
+            .try_as_basic_value()
+            .left()
+            .expect("strcmp returns a value")
+            .into_int_value())
+    }
+
+    fn build_int_pow(
+        &mut self,
+        base: IntValue<'ctx>,
+        exp: IntValue<'ctx>,
+    ) -> Result<IntValue<'ctx>> {
+        let ipow = match self.module.get_function("pulse.ipow") {
+            Some(ipow) => ipow,
+            None => self.define_int_pow()?,
+        };
+
+        Ok(self
+            .builder
+            .build_call(ipow, &[base.into(), exp.into()], "pow")?
+            .try_as_basic_value()
+            .left()
+            .expect("ipow returns a value")
+            .into_int_value())
+    }
+
+    /// Exponentiation by squaring, negative exponents yield 0.
+    fn define_int_pow(&mut self) -> Result<FunctionValue<'ctx>> {
+        let i64_type = self.context.i64_type();
+        let function = self.module.add_function(
+            "pulse.ipow",
+            i64_type.fn_type(&[i64_type.into(), i64_type.into()], false),
+            Some(Linkage::Internal),
+        );
+        let builder = self.context.create_builder();
+
+        let entry = self.context.append_basic_block(function, "entry");
+        let loop_bb = self.context.append_basic_block(function, "loop");
+        let body_bb = self.context.append_basic_block(function, "body");
+        let end_bb = self.context.append_basic_block(function, "end");
+
+        let base = function.get_nth_param(0).unwrap().into_int_value();
+        let exp = function.get_nth_param(1).unwrap().into_int_value();
+        let zero = i64_type.const_zero();
+        let one = i64_type.const_int(1, false);
+
+        builder.position_at_end(entry);
+        builder.build_unconditional_branch(loop_bb)?;
+
+        builder.position_at_end(loop_bb);
+        let result = builder.build_phi(i64_type, "result")?;
+        let acc = builder.build_phi(i64_type, "acc")?;
+        let n = builder.build_phi(i64_type, "n")?;
+        let n_value = n.as_basic_value().into_int_value();
+        let done = builder.build_int_compare(IntPredicate::SLE, n_value, zero, "done")?;
+        builder.build_conditional_branch(done, end_bb, body_bb)?;
+
+        builder.position_at_end(body_bb);
+        let result_value = result.as_basic_value().into_int_value();
+        let acc_value = acc.as_basic_value().into_int_value();
+        let odd = builder.build_and(n_value, one, "odd")?;
+        let is_odd = builder.build_int_compare(IntPredicate::NE, odd, zero, "is_odd")?;
+        let multiplied = builder.build_int_mul(result_value, acc_value, "mul")?;
+        let next_result = builder
+            .build_select(is_odd, multiplied, result_value, "next_result")?
+            .into_int_value();
+        let next_acc = builder.build_int_mul(acc_value, acc_value, "next_acc")?;
+        let next_n = builder.build_right_shift(n_value, one, true, "next_n")?;
+        builder.build_unconditional_branch(loop_bb)?;
+
+        result.add_incoming(&[(&one, entry), (&next_result, body_bb)]);
+        acc.add_incoming(&[(&base, entry), (&next_acc, body_bb)]);
+        n.add_incoming(&[(&exp, entry), (&next_n, body_bb)]);
+
+        builder.position_at_end(end_bb);
+        let negative = builder.build_int_compare(IntPredicate::SLT, exp, zero, "negative")?;
+        let value = builder.build_select(
+            negative,
+            zero,
+            result.as_basic_value().into_int_value(),
+            "pow",
+        )?;
+        builder.build_return(Some(&value))?;
+
+        Ok(function)
+    }
+}
+
+/// The types bound to `type_params` in order, failing on the first one left unbound.
+fn type_args<'ctx>(
+    type_params: &[GenericParam],
+    bindings: &HashMap<String, BasicTypeEnum<'ctx>>,
+    name: &str,
+) -> Result<Vec<BasicTypeEnum<'ctx>>> {
+    type_params
+        .iter()
+        .map(|param| {
+            bindings.get(&param.name.literal()).copied().ok_or_else(|| {
+                CodegenError(format!(
+                    "Cannot infer type parameter '{}' of '{}'",
+                    param.name.literal(),
+                    name
+                ))
+                .into()
+            })
+        })
+        .collect()
+}
+
+/// How `ty` is spelled in the names of the types and functions instantiated for it. Named
+/// structs go by their name alone, as their body may not be set yet.
+fn type_key(ty: BasicTypeEnum) -> String {
+    match ty {
+        BasicTypeEnum::StructType(struct_type) => match struct_type.get_name() {
+            Some(name) => format!("%{}", name.to_string_lossy()),
+            None => ty.print_to_string().to_string(),
+        },
+        _ => ty.print_to_string().to_string(),
+    }
+}
+
+/// The symbol `fn_stmt` is emitted under, qualified by its `impl` type `owner` if any.
+fn fn_name(fn_stmt: &Fn, owner: Option<&Token>) -> String {
+    match owner {
+        Some(owner) => qualified_name(&owner.literal(), &fn_stmt.name),
+        None => fn_stmt.name.clone(),
+    }
 }
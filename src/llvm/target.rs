@@ -0,0 +1,85 @@
+use crate::{error::PulseError::CodegenError, llvm::ir::CodeGen};
+use anyhow::Result;
+use inkwell::{
+    passes::PassBuilderOptions,
+    targets::{CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetMachine},
+    OptimizationLevel,
+};
+use std::path::Path;
+
+pub fn optimization_level(opt_level: u8) -> OptimizationLevel {
+    match opt_level {
+        0 => OptimizationLevel::None,
+        1 => OptimizationLevel::Less,
+        2 => OptimizationLevel::Default,
+        _ => OptimizationLevel::Aggressive,
+    }
+}
+
+pub fn host_target_machine(opt_level: u8) -> Result<TargetMachine> {
+    Target::initialize_native(&InitializationConfig::default()).map_err(CodegenError)?;
+
+    let triple = TargetMachine::get_default_triple();
+    let target = Target::from_triple(&triple).map_err(|err| CodegenError(err.to_string()))?;
+
+    target
+        .create_target_machine(
+            &triple,
+            &TargetMachine::get_host_cpu_name().to_string(),
+            &TargetMachine::get_host_cpu_features().to_string(),
+            optimization_level(opt_level),
+            RelocMode::PIC,
+            CodeModel::Default,
+        )
+        .ok_or_else(|| {
+            CodegenError(format!(
+                "Couldn't create a target machine for {}",
+                triple.as_str().to_string_lossy()
+            ))
+            .into()
+        })
+}
+
+impl<'ctx> CodeGen<'ctx> {
+    /// Runs LLVM's default `O1`-`O3` pipeline, `O0` leaves the module untouched.
+    pub fn optimize(&self, machine: &TargetMachine, opt_level: u8) -> Result<()> {
+        if opt_level == 0 {
+            return Ok(());
+        }
+
+        self.module
+            .run_passes(
+                &format!("default<O{}>", opt_level),
+                machine,
+                PassBuilderOptions::create(),
+            )
+            .map_err(|err| CodegenError(err.to_string()))?;
+
+        Ok(())
+    }
+
+    pub fn write_object_file(&self, machine: &TargetMachine, path: &Path) -> Result<()> {
+        self.write_to_file(machine, FileType::Object, path)
+    }
+
+    pub fn write_assembly_file(&self, machine: &TargetMachine, path: &Path) -> Result<()> {
+        self.write_to_file(machine, FileType::Assembly, path)
+    }
+
+    fn write_to_file(
+        &self,
+        machine: &TargetMachine,
+        file_type: FileType,
+        path: &Path,
+    ) -> Result<()> {
+        self.module.set_triple(&machine.get_triple());
+        self.module
+            .set_data_layout(&machine.get_target_data().get_data_layout());
+
+        machine
+            .write_to_file(&self.module, file_type, path)
+            .map_err(|err| CodegenError(err.to_string()))?;
+
+        Ok(())
+    }
+}
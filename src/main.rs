@@ -1,28 +1,18 @@
-use crate::{commands::init::init_command, diagnostic::print_diagnostic};
+#[cfg(feature = "llvm")]
+use crate::commands::build::build_command;
+use crate::commands::init::init_command;
 use anyhow::Result;
-use clap::{
-    builder::{styling, PossibleValuesParser, Styles, TypedValueParser},
-    Args, Parser, Subcommand, ValueHint,
-};
+use clap::Parser;
 use cli::{Cli, Commands};
 use commands::run::run_command;
 use logger::setup_logger;
 use panic_handler::setup_panic_handler;
+use pulse_lang::{diagnostic::print_diagnostic, error::FAILURE_EXIT_CODE};
 
-pub mod ast;
-pub mod cli;
-pub mod commands;
-mod diagnostic;
-pub mod error;
-pub mod fs;
-pub mod lexer;
-pub mod logger;
-pub mod panic_handler;
-pub mod project;
-pub mod resolver;
-pub mod llvm {
-    pub mod ir;
-}
+mod cli;
+mod commands;
+mod logger;
+mod panic_handler;
 
 fn main() -> Result<()> {
     setup_panic_handler();
@@ -32,7 +22,13 @@ fn main() -> Result<()> {
     log::debug!("Parsed clap arguments");
 
     let result = match args.command {
-        Commands::Run {} => run_command(),
+        Commands::Run {
+            options,
+            backend,
+            args,
+        } => run_command(options, backend, args),
+        #[cfg(feature = "llvm")]
+        Commands::Build { options } => build_command(options),
         Commands::Init { name } => init_command(name.clone()),
     };
 
@@ -41,7 +37,8 @@ fn main() -> Result<()> {
             log::debug!("Finished program")
         }
         Err(err) => {
-            log::error!("{:?}", err);
+            print_diagnostic(err, None);
+            std::process::exit(FAILURE_EXIT_CODE);
         }
     }
 
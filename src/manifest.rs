@@ -0,0 +1,98 @@
+use crate::error::PulseError::ManifestError;
+use anyhow::Result;
+use std::{fs, path::Path};
+pub use toml::Value;
+
+#[derive(Debug, Default)]
+pub struct Manifest {
+    pub name: String,
+    pub version: String,
+    pub table: toml::Table,
+}
+
+impl Manifest {
+    pub fn from_file(path: &Path) -> Result<Manifest> {
+        let content = fs::read_to_string(path)?;
+
+        Manifest::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<Manifest> {
+        let table: toml::Table = content.parse().map_err(|err: toml::de::Error| {
+            let line = err
+                .span()
+                .map(|span| content[..span.start].lines().count().max(1))
+                .unwrap_or(1);
+            ManifestError(format!(
+                "{} on line {}",
+                err.message().trim_end().replace('\n', ", "),
+                line
+            ))
+        })?;
+
+        let project = table
+            .get("project")
+            .and_then(Value::as_table)
+            .ok_or_else(|| ManifestError("Missing [project] table".to_string()))?;
+        let name = match project.get("name") {
+            Some(Value::String(name)) => name.clone(),
+            _ => return Err(ManifestError("Missing project name".to_string()).into()),
+        };
+        let version = match project.get("version") {
+            Some(Value::String(version)) => version.clone(),
+            _ => "0.1.0".to_string(),
+        };
+
+        Ok(Manifest {
+            name,
+            version,
+            table,
+        })
+    }
+
+    /// The value of `key` in `table`, where `table` is a dotted path like `profile.release`.
+    pub fn get(&self, table: &str, key: &str) -> Option<&Value> {
+        table
+            .split('.')
+            .try_fold(&self.table, |table, name| table.get(name)?.as_table())
+            .and_then(|table| table.get(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_project_and_other_tables() {
+        let manifest = Manifest::parse(
+            "
+            # pulse.toml
+            [project]
+            name = \"demo\" # the binary's name
+
+            [profile.release]
+            opt-level = 2
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(manifest.name, "demo");
+        assert_eq!(manifest.version, "0.1.0");
+        assert_eq!(
+            manifest.get("profile.release", "opt-level"),
+            Some(&Value::Integer(2))
+        );
+        assert_eq!(manifest.get("profile.debug", "opt-level"), None);
+    }
+
+    #[test]
+    fn requires_a_project_table() {
+        let err = Manifest::parse("[package]\nname = \"demo\"").unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Invalid pulse.toml: Missing [project] table"
+        );
+    }
+}
@@ -0,0 +1,484 @@
+use crate::{
+    ast::{
+        ast::{Ast, Stmt},
+        parser::Parser,
+    },
+    error::PulseError::{self, CircularImport, InModule, SemanticError},
+    lexer::{span::TextSpan, token::Token, Lexer},
+    resolver::Resolver,
+};
+use anyhow::Result;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// A source file of the project, after the frontend went through it.
+#[derive(Debug)]
+pub struct Module {
+    /// The path of the file relative to the project root, `src/math.pulse`.
+    pub name: String,
+    pub path: PathBuf,
+    pub content: String,
+    pub tokens: Vec<Token>,
+    pub ast: Ast,
+    pub resolver: Resolver,
+}
+
+/// The main file and every module it imports, directly or through other modules.
+#[derive(Debug)]
+pub struct ModuleGraph {
+    /// Modules come after the modules they import, so the main file is last.
+    pub modules: Vec<Module>,
+    root: PathBuf,
+    /// Top-level names across all modules and the module declaring each. The backends
+    /// compile the whole graph as a single program, so these can't be shared.
+    declared: HashMap<String, String>,
+    /// The `use` statements being followed, outermost first. Loading a module that is
+    /// already on the stack means the imports form a cycle.
+    importing: Vec<Import>,
+}
+
+/// A `use` statement whose module is being loaded.
+#[derive(Debug)]
+struct Import {
+    importer: PathBuf,
+    name: String,
+    content: String,
+    module: String,
+    span: TextSpan,
+}
+
+impl ModuleGraph {
+    /// Lexes, parses and resolves `main_file` and the modules it imports, each one once.
+    pub fn load(root: &Path, main_file: &Path) -> Result<ModuleGraph> {
+        let mut graph = ModuleGraph {
+            modules: vec![],
+            root: root.to_path_buf(),
+            declared: HashMap::new(),
+            importing: vec![],
+        };
+        graph.load_module(main_file)?;
+
+        Ok(graph)
+    }
+
+    pub fn main(&self) -> &Module {
+        self.modules.last().expect("The main file is always loaded")
+    }
+
+    /// All modules as one program, imported declarations first.
+    pub fn program(&self) -> Ast {
+        let mut program = Ast::new();
+        for module in &self.modules {
+            program.stmts.extend(module.ast.stmts.iter().cloned());
+        }
+
+        program
+    }
+
+    /// The file declaring each top-level function, type and variant, by name.
+    pub fn sources(&self) -> HashMap<String, PathBuf> {
+        let mut sources = HashMap::new();
+        for module in &self.modules {
+            for (name, _) in top_level_names(&module.ast) {
+                sources.insert(name, module.path.clone());
+            }
+        }
+
+        sources
+    }
+
+    fn load_module(&mut self, path: &Path) -> Result<usize> {
+        if let Some(index) = self.modules.iter().position(|module| module.path == path) {
+            return Ok(index);
+        }
+        if let Some(start) = self
+            .importing
+            .iter()
+            .position(|import| import.importer == path)
+        {
+            return Err(self.import_cycle(start));
+        }
+
+        let name = self.display_name(path);
+        let content = fs::read_to_string(path)?;
+        log::debug!("Loading module {}", name);
+
+        let in_module = |err: anyhow::Error| match err.downcast::<PulseError>() {
+            Ok(err) => InModule(name.clone(), content.clone(), Box::new(err)).into(),
+            Err(err) => err,
+        };
+
+        let tokens = Lexer::from_source(content.clone())
+            .lex()
+            .map_err(in_module)?;
+        let ast = Parser::new(tokens.clone()).parse().map_err(in_module)?;
+
+        let mut resolver = Resolver::new();
+        for stmt in &ast.stmts {
+            let Stmt::Use(use_stmt) = stmt else {
+                continue;
+            };
+            if !use_stmt.is_relative() {
+                continue;
+            }
+
+            let module = use_stmt.module();
+            let import = match self.module_path(path, &module) {
+                Ok(dependency) => {
+                    self.importing.push(Import {
+                        importer: path.to_path_buf(),
+                        name: name.clone(),
+                        content: content.clone(),
+                        module: module.clone(),
+                        span: use_stmt.from.span.clone(),
+                    });
+                    let index = self.load_module(&dependency)?;
+                    self.importing.pop();
+
+                    let dependency = &self.modules[index];
+                    Ok(dependency.resolver.module_def(&dependency.ast))
+                }
+                Err(message) => Err(message),
+            };
+            resolver.imports.insert(module, import);
+        }
+
+        for (declared, span) in top_level_names(&ast) {
+            if let Some(other) = self.declared.get(&declared) {
+                resolver.errors.push(SemanticError(
+                    format!("'{}' is already declared in {}", declared, other),
+                    span,
+                ));
+            }
+        }
+        resolver.resolve_ast(&ast).map_err(in_module)?;
+
+        for (declared, _) in top_level_names(&ast) {
+            self.declared.entry(declared).or_insert(name.clone());
+        }
+        self.modules.push(Module {
+            name,
+            path: path.to_path_buf(),
+            content,
+            tokens,
+            ast,
+            resolver,
+        });
+
+        Ok(self.modules.len() - 1)
+    }
+
+    /// Reports the imports from `importing[start]` onwards, which lead back to the module
+    /// that started them.
+    fn import_cycle(&self, start: usize) -> anyhow::Error {
+        let cycle = &self.importing[start..];
+        let chain: Vec<&str> = cycle
+            .iter()
+            .chain(cycle.first())
+            .map(|import| import.name.as_str())
+            .collect();
+        let imports = cycle
+            .iter()
+            .map(|import| {
+                let err = SemanticError(
+                    format!("{} imports '{}'", import.name, import.module),
+                    import.span.clone(),
+                );
+                InModule(import.name.clone(), import.content.clone(), Box::new(err))
+            })
+            .collect();
+
+        CircularImport(chain.join(" -> "), imports).into()
+    }
+
+    /// The file `module` refers to when imported from `importer`, or why there is none.
+    fn module_path(&self, importer: &Path, module: &str) -> Result<PathBuf, String> {
+        let directory = importer.parent().unwrap_or(&self.root);
+        let path = normalize(&directory.join(format!("{}.pulse", module)));
+
+        if !path.starts_with(self.root.join("src")) {
+            return Err(format!(
+                "Cannot find module '{}', modules must be inside src/",
+                module
+            ));
+        }
+        if !path.is_file() {
+            return Err(format!(
+                "Cannot find module '{}', expected a file at {}",
+                module,
+                self.display_name(&path)
+            ));
+        }
+
+        Ok(path)
+    }
+
+    fn display_name(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    }
+}
+
+/// Functions, types and enum variants declared at the top level of `ast`.
+fn top_level_names(ast: &Ast) -> Vec<(String, TextSpan)> {
+    let mut names = vec![];
+    for stmt in &ast.stmts {
+        match stmt {
+            Stmt::Fn(fn_stmt) => names.push((fn_stmt.name.clone(), fn_stmt.fn_token.span.clone())),
+            Stmt::Struct(struct_stmt) => {
+                names.push((struct_stmt.name.literal(), struct_stmt.name.span.clone()))
+            }
+            Stmt::Enum(enum_stmt) => {
+                names.push((enum_stmt.name.literal(), enum_stmt.name.span.clone()));
+                for variant in &enum_stmt.variants {
+                    names.push((variant.ident.literal(), variant.ident.span.clone()));
+                }
+            }
+            Stmt::Interface(interface) => {
+                names.push((interface.name.literal(), interface.name.span.clone()))
+            }
+            _ => {}
+        }
+    }
+
+    names
+}
+
+/// Resolves `.` and `..` without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PulseError::Errors;
+
+    /// Writes `files` under `src/` of a fresh project directory and returns its root.
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("pulse-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for (path, content) in files {
+            let path = root.join("src").join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        root
+    }
+
+    fn load(root: &Path) -> Result<ModuleGraph> {
+        ModuleGraph::load(root, &root.join("src/main.pulse"))
+    }
+
+    /// The errors loading the project at `root` reports, prefixed by their module.
+    fn errors(root: &Path) -> Vec<String> {
+        let err = load(root).unwrap_err().downcast::<PulseError>().unwrap();
+        let InModule(name, _, err) = err else {
+            panic!("Expected an error in a module, found {:?}", err);
+        };
+        let Errors(errors) = *err else {
+            panic!("Expected a list of errors, found {:?}", err);
+        };
+
+        errors
+            .iter()
+            .map(|err| format!("{}: {}", name, err))
+            .collect()
+    }
+
+    #[test]
+    fn loads_imported_modules_first() {
+        let root = project(
+            "imports",
+            &[
+                (
+                    "main.pulse",
+                    "use { add } from \"./math\";\nfn main() -> int { return add(1, 2); }",
+                ),
+                (
+                    "math.pulse",
+                    "use { twice } from \"./util/twice\";\n\
+                     export fn add(a: int, b: int) -> int { return twice(a) + b; }",
+                ),
+                (
+                    "util/twice.pulse",
+                    "export fn twice(a: int) -> int { return a * 2; }",
+                ),
+            ],
+        );
+
+        let graph = load(&root);
+        fs::remove_dir_all(&root).unwrap();
+
+        let names: Vec<String> = graph
+            .unwrap()
+            .modules
+            .iter()
+            .map(|module| module.name.clone())
+            .collect();
+        assert_eq!(
+            names,
+            ["src/util/twice.pulse", "src/math.pulse", "src/main.pulse"]
+        );
+    }
+
+    #[test]
+    fn imports_name_exported_functions_of_existing_modules() {
+        let root = project(
+            "bad-imports",
+            &[
+                (
+                    "main.pulse",
+                    "use { add, hidden, missing } from \"./math\";\n\
+                     use { x } from \"./nowhere\";\n\
+                     use { y } from \"../outside\";\n\
+                     fn main() -> int { return 0; }",
+                ),
+                (
+                    "math.pulse",
+                    "export fn add(a: int, b: int) -> int { return a + b; }\n\
+                     fn hidden() -> int { return 0; }",
+                ),
+            ],
+        );
+
+        let errors = errors(&root);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            errors,
+            [
+                "src/main.pulse: Function 'hidden' is not exported by './math'",
+                "src/main.pulse: Module './math' has no function 'missing'",
+                "src/main.pulse: Cannot find module './nowhere', expected a file at src/nowhere.pulse",
+                "src/main.pulse: Cannot find module '../outside', modules must be inside src/",
+            ]
+        );
+    }
+
+    #[test]
+    fn top_level_names_are_unique_across_modules() {
+        let root = project(
+            "duplicates",
+            &[
+                (
+                    "main.pulse",
+                    "use { perimeter } from \"./shapes\";\n\
+                     struct Point { x: int }\n\
+                     fn area() -> int { return 0; }\n\
+                     fn main() -> int { return 0; }",
+                ),
+                (
+                    "shapes.pulse",
+                    "struct Point { x: int, y: int }\n\
+                     fn area() -> int { return 1; }\n\
+                     export fn perimeter() -> int { return 4; }",
+                ),
+            ],
+        );
+
+        let errors = errors(&root);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            errors,
+            [
+                "src/main.pulse: 'Point' is already declared in src/shapes.pulse",
+                "src/main.pulse: 'area' is already declared in src/shapes.pulse",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_the_modules_forming_a_cycle() {
+        let root = project(
+            "cycle",
+            &[
+                (
+                    "main.pulse",
+                    "use { a } from \"./a\";\nfn main() -> int { return a(); }",
+                ),
+                (
+                    "a.pulse",
+                    "use { b } from \"./b\";\nexport fn a() -> int { return b(); }",
+                ),
+                (
+                    "b.pulse",
+                    "use { a } from \"./a\";\nexport fn b() -> int { return 1; }",
+                ),
+            ],
+        );
+
+        let err = load(&root).unwrap_err().downcast::<PulseError>().unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        let CircularImport(chain, imports) = err else {
+            panic!("Expected a circular import, found {:?}", err);
+        };
+        assert_eq!(chain, "src/a.pulse -> src/b.pulse -> src/a.pulse");
+        let imports: Vec<String> = imports.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            imports,
+            ["src/a.pulse imports './b'", "src/b.pulse imports './a'"]
+        );
+    }
+
+    #[test]
+    fn loads_a_module_imported_twice_once() {
+        let root = project(
+            "diamond",
+            &[
+                (
+                    "main.pulse",
+                    "use { left } from \"./left\";\nuse { right } from \"./right\";\n\
+                     fn main() -> int { return left() + right(); }",
+                ),
+                (
+                    "left.pulse",
+                    "use { base } from \"./base\";\nexport fn left() -> int { return base(); }",
+                ),
+                (
+                    "right.pulse",
+                    "use { base } from \"./base\";\nexport fn right() -> int { return base(); }",
+                ),
+                ("base.pulse", "export fn base() -> int { return 1; }"),
+            ],
+        );
+
+        let graph = load(&root);
+        fs::remove_dir_all(&root).unwrap();
+
+        let names: Vec<String> = graph
+            .unwrap()
+            .modules
+            .iter()
+            .map(|module| module.name.clone())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "src/base.pulse",
+                "src/left.pulse",
+                "src/right.pulse",
+                "src/main.pulse"
+            ]
+        );
+    }
+}
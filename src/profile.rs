@@ -0,0 +1,129 @@
+use crate::{
+    error::PulseError::ManifestError,
+    manifest::{Manifest, Value},
+};
+use anyhow::Result;
+
+/// Build settings picked from `--release`/`-O` and the `[profile.*]` tables in pulse.toml.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    pub opt_level: u8,
+    pub debug: bool,
+}
+
+impl Profile {
+    pub fn debug() -> Self {
+        Self {
+            name: "debug".to_string(),
+            opt_level: 0,
+            debug: true,
+        }
+    }
+
+    pub fn release() -> Self {
+        Self {
+            name: "release".to_string(),
+            opt_level: 3,
+            debug: false,
+        }
+    }
+
+    pub fn resolve(manifest: &Manifest, release: bool, opt_level: Option<u8>) -> Result<Self> {
+        let mut profile = if release {
+            Profile::release()
+        } else {
+            Profile::debug()
+        };
+
+        let table = format!("profile.{}", profile.name);
+        match manifest.get(&table, "opt-level") {
+            Some(Value::Integer(level)) if (0..=3).contains(level) => {
+                profile.opt_level = *level as u8;
+            }
+            Some(_) => {
+                return Err(ManifestError(format!(
+                    "'opt-level' in [{}] must be an integer between 0 and 3",
+                    table
+                ))
+                .into())
+            }
+            None => {}
+        }
+
+        match manifest.get(&table, "debug") {
+            Some(Value::Boolean(debug)) => profile.debug = *debug,
+            Some(_) => {
+                return Err(
+                    ManifestError(format!("'debug' in [{}] must be a boolean", table)).into(),
+                )
+            }
+            None => {}
+        }
+
+        if let Some(level) = opt_level {
+            profile.opt_level = level;
+        }
+
+        log::debug!("Using profile {:?}", profile);
+
+        Ok(profile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(profiles: &str) -> Manifest {
+        Manifest::parse(&format!("[project]\nname = \"demo\"\n{}", profiles)).unwrap()
+    }
+
+    #[test]
+    fn release_picks_the_release_profile() {
+        let manifest = manifest("");
+
+        assert_eq!(
+            Profile::resolve(&manifest, false, None).unwrap(),
+            Profile::debug()
+        );
+        assert_eq!(
+            Profile::resolve(&manifest, true, None).unwrap(),
+            Profile::release()
+        );
+    }
+
+    #[test]
+    fn manifest_settings_apply_to_their_profile() {
+        let manifest = manifest("[profile.release]\nopt-level = 1\n[profile.debug]\nopt-level = 2");
+
+        assert_eq!(
+            Profile::resolve(&manifest, true, None).unwrap().opt_level,
+            1
+        );
+        assert_eq!(
+            Profile::resolve(&manifest, false, None).unwrap().opt_level,
+            2
+        );
+    }
+
+    #[test]
+    fn opt_level_flag_overrides_the_manifest() {
+        let manifest = manifest("[profile.release]\nopt-level = 1");
+        let profile = Profile::resolve(&manifest, true, Some(0)).unwrap();
+
+        assert_eq!(profile.name, "release");
+        assert_eq!(profile.opt_level, 0);
+    }
+
+    #[test]
+    fn opt_level_must_be_between_0_and_3() {
+        let manifest = manifest("[profile.debug]\nopt-level = 4");
+        let err = Profile::resolve(&manifest, false, None).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Invalid pulse.toml: 'opt-level' in [profile.debug] must be an integer between 0 and 3"
+        );
+    }
+}
@@ -1,15 +1,22 @@
+#[cfg(feature = "llvm")]
+use crate::llvm::{ir::CodeGen, target::host_target_machine};
 use crate::{
-    ast::parser::Parser,
+    ast::ast::{Ast, GetSpan, Stmt},
+    emit::Emit,
     error::PulseError::{
-        InvalidProjectStructure, MultipleEntryPoints, ProjectNotFound, SemanticError,
+        InvalidProjectStructure, MissingEntryPoint, MultipleEntryPoints, ProjectNotFound,
+        SemanticError,
     },
     fs::find_nearest_file,
-    lexer::{token::Token, Lexer},
-    resolver::Resolver,
+    lexer::token::Token,
+    manifest::Manifest,
+    module::ModuleGraph,
+    profile::Profile,
 };
 use anyhow::Result;
-use log::debug;
-use std::{cmp::PartialEq, env, fmt::format, fs, path::PathBuf};
+#[cfg(feature = "llvm")]
+use inkwell::context::Context;
+use std::{cmp::PartialEq, collections::HashMap, env, fs, path::PathBuf};
 
 #[derive(Debug, PartialEq)]
 pub enum ProjectType {
@@ -26,21 +33,88 @@ impl ProjectType {
     }
 }
 
+/// Supported shapes of `main`: `fn main()`, `fn main(arg: int)`, either returning `int` or nothing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EntryPoint {
+    pub takes_arg: bool,
+    pub returns_int: bool,
+}
+
+impl EntryPoint {
+    pub fn from_ast(ast: &Ast) -> Result<EntryPoint> {
+        let main = ast
+            .stmts
+            .iter()
+            .find_map(|stmt| match stmt {
+                Stmt::Fn(f) if f.name == "main" => Some(f),
+                _ => None,
+            })
+            .ok_or(MissingEntryPoint)?;
+
+        let takes_arg = match main.params.as_slice() {
+            [] => false,
+            [param] if param.type_annotation.type_name.to_string() == "int" => true,
+            _ => {
+                return Err(SemanticError(
+                    "Entry point must be declared as 'fn main()' or 'fn main(arg: int)'"
+                        .to_string(),
+                    main.fn_token.span.clone(),
+                )
+                .into())
+            }
+        };
+
+        let returns_int = match &main.return_type {
+            None => false,
+            Some(ret) => match ret.type_name.to_string().as_str() {
+                "int" => true,
+                "void" => false,
+                other => {
+                    return Err(SemanticError(
+                        format!(
+                            "Entry point must return 'int' or nothing, found '{}'",
+                            other
+                        ),
+                        ret.type_name.span(),
+                    )
+                    .into())
+                }
+            },
+        };
+
+        Ok(EntryPoint {
+            takes_arg,
+            returns_int,
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct Project {
     pub project_type: ProjectType,
     pub root: PathBuf,
     pub tokens: Vec<Token>,
     pub content: String,
+    /// The file declaring each top-level function, type and variant of the program.
+    pub sources: HashMap<String, PathBuf>,
+    pub entry_point: Option<EntryPoint>,
+    pub manifest: Manifest,
+    pub emit: Vec<Emit>,
+    pub profile: Profile,
 }
 
 impl Project {
-    pub fn from_path(root: PathBuf, project_type: ProjectType) -> Project {
+    pub fn from_path(root: PathBuf, project_type: ProjectType, manifest: Manifest) -> Project {
         Project {
             project_type,
             root,
+            manifest,
+            emit: vec![],
+            profile: Profile::debug(),
             tokens: vec![],
             content: String::new(),
+            sources: HashMap::new(),
+            entry_point: None,
         }
     }
 }
@@ -65,7 +139,13 @@ impl Project {
                     _ => return Err(InvalidProjectStructure.into()),
                 };
 
-                return Ok(Project::from_path(root.to_path_buf(), project_type));
+                let manifest = Manifest::from_file(&path)?;
+
+                return Ok(Project::from_path(
+                    root.to_path_buf(),
+                    project_type,
+                    manifest,
+                ));
             }
         }
 
@@ -79,25 +159,157 @@ impl Project {
         self.root.join("src").join(self.project_type.file_name())
     }
 
-    pub fn build_main(&mut self) -> Result<()> {
-        let main_file = self.main_file();
-        let main_content = fs::read_to_string(&main_file)?;
-        self.content = main_content.clone();
+    pub fn build_dir(&self) -> PathBuf {
+        self.root.join("build")
+    }
+
+    /// Applies the command line build settings on top of pulse.toml.
+    pub fn configure(
+        &mut self,
+        emit: Vec<Emit>,
+        release: bool,
+        opt_level: Option<u8>,
+    ) -> Result<()> {
+        self.emit = emit;
+        self.profile = Profile::resolve(&self.manifest, release, opt_level)?;
 
+        Ok(())
+    }
+
+    pub fn artifact_path(&self, emit: Emit) -> PathBuf {
+        self.build_dir()
+            .join(format!("{}.{}", self.manifest.name, emit.extension()))
+    }
+
+    fn write_artifact(&self, emit: Emit, content: impl FnOnce() -> String) -> Result<()> {
+        if !self.emit.contains(&emit) {
+            return Ok(());
+        }
+
+        let path = self.artifact_path(emit);
+        fs::create_dir_all(self.build_dir())?;
+        fs::write(&path, content())?;
+        log::info!("Emitted {:?}", path);
+
+        Ok(())
+    }
+
+    /// Lexes, parses and resolves the main file and the modules it imports, the part
+    /// shared by every backend. Returns them as a single program.
+    pub fn analyze_main(&mut self) -> Result<Ast> {
+        let main_file = self.main_file();
         log::debug!("Building main file: {:?}", main_file);
 
-        let mut lexer = Lexer::from_source(main_content);
-        let tokens = lexer.lex()?;
+        let graph = ModuleGraph::load(&self.root, &main_file)?;
+        let main = graph.main();
+        self.content = main.content.clone();
+        self.tokens = main.tokens.clone();
+        self.sources = graph.sources();
 
-        self.tokens = tokens;
+        self.write_artifact(Emit::Tokens, || {
+            self.tokens
+                .iter()
+                .map(|token| {
+                    format!(
+                        "{}:{} {:?} {:?}\n",
+                        token.span.start.line,
+                        token.span.start.column,
+                        token.kind,
+                        token.literal()
+                    )
+                })
+                .collect()
+        })?;
 
-        let mut parser = Parser::new(self.tokens.clone());
-        let ast = parser.parse()?;
+        self.write_artifact(Emit::Ast, || format!("{:#?}\n", main.ast))?;
+        self.write_artifact(Emit::Symbols, || main.resolver.dump_symbols())?;
 
-        let mut resolver = Resolver::new();
+        let ast = graph.program();
+        if self.project_type == ProjectType::Binary {
+            self.entry_point = Some(EntryPoint::from_ast(&ast)?);
+        }
 
-        resolver.resolve_ast(&ast)?;
+        Ok(ast)
+    }
+}
 
-        Ok(())
+#[cfg(feature = "llvm")]
+impl Project {
+    pub fn build_main<'ctx>(&mut self, context: &'ctx Context) -> Result<CodeGen<'ctx>> {
+        let ast = self.analyze_main()?;
+
+        let mut codegen = CodeGen::new(context, &self.manifest.name, self.profile.opt_level)?;
+        if self.profile.debug {
+            codegen.enable_debug_info(&self.main_file(), &self.sources, self.profile.opt_level > 0);
+        }
+        codegen.compile(&ast)?;
+
+        let machine = host_target_machine(self.profile.opt_level)?;
+        codegen.optimize(&machine, self.profile.opt_level)?;
+
+        log::debug!("Generated LLVM IR:\n{}", codegen.print_ir());
+        self.write_artifact(Emit::LlvmIr, || codegen.print_ir())?;
+
+        if self.emit.contains(&Emit::Asm) || self.emit.contains(&Emit::Obj) {
+            fs::create_dir_all(self.build_dir())?;
+
+            if self.emit.contains(&Emit::Asm) {
+                codegen.write_assembly_file(&machine, &self.artifact_path(Emit::Asm))?;
+                log::info!("Emitted {:?}", self.artifact_path(Emit::Asm));
+            }
+            if self.emit.contains(&Emit::Obj) {
+                codegen.write_object_file(&machine, &self.artifact_path(Emit::Obj))?;
+                log::info!("Emitted {:?}", self.artifact_path(Emit::Obj));
+            }
+        }
+
+        Ok(codegen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// A copy of the fixture project `name`, so `build/` stays out of the tree.
+    fn fixture(name: &str) -> PathBuf {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        let root = env::temp_dir().join(format!("pulse-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::copy(fixture.join("pulse.toml"), root.join("pulse.toml")).unwrap();
+        for entry in fs::read_dir(fixture.join("src")).unwrap() {
+            let entry = entry.unwrap();
+            fs::copy(entry.path(), root.join("src").join(entry.file_name())).unwrap();
+        }
+
+        root
+    }
+
+    fn golden(file: &str) -> String {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(file);
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn emitted_stages_match_the_golden_files() {
+        let root = fixture("emit");
+        let manifest = Manifest::from_file(&root.join("pulse.toml")).unwrap();
+        let mut project = Project::from_path(root.clone(), ProjectType::Binary, manifest);
+        project.emit = vec![Emit::Tokens, Emit::Symbols];
+
+        project.analyze_main().unwrap();
+        let tokens = fs::read_to_string(project.artifact_path(Emit::Tokens)).unwrap();
+        let symbols = fs::read_to_string(project.artifact_path(Emit::Symbols)).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(tokens, golden("emit.tokens"));
+        assert_eq!(symbols, golden("emit.symbols"));
     }
 }
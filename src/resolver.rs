@@ -1,13 +1,21 @@
 use crate::{
     ast::ast::*,
-    error::PulseError::{ResolverError, SemanticError},
+    error::{
+        PulseError,
+        PulseError::{Errors, ResolverError, SemanticError},
+    },
     lexer::{
         span::TextSpan,
         token::{Token, TokenKind},
     },
+    stdlib::StdFn,
 };
 use anyhow::Result;
-use std::{collections::HashMap, rc::Rc};
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    fmt::{Display, Formatter},
+    rc::Rc,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Type {
@@ -16,16 +24,169 @@ pub enum Type {
     Bool,
     String,
     Void,
+    /// `start..end` over ints, the only thing a `for` loop can iterate so far.
+    Range,
+    /// A user-defined struct, looked up by name in `Resolver::structs`, with the type
+    /// arguments of a generic struct like `Pair<Int, String>`.
+    Struct(String, Vec<Type>),
+    /// A user-defined enum, looked up by name in `Resolver::enums`.
+    Enum(String),
+    /// `[T]`, or `[T; N]` when the length is known at compile time.
+    Array(Box<Type>, Option<usize>),
+    /// A function or closure taking the parameter types and returning the second type.
+    Function(Vec<Type>, Box<Type>),
+    /// A type parameter like the `T` of `fn max<T>`, only seen inside the declaration
+    /// that introduces it.
+    Param(String),
+    /// The type of an expression that already failed to resolve. It is compatible with
+    /// everything so one mistake doesn't cascade into follow-on errors.
+    Unknown,
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Struct(name, args) if !args.is_empty() => {
+                let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                write!(f, "{}<{}>", name, args.join(", "))
+            }
+            Type::Struct(name, _) | Type::Enum(name) | Type::Param(name) => write!(f, "{}", name),
+            Type::Array(element, Some(size)) => write!(f, "[{}; {}]", element, size),
+            Type::Array(element, None) => write!(f, "[{}]", element),
+            Type::Function(params, return_type) => {
+                let params: Vec<String> = params.iter().map(|param| param.to_string()).collect();
+                write!(f, "fn({})", params.join(", "))?;
+                match return_type.as_ref() {
+                    Type::Void => Ok(()),
+                    return_type => write!(f, " -> {}", return_type),
+                }
+            }
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// The fields of a declared struct, in declaration order.
+#[derive(Debug, Clone)]
+pub struct StructDef {
+    pub type_params: Vec<TypeParam>,
+    pub fields: Vec<(String, Type)>,
+}
+
+impl StructDef {
+    pub fn field(&self, name: &str) -> Option<&Type> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, field_type)| field_type)
+    }
+
+    /// The type of a field in the instance of this struct with `args` for its type
+    /// parameters.
+    pub fn instance_field(&self, name: &str, args: &[Type]) -> Option<Type> {
+        let bindings = self
+            .type_params
+            .iter()
+            .map(|type_param| type_param.name.clone())
+            .zip(args.iter().cloned())
+            .collect();
+        self.field(name)
+            .map(|field_type| substitute(field_type, &bindings))
+    }
+}
+
+/// The variants of a declared enum, in declaration order.
+#[derive(Debug, Clone)]
+pub struct EnumDef {
+    pub variants: Vec<VariantDef>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VariantDef {
+    pub name: String,
+    pub fields: Vec<Type>,
+}
+
+/// A function of an `impl` block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodDef {
+    /// Whether it takes `self`, otherwise it's only called as `Type::name(...)`.
+    pub receiver: bool,
+    pub params: Vec<Type>,
+    pub return_type: Type,
+}
+
+impl Display for MethodDef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let receiver = self.receiver.then(|| "self".to_string());
+        let params: Vec<String> = receiver
+            .into_iter()
+            .chain(self.params.iter().map(|param| param.to_string()))
+            .collect();
+        write!(f, "fn({})", params.join(", "))?;
+        match &self.return_type {
+            Type::Void => Ok(()),
+            return_type => write!(f, " -> {}", return_type),
+        }
+    }
+}
+
+/// A type parameter of a generic function or struct, with the interfaces its type
+/// argument must implement. For functions, also the operators the body applies to
+/// values of that type. Every call checks both against the inferred type argument.
+#[derive(Debug, Clone)]
+pub struct TypeParam {
+    pub name: String,
+    pub bounds: Vec<String>,
+    pub operators: Vec<BinOpKind>,
+}
+
+/// The methods a type must have to implement an interface, in declaration order.
+#[derive(Debug, Clone)]
+pub struct InterfaceDef {
+    pub methods: Vec<(String, MethodDef)>,
+}
+
+/// What the arms of a `match` seen so far already handle.
+#[derive(Debug, Default)]
+struct Coverage {
+    wildcard: bool,
+    variants: Vec<String>,
+    literals: Vec<LiteralType>,
+}
+
+/// A call in a generic function passing its type parameter `param` on as the type parameter
+/// `callee_param` of the generic function `callee`.
+#[derive(Debug, Clone)]
+struct ForwardedParam {
+    param: String,
+    callee: String,
+    callee_param: String,
 }
 
+/// Functions every program can call without importing them.
+const BUILTINS: [&str; 1] = ["len"];
+
 #[derive(Debug, Clone)]
 pub struct Symbol {
     name: String,
     symbol_type: Type,
     is_mutable: bool,
+    /// Empty unless this is a generic function.
+    type_params: Vec<TypeParam>,
+}
+
+/// A top-level function of a module, as seen by the modules importing it.
+#[derive(Debug, Clone)]
+pub struct ModuleFn {
+    symbol: Symbol,
+    exported: bool,
 }
 
-#[derive(Debug)]
+/// The top-level functions of a resolved module, by name.
+pub type ModuleDef = HashMap<String, ModuleFn>;
+
+#[derive(Debug, Default)]
 pub struct Scope {
     symbols: HashMap<String, Symbol>,
 }
@@ -38,11 +199,14 @@ impl Scope {
     }
 
     fn define(&mut self, name: String, symbol: Symbol) -> Result<()> {
-        if self.symbols.contains_key(&name) {
-            Err(ResolverError(format!("Duplicate declaration of '{}'", name)).into())
-        } else {
-            self.symbols.insert(name, symbol);
-            Ok(())
+        match self.symbols.entry(name) {
+            Entry::Occupied(entry) => {
+                Err(ResolverError(format!("Duplicate declaration of '{}'", entry.key())).into())
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(symbol);
+                Ok(())
+            }
         }
     }
 
@@ -51,19 +215,71 @@ impl Scope {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Resolver {
     pub scopes: Vec<Scope>,
     pub current_function_return_type: Option<Type>,
+    /// Labels of the loops enclosing the current statement, innermost last.
+    pub loops: Vec<Option<String>>,
+    pub structs: HashMap<String, StructDef>,
+    pub enums: HashMap<String, EnumDef>,
+    /// The enum each variant belongs to. Variants are global constructors, `Circle(1.0)`.
+    pub variants: HashMap<String, String>,
+    /// Methods and associated functions by type name, then by function name.
+    pub methods: HashMap<String, HashMap<String, MethodDef>>,
+    pub interfaces: HashMap<String, InterfaceDef>,
+    /// The interfaces each type has an `impl` block for.
+    pub implementations: HashMap<String, Vec<String>>,
+    /// Types of the `match` expressions resolved so far, keyed by the position of their
+    /// `match` token. Arms may use pattern bindings that are out of scope by the time
+    /// `infer_expr_type` looks at the `match`.
+    match_types: HashMap<usize, Type>,
+    /// Types of the closures resolved so far, keyed by the position of their `fn` token.
+    closure_types: HashMap<usize, Type>,
+    /// How many scopes were open when each enclosing closure started, innermost last.
+    /// Variables from scopes below that are captured by the closure.
+    closures: Vec<usize>,
+    /// The type parameters of the generic function or struct being resolved.
+    type_params: Vec<TypeParam>,
+    /// Types and signatures declared ahead of the bodies by `resolve_ast`, keyed by the
+    /// position of their name, `fn` or `impl` token.
+    hoisted_types: HashSet<usize>,
+    hoisted_fns: HashMap<usize, (MethodDef, Vec<TypeParam>)>,
+    hoisted_impls: HashMap<usize, (Type, Vec<MethodDef>)>,
+    /// Type parameters passed on by the generic function being resolved, then by every
+    /// top-level generic function once its body is resolved.
+    forwarded_params: Vec<ForwardedParam>,
+    forwarded_by_fn: HashMap<String, Vec<ForwardedParam>>,
+    /// Modules imported with a relative `use`, keyed by the module as written. `Err`
+    /// explains why the module couldn't be loaded.
+    pub imports: HashMap<String, Result<ModuleDef, String>>,
+    pub errors: Vec<PulseError>,
 }
 
 impl Resolver {
     pub fn new() -> Self {
-        let mut resolver = Resolver {
+        Resolver {
             scopes: vec![Scope::new()],
             current_function_return_type: None,
-        };
-        resolver
+            loops: vec![],
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            variants: HashMap::new(),
+            methods: HashMap::new(),
+            interfaces: HashMap::new(),
+            implementations: HashMap::new(),
+            match_types: HashMap::new(),
+            closure_types: HashMap::new(),
+            closures: vec![],
+            type_params: vec![],
+            hoisted_types: HashSet::new(),
+            hoisted_fns: HashMap::new(),
+            hoisted_impls: HashMap::new(),
+            forwarded_params: vec![],
+            forwarded_by_fn: HashMap::new(),
+            imports: HashMap::new(),
+            errors: vec![],
+        }
     }
 
     fn begin_scope(&mut self) {
@@ -74,12 +290,42 @@ impl Resolver {
         self.scopes.pop();
     }
 
-    fn define_symbol(&mut self, name: String, symbol: Symbol, span: TextSpan) -> Result<()> {
+    fn report(&mut self, message: String, span: TextSpan) {
+        self.errors.push(SemanticError(message, span));
+    }
+
+    fn define_symbol(&mut self, name: String, symbol: Symbol, span: TextSpan) {
         if let Some(scope) = self.scopes.last_mut() {
-            scope.define(name.clone(), symbol.clone())?;
+            if scope.define(name.clone(), symbol).is_err() {
+                self.report(format!("Duplicate declaration of '{}'", name), span);
+            }
         };
+    }
 
-        Ok(())
+    fn is_type_declared(&self, name: &str) -> bool {
+        self.structs.contains_key(name)
+            || self.enums.contains_key(name)
+            || self.interfaces.contains_key(name)
+    }
+
+    fn variant_def(&self, name: &str) -> Option<&VariantDef> {
+        let enum_def = self.enums.get(self.variants.get(name)?)?;
+        enum_def
+            .variants
+            .iter()
+            .find(|variant| variant.name == name)
+    }
+
+    /// Whether `name` refers to a local of a function enclosing the current closure.
+    fn is_captured(&self, name: &str) -> bool {
+        let Some(&boundary) = self.closures.last() else {
+            return false;
+        };
+        // The global scope only holds functions, types and imports
+        self.scopes
+            .iter()
+            .rposition(|scope| scope.resolve(name).is_some())
+            .is_some_and(|depth| depth > 0 && depth < boundary)
     }
 
     fn resolve_symbol(&self, name: &str) -> Option<Rc<Symbol>> {
@@ -91,377 +337,2270 @@ impl Resolver {
         None
     }
 
-    pub fn resolve_ast(&mut self, ast: &Ast) -> Result<()> {
-        for stmt in &ast.stmts {
-            self.resolve_stmt(stmt)?;
-        }
+    /// The top-level functions of `ast`, once this resolver went through it.
+    pub fn module_def(&self, ast: &Ast) -> ModuleDef {
+        ast.stmts
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Fn(fn_stmt) => {
+                    let symbol = self.scopes.first()?.symbols.get(&fn_stmt.name)?.clone();
+                    let function = ModuleFn {
+                        symbol,
+                        exported: fn_stmt.exported,
+                    };
+                    Some((fn_stmt.name.clone(), function))
+                }
+                _ => None,
+            })
+            .collect()
+    }
 
-        Ok(())
+    /// Lists the declared structs, enums and the symbols left in the global scope, one
+    /// `name: Type` per line.
+    pub fn dump_symbols(&self) -> String {
+        let mut structs: Vec<(&String, &StructDef)> = self.structs.iter().collect();
+        structs.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut symbols: Vec<&Symbol> = self
+            .scopes
+            .iter()
+            .flat_map(|scope| scope.symbols.values())
+            .collect();
+        symbols.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let structs = structs.iter().map(|(name, def)| {
+            let fields: Vec<String> = def
+                .fields
+                .iter()
+                .map(|(field, field_type)| format!("{}: {}", field, field_type))
+                .collect();
+            let type_params = if def.type_params.is_empty() {
+                String::new()
+            } else {
+                let names: Vec<&str> = def
+                    .type_params
+                    .iter()
+                    .map(|type_param| type_param.name.as_str())
+                    .collect();
+                format!("<{}>", names.join(", "))
+            };
+            format!(
+                "struct {}{} {{ {} }}\n",
+                name,
+                type_params,
+                fields.join(", ")
+            )
+        });
+
+        let mut enums: Vec<(&String, &EnumDef)> = self.enums.iter().collect();
+        enums.sort_by(|a, b| a.0.cmp(b.0));
+        let enums = enums.iter().map(|(name, def)| {
+            let variants: Vec<String> = def
+                .variants
+                .iter()
+                .map(|variant| {
+                    if variant.fields.is_empty() {
+                        return variant.name.clone();
+                    }
+                    let fields: Vec<String> = variant
+                        .fields
+                        .iter()
+                        .map(|field| field.to_string())
+                        .collect();
+                    format!("{}({})", variant.name, fields.join(", "))
+                })
+                .collect();
+            format!("enum {} {{ {} }}\n", name, variants.join(", "))
+        });
+
+        let mut methods: Vec<(String, &MethodDef)> = self
+            .methods
+            .iter()
+            .flat_map(|(type_name, methods)| {
+                methods
+                    .iter()
+                    .map(|(name, def)| (qualified_name(type_name, name), def))
+            })
+            .collect();
+        methods.sort_by(|a, b| a.0.cmp(&b.0));
+        let methods = methods
+            .into_iter()
+            .map(|(name, def)| format!("{}: {}\n", name, def.return_type));
+
+        let mut interfaces: Vec<(&String, &InterfaceDef)> = self.interfaces.iter().collect();
+        interfaces.sort_by(|a, b| a.0.cmp(b.0));
+        let interfaces = interfaces.iter().map(|(name, def)| {
+            let methods: Vec<String> = def
+                .methods
+                .iter()
+                .map(|(method, signature)| format!("{}: {}", method, signature))
+                .collect();
+            format!("interface {} {{ {} }}\n", name, methods.join(", "))
+        });
+
+        structs
+            .chain(enums)
+            .chain(interfaces)
+            .chain(methods)
+            .chain(symbols.iter().map(|symbol| {
+                format!(
+                    "{}: {}{}\n",
+                    symbol.name,
+                    symbol.symbol_type,
+                    if symbol.is_mutable { " (mutable)" } else { "" }
+                )
+            }))
+            .collect()
     }
 
-    fn resolve_stmt(&mut self, stmt: &Stmt) -> Result<()> {
-        match stmt {
-            Stmt::Use(use_stmt) => {
-                self.resolve_use(use_stmt)?;
-            }
-            Stmt::Fn(fn_stmt) => {
-                self.resolve_fn(fn_stmt)?;
-            }
-            Stmt::Let(let_stmt) => {
-                self.resolve_let(let_stmt)?;
-            }
-            Stmt::If(if_stmt) => {
-                self.resolve_if(if_stmt)?;
-            }
-            Stmt::Return(ret_stmt) => {
-                self.resolve_return(ret_stmt)?;
-            }
-            Stmt::Expr(expr) => {
-                self.resolve_expr(expr)?;
-            }
-            Stmt::Block(block) => {
-                self.resolve_block(block)?;
+    /// Resolves the whole tree, returning every semantic error found as `Errors`.
+    pub fn resolve_ast(&mut self, ast: &Ast) -> Result<()> {
+        // Imports and types come first, then every function and method signature, so
+        // bodies can call anything declared at the top level wherever it is. Type names
+        // are declared before any field, payload or member refers to them.
+        let (declarations, stmts): (Vec<&Stmt>, Vec<&Stmt>) = ast.stmts.iter().partition(|stmt| {
+            matches!(
+                stmt,
+                Stmt::Use(_) | Stmt::Struct(_) | Stmt::Enum(_) | Stmt::Interface(_)
+            )
+        });
+        self.declare_types(&declarations);
+        for stmt in &declarations {
+            self.resolve_stmt(stmt);
+        }
+        self.check_containment(&declarations);
+        for stmt in &stmts {
+            match stmt {
+                Stmt::Fn(fn_stmt) => {
+                    let declared = self.declare_fn(fn_stmt);
+                    self.hoisted_fns
+                        .insert(fn_stmt.fn_token.span.start.index, declared);
+                }
+                Stmt::Impl(impl_stmt) => {
+                    let declared = self.declare_impl(impl_stmt);
+                    self.hoisted_impls
+                        .insert(impl_stmt.impl_token.span.start.index, declared);
+                }
+                _ => {}
             }
         }
 
-        Ok(())
-    }
+        // The bodies of generic functions tell which operators the type arguments of
+        // their calls have to support, so they are resolved before the calls
+        let (generic_fns, stmts): (Vec<&Stmt>, Vec<&Stmt>) = stmts
+            .into_iter()
+            .partition(|stmt| matches!(stmt, Stmt::Fn(fn_stmt) if !fn_stmt.type_params.is_empty()));
+        for stmt in generic_fns {
+            self.resolve_stmt(stmt);
+        }
+        self.propagate_operators();
+        for stmt in stmts {
+            self.resolve_stmt(stmt);
+        }
 
-    // TODO:
-    fn resolve_use(&mut self, use_stmt: &Use) -> Result<()> {
-        let from_module = use_stmt.from.literal();
-        for item in &use_stmt.items {
-            let item_name = item.literal();
-            let symbol = Symbol {
-                name: item_name.clone(),
-                symbol_type: Type::Void,
-                is_mutable: false,
-            };
-            self.define_symbol(item_name, symbol, item.span.clone())?;
+        if !self.errors.is_empty() {
+            return Err(Errors(std::mem::take(&mut self.errors)).into());
         }
 
         Ok(())
     }
 
-    fn resolve_fn(&mut self, fn_stmt: &Fn) -> Result<()> {
-        let return_type = if let Some(ftype) = &fn_stmt.return_type {
-            self.map_type(&ftype.type_name)
-        } else {
-            Type::Void
-        };
-
-        let fn_name = fn_stmt.name.clone();
-        let fn_symbol = Symbol {
-            name: fn_name.clone(),
-            symbol_type: return_type.clone(),
-            is_mutable: false,
-        };
-        self.define_symbol(fn_name.clone(), fn_symbol, fn_stmt.fn_token.span.clone())?;
+    /// Registers the name of every struct, enum and interface in `stmts`, and the type
+    /// parameters of the structs. Fields, payloads and members are resolved later.
+    fn declare_types(&mut self, stmts: &[&Stmt]) {
+        for stmt in stmts {
+            let name = match stmt {
+                Stmt::Struct(struct_stmt) => &struct_stmt.name,
+                Stmt::Enum(enum_stmt) => &enum_stmt.name,
+                Stmt::Interface(interface) => &interface.name,
+                _ => continue,
+            };
+            let type_name = name.literal();
+            // Duplicates are reported when they are resolved
+            if self.is_type_declared(&type_name) {
+                continue;
+            }
 
-        let previous_return_type = self.current_function_return_type.clone();
-        self.current_function_return_type = Some(return_type.clone());
+            match stmt {
+                Stmt::Struct(_) => {
+                    self.structs.insert(
+                        type_name,
+                        StructDef {
+                            type_params: vec![],
+                            fields: vec![],
+                        },
+                    );
+                }
+                Stmt::Enum(_) => {
+                    self.enums.insert(type_name, EnumDef { variants: vec![] });
+                }
+                _ => {
+                    self.interfaces
+                        .insert(type_name, InterfaceDef { methods: vec![] });
+                }
+            }
+            self.hoisted_types.insert(name.span.start.index);
+        }
 
-        self.begin_scope();
+        // Bounds name interfaces, which are all known by now
+        for stmt in stmts {
+            let Stmt::Struct(struct_stmt) = stmt else {
+                continue;
+            };
+            if self
+                .hoisted_types
+                .contains(&struct_stmt.name.span.start.index)
+            {
+                let type_params = self.declare_type_params(&struct_stmt.type_params);
+                if let Some(def) = self.structs.get_mut(&struct_stmt.name.literal()) {
+                    def.type_params = type_params;
+                }
+            }
+        }
+    }
 
-        for param in &fn_stmt.params {
-            let param_name = param.ident.literal();
-            let param_type = self.map_type(&param.type_annotation.type_name);
-            let param_symbol = Symbol {
-                name: param_name.clone(),
-                symbol_type: param_type.clone(),
-                is_mutable: true,
+    /// Reports the structs and enums of `stmts` holding a value of their own type, directly
+    /// or through other types, which would make them infinitely large.
+    fn check_containment(&mut self, stmts: &[&Stmt]) {
+        for stmt in stmts {
+            let (kind, name) = match stmt {
+                Stmt::Struct(struct_stmt) => ("Struct", &struct_stmt.name),
+                Stmt::Enum(enum_stmt) => ("Enum", &enum_stmt.name),
+                _ => continue,
             };
-            self.define_symbol(param_name, param_symbol, param.ident.span.clone())?;
+            let type_name = name.literal();
+            let mut visited = HashSet::new();
+            if self
+                .held_types(&type_name)
+                .iter()
+                .any(|held| self.holds(held, &type_name, &mut visited))
+            {
+                self.report(
+                    format!("{} '{}' cannot contain itself", kind, type_name),
+                    name.span.clone(),
+                );
+            }
         }
+    }
 
-        self.resolve_block(&fn_stmt.body)?;
+    /// The types of the fields or payloads of the struct or enum `name`.
+    fn held_types(&self, name: &str) -> Vec<Type> {
+        if let Some(def) = self.structs.get(name) {
+            return def.fields.iter().map(|(_, ty)| ty.clone()).collect();
+        }
+        match self.enums.get(name) {
+            Some(def) => def
+                .variants
+                .iter()
+                .flat_map(|variant| variant.fields.iter().cloned())
+                .collect(),
+            None => vec![],
+        }
+    }
 
-        self.end_scope();
+    /// Whether a value of type `ty` holds a value of the struct or enum `name`. Functions
+    /// are only referenced, so they don't count.
+    fn holds(&self, ty: &Type, name: &str, visited: &mut HashSet<String>) -> bool {
+        let (other, args) = match ty {
+            Type::Array(element, _) => return self.holds(element, name, visited),
+            Type::Struct(other, args) => (other, args.as_slice()),
+            Type::Enum(other) => (other, [].as_slice()),
+            _ => return false,
+        };
 
-        self.current_function_return_type = previous_return_type;
+        other == name
+            || args.iter().any(|arg| self.holds(arg, name, visited))
+            || (visited.insert(other.clone())
+                && self
+                    .held_types(other)
+                    .iter()
+                    .any(|held| self.holds(held, name, visited)))
+    }
 
-        Ok(())
+    fn resolve_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Use(use_stmt) => self.resolve_use(use_stmt),
+            Stmt::Fn(fn_stmt) => self.resolve_fn(fn_stmt),
+            Stmt::Let(let_stmt) => self.resolve_let(let_stmt),
+            Stmt::If(if_stmt) => self.resolve_if(if_stmt),
+            Stmt::Return(ret_stmt) => self.resolve_return(ret_stmt),
+            // The arms of a `match` statement don't have to agree on a type
+            Stmt::Expr(expr) => match expr.as_ref() {
+                Expr::Match(match_expr) => self.resolve_match(match_expr, false),
+                _ => self.resolve_expr(expr),
+            },
+            Stmt::Block(block) => self.resolve_block(block),
+            Stmt::While(while_stmt) => self.resolve_while(while_stmt),
+            Stmt::For(for_stmt) => self.resolve_for(for_stmt),
+            Stmt::Loop(loop_stmt) => self.resolve_loop_body(&loop_stmt.label, &loop_stmt.body),
+            Stmt::Break(break_stmt) => {
+                self.resolve_loop_control("break", &break_stmt.break_token, &break_stmt.label)
+            }
+            Stmt::Continue(continue_stmt) => self.resolve_loop_control(
+                "continue",
+                &continue_stmt.continue_token,
+                &continue_stmt.label,
+            ),
+            Stmt::Struct(struct_stmt) => self.resolve_struct(struct_stmt),
+            Stmt::Enum(enum_stmt) => self.resolve_enum(enum_stmt),
+            Stmt::Impl(impl_stmt) => self.resolve_impl(impl_stmt),
+            Stmt::Interface(interface) => self.resolve_interface(interface),
+        }
     }
 
-    fn resolve_let(&mut self, let_stmt: &Let) -> Result<()> {
-        let var_name = let_stmt.ident.literal();
-        let var_type = if let Some(type_annotation) = &let_stmt.type_annotation {
-            self.map_type(&type_annotation.type_name)
+    fn resolve_use(&mut self, use_stmt: &Use) {
+        let module = use_stmt.module();
+        let functions = if use_stmt.is_relative() {
+            match self.imports.get(&module) {
+                Some(Ok(functions)) => functions.clone(),
+                Some(Err(message)) => {
+                    self.report(message.clone(), use_stmt.from.span.clone());
+                    return;
+                }
+                None => {
+                    self.report(
+                        format!("Cannot find module '{}'", module),
+                        use_stmt.from.span.clone(),
+                    );
+                    return;
+                }
+            }
         } else {
-            self.infer_expr_type(&let_stmt.initializer)?
-        };
-        let var_symbol = Symbol {
-            name: var_name.clone(),
-            symbol_type: var_type.clone(),
-            is_mutable: true,
+            let functions = std_module_def(&module);
+            if functions.is_empty() {
+                let message = if module.starts_with("std::") {
+                    format!("The standard library has no module '{}'", module)
+                } else {
+                    format!(
+                        "Cannot find module '{}', modules of the project are imported with a path like './{}'",
+                        module, module
+                    )
+                };
+                self.report(message, use_stmt.from.span.clone());
+                return;
+            }
+            functions
         };
-        self.define_symbol(var_name.clone(), var_symbol, let_stmt.ident.span.clone())?;
 
-        self.resolve_expr(&let_stmt.initializer)?;
-
-        Ok(())
+        for item in &use_stmt.items {
+            let item_name = item.literal();
+            match functions.get(&item_name) {
+                Some(function) if function.exported => {
+                    self.define_symbol(item_name, function.symbol.clone(), item.span.clone())
+                }
+                Some(_) => self.report(
+                    format!("Function '{}' is not exported by '{}'", item_name, module),
+                    item.span.clone(),
+                ),
+                None => self.report(
+                    format!("Module '{}' has no function '{}'", module, item_name),
+                    item.span.clone(),
+                ),
+            }
+        }
     }
 
-    fn resolve_if(&mut self, if_stmt: &If) -> Result<()> {
-        self.resolve_expr(&if_stmt.condition)?;
+    fn resolve_fn(&mut self, fn_stmt: &Fn) {
+        let (signature, type_params) =
+            match self.hoisted_fns.remove(&fn_stmt.fn_token.span.start.index) {
+                Some(declared) => declared,
+                None => self.declare_fn(fn_stmt),
+            };
+        let enclosing_type_params = std::mem::replace(&mut self.type_params, type_params);
+        let forwarded_start = self.forwarded_params.len();
 
-        let cond_type = self.infer_expr_type(&if_stmt.condition)?;
-        if cond_type != Type::Bool {
-            return Err(SemanticError(
-                format!(
-                    "Condition in 'if' statement must be of type Bool, found {:?}",
-                    cond_type
-                ),
-                if_stmt.if_token.span.clone(),
-            )
-            .into());
-        }
+        self.resolve_fn_body(fn_stmt, &signature, None);
 
-        self.begin_scope();
-        self.resolve_block(&if_stmt.then_block)?;
-        self.end_scope();
+        // Only the body tells which operators the type arguments have to support
+        let type_params = std::mem::replace(&mut self.type_params, enclosing_type_params);
+        let forwarded = self.forwarded_params.split_off(forwarded_start);
+        if !type_params.is_empty() {
+            if self.scopes.len() == 1 {
+                self.forwarded_by_fn.insert(fn_stmt.name.clone(), forwarded);
+            }
+            if let Some(symbol) = self
+                .scopes
+                .last_mut()
+                .and_then(|scope| scope.symbols.get_mut(&fn_stmt.name))
+            {
+                symbol.type_params = type_params;
+            }
+        }
+    }
 
-        for else_if in &if_stmt.else_ifs {
-            self.resolve_expr(&else_if.condition)?;
+    /// Defines the symbol of `fn_stmt` from its signature. Returns the signature and the
+    /// type parameters its body sees.
+    fn declare_fn(&mut self, fn_stmt: &Fn) -> (MethodDef, Vec<TypeParam>) {
+        // A nested function doesn't see the type parameters of the one enclosing it
+        let type_params = self.declare_type_params(&fn_stmt.type_params);
+        let enclosing_type_params = std::mem::replace(&mut self.type_params, type_params);
 
-            let else_if_cond_type = self.infer_expr_type(&else_if.condition)?;
-            if else_if_cond_type != Type::Bool {
-                return Err(SemanticError(
+        let signature = self.method_def(fn_stmt);
+        self.reject_receiver(fn_stmt);
+        for type_param in &fn_stmt.type_params {
+            let name = type_param.name.literal();
+            if !signature.params.iter().any(|param| mentions(param, &name)) {
+                self.report(
                     format!(
-                        "Condition in 'else if' statement must be of type Bool, found {:?}",
-                        else_if_cond_type
+                        "Type parameter '{}' is not used by the parameters of '{}', so it can't be inferred",
+                        name, fn_stmt.name
                     ),
-                    else_if.condition.span(),
-                )
-                .into());
+                    type_param.name.span.clone(),
+                );
             }
-
-            self.begin_scope();
-            self.resolve_block(&else_if.block)?;
-            self.end_scope();
         }
 
-        if let Some(else_block) = &if_stmt.else_block {
-            self.begin_scope();
-            self.resolve_block(&else_block.block)?;
-            self.end_scope();
+        let fn_name = fn_stmt.name.clone();
+        if BUILTINS.contains(&fn_name.as_str()) {
+            self.report(
+                format!("Cannot redefine the builtin function '{}'", fn_name),
+                fn_stmt.fn_token.span.clone(),
+            );
         }
+        let fn_symbol = Symbol {
+            name: fn_name.clone(),
+            symbol_type: Type::Function(
+                signature.params.clone(),
+                Box::new(signature.return_type.clone()),
+            ),
+            is_mutable: false,
+            type_params: self.type_params.clone(),
+        };
+        self.define_symbol(fn_name, fn_symbol, fn_stmt.fn_token.span.clone());
 
-        Ok(())
+        let type_params = std::mem::replace(&mut self.type_params, enclosing_type_params);
+        (signature, type_params)
     }
 
-    fn resolve_return(&mut self, ret_stmt: &Return) -> Result<()> {
-        if let Some(expr) = &ret_stmt.expr {
-            self.resolve_expr(expr)?;
-            let expr_type = self.infer_expr_type(expr)?;
-            if let Some(expected_type) = &self.current_function_return_type {
-                if &expr_type != expected_type {
-                    return Err(SemanticError(
-                        format!(
-                            "Type mismatch in return statement: expected {:?}, found {:?}",
-                            expected_type, expr_type
-                        ),
-                        ret_stmt.return_token.span.clone(),
-                    )
-                    .into());
-                }
+    /// The type parameters of a generic declaration, reporting the duplicates and bounds
+    /// that aren't interfaces.
+    fn declare_type_params(&mut self, params: &[GenericParam]) -> Vec<TypeParam> {
+        let mut type_params: Vec<TypeParam> = vec![];
+        for param in params {
+            let name = param.name.literal();
+            if type_params.iter().any(|existing| existing.name == name) {
+                self.report(
+                    format!("Duplicate type parameter '{}'", name),
+                    param.name.span.clone(),
+                );
+                continue;
             }
-        } else {
-            if let Some(expected_type) = &self.current_function_return_type {
-                if *expected_type != Type::Void {
-                    return Err(SemanticError(
-                        format!(
-                            "Return statement missing expression: expected return type {:?}",
-                            expected_type
-                        ),
-                        ret_stmt.return_token.span.clone(),
-                    )
-                    .into());
+
+            let mut bounds = vec![];
+            for bound in &param.bounds {
+                if self.interfaces.contains_key(&bound.literal()) {
+                    bounds.push(bound.literal());
+                } else {
+                    self.report(
+                        format!("Undefined interface '{}'", bound.literal()),
+                        bound.span.clone(),
+                    );
                 }
             }
-        }
 
-        Ok(())
+            type_params.push(TypeParam {
+                name,
+                bounds,
+                operators: vec![],
+            });
+        }
+        type_params
     }
 
-    fn resolve_block(&mut self, block: &Block) -> Result<()> {
-        self.begin_scope();
-        for stmt in &block.stmts {
-            self.resolve_stmt(stmt)?;
+    /// Whether values of type `ty` have the methods of `interface`.
+    fn implements(&self, ty: &Type, interface: &str) -> bool {
+        match ty {
+            Type::Struct(name, _) | Type::Enum(name) => self
+                .implementations
+                .get(name)
+                .is_some_and(|interfaces| interfaces.iter().any(|existing| existing == interface)),
+            Type::Param(name) => self.type_params.iter().any(|type_param| {
+                type_param.name == *name && type_param.bounds.iter().any(|bound| bound == interface)
+            }),
+            Type::Unknown => true,
+            _ => false,
         }
-        self.end_scope();
-
-        Ok(())
     }
 
-    fn resolve_expr(&mut self, expr: &Expr) -> Result<()> {
-        match expr {
-            Expr::Literal(_) => Ok(()),
-            Expr::Variable(var) => {
-                self.resolve_variable(var)?;
-                Ok(())
-            }
-            Expr::Binary(bin) => {
-                self.resolve_expr(&bin.left)?;
-                self.resolve_expr(&bin.right)?;
-                self.check_binary_operator(bin)?;
-                Ok(())
-            }
-            Expr::Unary(un) => {
-                self.resolve_expr(&un.expr)?;
-                self.check_unary_operator(un)?;
-                Ok(())
+    /// Reports the bounds of `type_param` that `type_arg` doesn't satisfy, in a use of the
+    /// generic function or struct `owner`.
+    fn check_bounds(
+        &mut self,
+        owner: &str,
+        type_param: &TypeParam,
+        type_arg: &Type,
+        span: &TextSpan,
+    ) {
+        for bound in &type_param.bounds {
+            if !self.implements(type_arg, bound) {
+                self.report(
+                    format!(
+                        "Type {} doesn't implement interface '{}', which '{}' requires for '{}'",
+                        type_arg, bound, owner, type_param.name
+                    ),
+                    span.clone(),
+                );
             }
-            Expr::Logical(logical) => {
-                self.resolve_expr(&logical.left)?;
-                self.resolve_expr(&logical.right)?;
-                let left_type = self.infer_expr_type(&logical.left)?;
-                let right_type = self.infer_expr_type(&logical.right)?;
-                if left_type != Type::Bool || right_type != Type::Bool {
-                    return Err(SemanticError(
-                        "Logical operations require Bool operands".to_string(),
-                        logical.token.span.clone(),
-                    )
-                    .into());
-                }
+        }
+    }
 
-                Ok(())
+    /// Records that the body of the generic function applies `operator` to values of the
+    /// type parameter `name`.
+    fn require_operator(&mut self, name: &str, operator: BinOpKind) {
+        if let Some(type_param) = self
+            .type_params
+            .iter_mut()
+            .find(|type_param| type_param.name == name)
+        {
+            if !type_param.operators.contains(&operator) {
+                type_param.operators.push(operator);
             }
-            Expr::Call(call) => {
-                let callee_symbol = self.resolve_symbol(&call.callee);
-                if callee_symbol.is_none() {
-                    return Err(SemanticError(
-                        format!("Undefined function '{}'", call.callee),
-                        call.token.span.clone(),
-                    )
-                    .into());
-                }
+        }
+    }
 
-                for arg in &call.args {
-                    self.resolve_expr(arg)?;
-                }
+    /// Carries the operators every top-level generic function applies to its type
+    /// parameters over to the callers passing theirs on, until nothing changes. A body may
+    /// call a generic function whose own body wasn't resolved yet.
+    fn propagate_operators(&mut self) {
+        let Some(globals) = self.scopes.first_mut() else {
+            return;
+        };
 
-                Ok(())
-            }
-            Expr::Assign(assign) => {
-                self.resolve_assign(assign)?;
-                Ok(())
-            }
-            Expr::Parenthesized(paren) => {
-                self.resolve_expr(&paren.expr)?;
-                Ok(())
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (caller, forwarded) in &self.forwarded_by_fn {
+                for forward in forwarded {
+                    let operators = globals
+                        .symbols
+                        .get(&forward.callee)
+                        .and_then(|callee| {
+                            callee
+                                .type_params
+                                .iter()
+                                .find(|type_param| type_param.name == forward.callee_param)
+                        })
+                        .map(|type_param| type_param.operators.clone())
+                        .unwrap_or_default();
+                    let Some(type_param) = globals.symbols.get_mut(caller).and_then(|caller| {
+                        caller
+                            .type_params
+                            .iter_mut()
+                            .find(|type_param| type_param.name == forward.param)
+                    }) else {
+                        continue;
+                    };
+
+                    for operator in operators {
+                        if !type_param.operators.contains(&operator) {
+                            type_param.operators.push(operator);
+                            changed = true;
+                        }
+                    }
+                }
             }
         }
     }
 
-    fn resolve_variable(&mut self, var: &Variable) -> Result<()> {
-        if self.resolve_symbol(&var.ident).is_none() {
-            return Err(SemanticError(
-                format!("Undefined variable '{}'", var.ident),
-                var.token.span.clone(),
-            )
-            .into());
-        }
+    fn resolve_closure(&mut self, closure: &Closure) {
+        let function = &closure.function;
+        let signature = self.method_def(function);
+        self.reject_receiver(function);
 
-        Ok(())
+        self.closure_types.insert(
+            function.fn_token.span.start.index,
+            Type::Function(
+                signature.params.clone(),
+                Box::new(signature.return_type.clone()),
+            ),
+        );
+
+        self.closures.push(self.scopes.len());
+        self.resolve_fn_body(function, &signature, None);
+        self.closures.pop();
     }
 
-    fn resolve_assign(&mut self, assign: &Assign) -> Result<()> {
-        if let Some(symbol) = self.resolve_symbol(&assign.ident.literal()) {
-            if !symbol.is_mutable {
-                return Err(SemanticError(
-                    format!("Cannot assign to immutable variable '{}'", symbol.name),
-                    assign.token.span.clone(),
-                )
-                .into());
+    fn reject_receiver(&mut self, fn_stmt: &Fn) {
+        if let Some(receiver) = &fn_stmt.receiver {
+            self.report(
+                "Only functions in an 'impl' block can take 'self'".to_string(),
+                receiver.span.clone(),
+            );
+        }
+    }
+
+    /// The parameter and return types of `fn_stmt`, not counting `self`.
+    fn method_def(&mut self, fn_stmt: &Fn) -> MethodDef {
+        self.signature_def(
+            fn_stmt.receiver.is_some(),
+            &fn_stmt.params,
+            &fn_stmt.return_type,
+        )
+    }
+
+    fn signature_def(
+        &mut self,
+        receiver: bool,
+        fn_params: &[FnParam],
+        fn_return_type: &Option<FunctionType>,
+    ) -> MethodDef {
+        let mut params = vec![];
+        for param in fn_params {
+            params.push(self.map_type(&param.type_annotation.type_name));
+        }
+
+        let return_type = if let Some(ftype) = fn_return_type {
+            self.map_type(&ftype.type_name)
+        } else {
+            Type::Void
+        };
+
+        MethodDef {
+            receiver,
+            params,
+            return_type,
+        }
+    }
+
+    /// Resolves the body of a function or method in a new scope holding its parameters,
+    /// with `self` bound to `receiver_type` for methods.
+    fn resolve_fn_body(
+        &mut self,
+        fn_stmt: &Fn,
+        signature: &MethodDef,
+        receiver_type: Option<Type>,
+    ) {
+        let previous_return_type = self.current_function_return_type.clone();
+        self.current_function_return_type = Some(signature.return_type.clone());
+        let enclosing_loops = std::mem::take(&mut self.loops);
+
+        self.begin_scope();
+
+        if let (Some(receiver), Some(receiver_type)) = (&fn_stmt.receiver, receiver_type) {
+            let symbol = Symbol {
+                name: receiver.literal(),
+                symbol_type: receiver_type,
+                is_mutable: false,
+                type_params: vec![],
+            };
+            self.define_symbol(receiver.literal(), symbol, receiver.span.clone());
+        }
+
+        for (param, param_type) in fn_stmt.params.iter().zip(&signature.params) {
+            let param_name = param.ident.literal();
+            let param_symbol = Symbol {
+                name: param_name.clone(),
+                symbol_type: param_type.clone(),
+                is_mutable: true,
+                type_params: vec![],
+            };
+            self.define_symbol(param_name, param_symbol, param.ident.span.clone());
+        }
+
+        self.resolve_block(&fn_stmt.body);
+
+        self.end_scope();
+
+        self.current_function_return_type = previous_return_type;
+        self.loops = enclosing_loops;
+    }
+
+    fn resolve_struct(&mut self, struct_stmt: &Struct) {
+        let name = struct_stmt.name.literal();
+        if self.current_function_return_type.is_some() {
+            self.report(
+                format!("Struct '{}' must be declared at the top level", name),
+                struct_stmt.name.span.clone(),
+            );
+        }
+
+        let hoisted = self
+            .hoisted_types
+            .remove(&struct_stmt.name.span.start.index);
+        let type_params = match self.structs.get(&name) {
+            Some(def) if hoisted => def.type_params.clone(),
+            _ => self.declare_type_params(&struct_stmt.type_params),
+        };
+        let enclosing_type_params = std::mem::replace(&mut self.type_params, type_params);
+
+        let mut fields: Vec<(String, Type)> = vec![];
+        for field in &struct_stmt.fields {
+            let field_name = field.ident.literal();
+            let field_type = self.map_type(&field.type_annotation.type_name);
+
+            if field_type == Type::Void {
+                self.report(
+                    format!("Field '{}' cannot be of type Void", field_name),
+                    field.type_annotation.type_name.span(),
+                );
+            }
+            if fields.iter().any(|(existing, _)| *existing == field_name) {
+                self.report(
+                    format!("Duplicate field '{}' in struct '{}'", field_name, name),
+                    field.ident.span.clone(),
+                );
+                continue;
             }
-            let value_type = self.infer_expr_type(&assign.value)?;
-            if &symbol.symbol_type != &value_type {
-                return Err(SemanticError(
+
+            fields.push((field_name, field_type));
+        }
+
+        let type_params = std::mem::replace(&mut self.type_params, enclosing_type_params);
+        for type_param in &struct_stmt.type_params {
+            let param_name = type_param.name.literal();
+            if !fields
+                .iter()
+                .any(|(_, field_type)| mentions(field_type, &param_name))
+            {
+                self.report(
                     format!(
-                        "Type mismatch in assignment to '{}': expected {:?}, found {:?}",
-                        symbol.name, symbol.symbol_type, value_type
+                        "Type parameter '{}' is not used by the fields of '{}'",
+                        param_name, name
                     ),
-                    assign.token.span.clone(),
-                )
-                .into());
+                    type_param.name.span.clone(),
+                );
+            }
+        }
+
+        if !hoisted && self.is_type_declared(&name) {
+            self.report(
+                format!("Duplicate declaration of type '{}'", name),
+                struct_stmt.name.span.clone(),
+            );
+            return;
+        }
+        self.structs.insert(
+            name,
+            StructDef {
+                type_params,
+                fields,
+            },
+        );
+    }
+
+    fn resolve_impl(&mut self, impl_stmt: &Impl) {
+        let (self_type, signatures) = match self
+            .hoisted_impls
+            .remove(&impl_stmt.impl_token.span.start.index)
+        {
+            Some(declared) => declared,
+            None => self.declare_impl(impl_stmt),
+        };
+
+        for (method, signature) in impl_stmt.methods.iter().zip(&signatures) {
+            self.resolve_fn_body(method, signature, Some(self_type.clone()));
+        }
+    }
+
+    /// Registers the methods of `impl_stmt`. Returns the type they belong to and their
+    /// signatures.
+    fn declare_impl(&mut self, impl_stmt: &Impl) -> (Type, Vec<MethodDef>) {
+        let type_name = impl_stmt.target.literal();
+        if self.current_function_return_type.is_some() {
+            self.report(
+                format!("Impl for '{}' must be declared at the top level", type_name),
+                impl_stmt.target.span.clone(),
+            );
+        }
+
+        let self_type = if let Some(def) = self.structs.get(&type_name) {
+            if !def.type_params.is_empty() {
+                self.report(
+                    format!(
+                        "Methods on the generic struct '{}' are not supported",
+                        type_name
+                    ),
+                    impl_stmt.target.span.clone(),
+                );
+            }
+            Type::Struct(type_name.clone(), vec![])
+        } else if self.enums.contains_key(&type_name) {
+            Type::Enum(type_name.clone())
+        } else {
+            self.report(
+                format!("Undefined type '{}'", type_name),
+                impl_stmt.target.span.clone(),
+            );
+            Type::Unknown
+        };
+
+        // Every signature is registered before any body so methods can call each other
+        let mut signatures = vec![];
+        for method in &impl_stmt.methods {
+            if let Some(type_param) = method.type_params.first() {
+                self.report(
+                    format!("Method '{}' cannot have type parameters", method.name),
+                    type_param.name.span.clone(),
+                );
+            }
+            let signature = self.method_def(method);
+            let exists = self
+                .methods
+                .get(&type_name)
+                .is_some_and(|methods| methods.contains_key(&method.name));
+            if exists {
+                self.report(
+                    format!("Duplicate function '{}' on '{}'", method.name, type_name),
+                    method.fn_token.span.clone(),
+                );
+            } else {
+                self.methods
+                    .entry(type_name.clone())
+                    .or_default()
+                    .insert(method.name.clone(), signature.clone());
+            }
+            signatures.push(signature);
+        }
+
+        if let Some(interface) = &impl_stmt.interface {
+            self.check_implementation(impl_stmt, interface, &signatures);
+        }
+
+        (self_type, signatures)
+    }
+
+    /// Checks that `impl Display for Point` has exactly the methods of `Display`, with
+    /// the same signatures.
+    fn check_implementation(
+        &mut self,
+        impl_stmt: &Impl,
+        interface: &Token,
+        signatures: &[MethodDef],
+    ) {
+        let interface_name = interface.literal();
+        let type_name = impl_stmt.target.literal();
+        let Some(def) = self.interfaces.get(&interface_name).cloned() else {
+            self.report(
+                format!("Undefined interface '{}'", interface_name),
+                interface.span.clone(),
+            );
+            return;
+        };
+
+        for (method, signature) in impl_stmt.methods.iter().zip(signatures) {
+            match def.methods.iter().find(|(name, _)| *name == method.name) {
+                None => self.report(
+                    format!(
+                        "Method '{}' is not a member of interface '{}'",
+                        method.name, interface_name
+                    ),
+                    method.fn_token.span.clone(),
+                ),
+                Some((_, expected)) if expected != signature => self.report(
+                    format!(
+                        "Method '{}' doesn't match interface '{}': expected {}, found {}",
+                        method.name, interface_name, expected, signature
+                    ),
+                    method.fn_token.span.clone(),
+                ),
+                Some(_) => {}
+            }
+        }
+
+        let missing: Vec<String> = def
+            .methods
+            .iter()
+            .filter(|(name, _)| !impl_stmt.methods.iter().any(|method| method.name == *name))
+            .map(|(name, _)| format!("'{}'", name))
+            .collect();
+        if !missing.is_empty() {
+            self.report(
+                format!(
+                    "Missing {} {} of interface '{}' in impl for '{}'",
+                    if missing.len() == 1 {
+                        "method"
+                    } else {
+                        "methods"
+                    },
+                    missing.join(", "),
+                    interface_name,
+                    type_name
+                ),
+                TextSpan::combine(vec![
+                    impl_stmt.impl_token.span.clone(),
+                    impl_stmt.target.span.clone(),
+                ]),
+            );
+        }
+
+        let interfaces = self.implementations.entry(type_name).or_default();
+        if !interfaces.contains(&interface_name) {
+            interfaces.push(interface_name);
+        }
+    }
+
+    fn resolve_interface(&mut self, interface: &Interface) {
+        let name = interface.name.literal();
+        if self.current_function_return_type.is_some() {
+            self.report(
+                format!("Interface '{}' must be declared at the top level", name),
+                interface.name.span.clone(),
+            );
+        }
+
+        let hoisted = self.hoisted_types.remove(&interface.name.span.start.index);
+        let mut methods: Vec<(String, MethodDef)> = vec![];
+        for method in &interface.methods {
+            let method_name = method.name.literal();
+            let signature = self.signature_def(
+                method.receiver.is_some(),
+                &method.params,
+                &method.return_type,
+            );
+
+            if method.receiver.is_none() {
+                self.report(
+                    format!(
+                        "Method '{}' of interface '{}' must take 'self'",
+                        method_name, name
+                    ),
+                    method.name.span.clone(),
+                );
+            }
+            if methods.iter().any(|(existing, _)| *existing == method_name) {
+                self.report(
+                    format!("Duplicate method '{}' in interface '{}'", method_name, name),
+                    method.name.span.clone(),
+                );
+                continue;
+            }
+
+            methods.push((method_name, signature));
+        }
+
+        if !hoisted && self.is_type_declared(&name) {
+            self.report(
+                format!("Duplicate declaration of type '{}'", name),
+                interface.name.span.clone(),
+            );
+            return;
+        }
+        self.interfaces.insert(name, InterfaceDef { methods });
+    }
+
+    fn resolve_enum(&mut self, enum_stmt: &Enum) {
+        let name = enum_stmt.name.literal();
+        if self.current_function_return_type.is_some() {
+            self.report(
+                format!("Enum '{}' must be declared at the top level", name),
+                enum_stmt.name.span.clone(),
+            );
+        }
+
+        let hoisted = self.hoisted_types.remove(&enum_stmt.name.span.start.index);
+        let mut variants: Vec<VariantDef> = vec![];
+        for variant in &enum_stmt.variants {
+            let variant_name = variant.ident.literal();
+
+            let mut fields = vec![];
+            for type_name in &variant.fields {
+                let field_type = self.map_type(type_name);
+                if field_type == Type::Void {
+                    self.report(
+                        format!("Variant '{}' cannot hold a Void value", variant_name),
+                        type_name.span(),
+                    );
+                }
+                fields.push(field_type);
+            }
+
+            if variants
+                .iter()
+                .any(|existing| existing.name == variant_name)
+            {
+                self.report(
+                    format!("Duplicate variant '{}' in enum '{}'", variant_name, name),
+                    variant.ident.span.clone(),
+                );
+                continue;
+            }
+
+            variants.push(VariantDef {
+                name: variant_name,
+                fields,
+            });
+        }
+
+        if !hoisted && self.is_type_declared(&name) {
+            self.report(
+                format!("Duplicate declaration of type '{}'", name),
+                enum_stmt.name.span.clone(),
+            );
+            return;
+        }
+
+        for (variant, def) in enum_stmt.variants.iter().zip(&variants) {
+            let symbol = Symbol {
+                name: def.name.clone(),
+                symbol_type: Type::Enum(name.clone()),
+                is_mutable: false,
+                type_params: vec![],
+            };
+            self.define_symbol(def.name.clone(), symbol, variant.ident.span.clone());
+            self.variants
+                .entry(def.name.clone())
+                .or_insert_with(|| name.clone());
+        }
+        self.enums.insert(name, EnumDef { variants });
+    }
+
+    fn resolve_let(&mut self, let_stmt: &Let) {
+        // Resolved first so the initializer can't refer to the variable being declared.
+        // An empty `[]` takes its element type from the annotation.
+        let annotated_empty_array =
+            let_stmt.type_annotation.is_some() && is_empty_array(&let_stmt.initializer);
+        if !annotated_empty_array {
+            self.resolve_expr(&let_stmt.initializer);
+        }
+
+        let var_name = let_stmt.ident.literal();
+        let var_type = if let Some(type_annotation) = &let_stmt.type_annotation {
+            let var_type = self.map_type(&type_annotation.type_name);
+            let init_type = self.infer_expr_type(&let_stmt.initializer);
+            if !compatible(&init_type, &var_type) {
+                self.report(
+                    format!(
+                        "Type mismatch in declaration of '{}': expected {}, found {}",
+                        var_name, var_type, init_type
+                    ),
+                    let_stmt.ident.span.clone(),
+                );
             }
+            var_type
         } else {
-            return Err(SemanticError(
-                format!("Undefined variable '{}'", assign.ident.literal()),
+            self.infer_expr_type(&let_stmt.initializer)
+        };
+        let var_symbol = Symbol {
+            name: var_name.clone(),
+            symbol_type: var_type.clone(),
+            is_mutable: true,
+            type_params: vec![],
+        };
+        self.define_symbol(var_name.clone(), var_symbol, let_stmt.ident.span.clone());
+    }
+
+    fn resolve_if(&mut self, if_stmt: &If) {
+        self.resolve_expr(&if_stmt.condition);
+
+        let cond_type = self.infer_expr_type(&if_stmt.condition);
+        if !compatible(&cond_type, &Type::Bool) {
+            self.report(
+                format!(
+                    "Condition in 'if' statement must be of type Bool, found {}",
+                    cond_type
+                ),
+                if_stmt.if_token.span.clone(),
+            );
+        }
+
+        self.begin_scope();
+        self.resolve_block(&if_stmt.then_block);
+        self.end_scope();
+
+        for else_if in &if_stmt.else_ifs {
+            self.resolve_expr(&else_if.condition);
+
+            let else_if_cond_type = self.infer_expr_type(&else_if.condition);
+            if !compatible(&else_if_cond_type, &Type::Bool) {
+                self.report(
+                    format!(
+                        "Condition in 'else if' statement must be of type Bool, found {}",
+                        else_if_cond_type
+                    ),
+                    else_if.condition.span(),
+                );
+            }
+
+            self.begin_scope();
+            self.resolve_block(&else_if.block);
+            self.end_scope();
+        }
+
+        if let Some(else_block) = &if_stmt.else_block {
+            self.begin_scope();
+            self.resolve_block(&else_block.block);
+            self.end_scope();
+        }
+    }
+
+    fn resolve_while(&mut self, while_stmt: &While) {
+        self.resolve_expr(&while_stmt.condition);
+
+        let cond_type = self.infer_expr_type(&while_stmt.condition);
+        if !compatible(&cond_type, &Type::Bool) {
+            self.report(
+                format!(
+                    "Condition in 'while' loop must be of type Bool, found {}",
+                    cond_type
+                ),
+                while_stmt.condition.span(),
+            );
+        }
+
+        self.resolve_loop_body(&while_stmt.label, &while_stmt.body);
+    }
+
+    fn resolve_for(&mut self, for_stmt: &For) {
+        self.resolve_expr(&for_stmt.iterable);
+
+        let iterable_type = self.infer_expr_type(&for_stmt.iterable);
+        let item_type = match iterable_type {
+            Type::Range => Type::Int,
+            Type::Unknown => Type::Unknown,
+            other => {
+                self.report(
+                    format!("Cannot iterate over a value of type {}", other),
+                    for_stmt.iterable.span(),
+                );
+                Type::Unknown
+            }
+        };
+
+        self.begin_scope();
+        let name = for_stmt.ident.literal();
+        let symbol = Symbol {
+            name: name.clone(),
+            symbol_type: item_type,
+            is_mutable: false,
+            type_params: vec![],
+        };
+        self.define_symbol(name, symbol, for_stmt.ident.span.clone());
+        self.resolve_loop_body(&for_stmt.label, &for_stmt.body);
+        self.end_scope();
+    }
+
+    fn resolve_loop_body(&mut self, label: &Option<Token>, body: &Block) {
+        self.loops.push(label.as_ref().map(|label| label.literal()));
+        self.resolve_block(body);
+        self.loops.pop();
+    }
+
+    fn resolve_loop_control(&mut self, keyword: &str, token: &Token, label: &Option<Token>) {
+        match label {
+            _ if self.loops.is_empty() => {
+                self.report(
+                    format!("'{}' outside of a loop", keyword),
+                    token.span.clone(),
+                );
+            }
+            Some(label) => {
+                let name = label.literal();
+                if !self
+                    .loops
+                    .iter()
+                    .any(|loop_label| loop_label.as_ref() == Some(&name))
+                {
+                    self.report(
+                        format!("Undefined loop label '{}'", name),
+                        label.span.clone(),
+                    );
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn resolve_return(&mut self, ret_stmt: &Return) {
+        let expected_type = self.current_function_return_type.clone();
+
+        if let Some(expr) = &ret_stmt.expr {
+            self.resolve_expr(expr);
+            let expr_type = self.infer_expr_type(expr);
+            if let Some(expected_type) = expected_type {
+                if !compatible(&expr_type, &expected_type) {
+                    self.report(
+                        format!(
+                            "Type mismatch in return statement: expected {}, found {}",
+                            expected_type, expr_type
+                        ),
+                        ret_stmt.return_token.span.clone(),
+                    );
+                }
+            }
+        } else if let Some(expected_type) = expected_type {
+            if !compatible(&expected_type, &Type::Void) {
+                self.report(
+                    format!(
+                        "Return statement missing expression: expected return type {}",
+                        expected_type
+                    ),
+                    ret_stmt.return_token.span.clone(),
+                );
+            }
+        }
+    }
+
+    fn resolve_block(&mut self, block: &Block) {
+        self.begin_scope();
+        for stmt in &block.stmts {
+            self.resolve_stmt(stmt);
+        }
+        self.end_scope();
+    }
+
+    fn resolve_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Literal(_) => {}
+            Expr::Variable(var) => self.resolve_variable(var),
+            Expr::Binary(bin) => {
+                self.resolve_expr(&bin.left);
+                self.resolve_expr(&bin.right);
+                self.check_binary_operator(bin);
+            }
+            Expr::Unary(un) => {
+                self.resolve_expr(&un.expr);
+                self.check_unary_operator(un);
+            }
+            Expr::Logical(logical) => {
+                self.resolve_expr(&logical.left);
+                self.resolve_expr(&logical.right);
+                let left_type = self.infer_expr_type(&logical.left);
+                let right_type = self.infer_expr_type(&logical.right);
+                if !compatible(&left_type, &Type::Bool) || !compatible(&right_type, &Type::Bool) {
+                    self.report(
+                        "Logical operations require Bool operands".to_string(),
+                        logical.token.span.clone(),
+                    );
+                }
+            }
+            Expr::Call(call) if is_builtin_call(call) => self.resolve_len(call),
+            Expr::Call(call) => self.resolve_call(call),
+            Expr::Path(path) => self.report(
+                format!(
+                    "Function '{}' has to be called",
+                    qualified_name(&path.type_name.literal(), &path.name.literal())
+                ),
+                expr.span(),
+            ),
+            Expr::Assign(assign) => self.resolve_assign(assign),
+            Expr::Parenthesized(paren) => self.resolve_expr(&paren.expr),
+            Expr::Range(range) => {
+                for bound in [&range.start, &range.end] {
+                    self.resolve_expr(bound);
+
+                    let bound_type = self.infer_expr_type(bound);
+                    if !compatible(&bound_type, &Type::Int) {
+                        self.report(
+                            format!("Range bounds must be of type Int, found {}", bound_type),
+                            bound.span(),
+                        );
+                    }
+                }
+            }
+            Expr::StructLiteral(literal) => self.resolve_struct_literal(literal),
+            Expr::Member(member) => {
+                self.resolve_expr(&member.object);
+                if let Err(message) = self.member_type(member) {
+                    self.report(message, member.field.span.clone());
+                }
+            }
+            Expr::Match(match_expr) => self.resolve_match(match_expr, true),
+            Expr::ArrayLiteral(literal) => self.resolve_array_literal(literal),
+            Expr::Index(index) => self.resolve_index(index),
+            Expr::Closure(closure) => self.resolve_closure(closure),
+        }
+    }
+
+    fn resolve_array_literal(&mut self, literal: &ArrayLiteral) {
+        let Some((first, rest)) = literal.elements.split_first() else {
+            self.report(
+                "Cannot infer the element type of an empty array, annotate the variable like 'let a: [int] = []'"
+                    .to_string(),
+                literal.open.span.clone(),
+            );
+            return;
+        };
+
+        self.resolve_expr(first);
+        let element_type = self.infer_expr_type(first);
+        if element_type == Type::Void {
+            self.report("Arrays cannot hold Void values".to_string(), first.span());
+        }
+
+        for element in rest {
+            self.resolve_expr(element);
+
+            let found = self.infer_expr_type(element);
+            if !compatible(&found, &element_type) {
+                self.report(
+                    format!(
+                        "Array elements must have the same type: expected {}, found {}",
+                        element_type, found
+                    ),
+                    element.span(),
+                );
+            }
+        }
+    }
+
+    fn resolve_index(&mut self, index: &Index) {
+        self.resolve_expr(&index.object);
+        self.resolve_expr(&index.index);
+
+        let index_type = self.infer_expr_type(&index.index);
+        if !compatible(&index_type, &Type::Int) {
+            self.report(
+                format!("Array index must be of type Int, found {}", index_type),
+                index.index.span(),
+            );
+        }
+
+        match self.infer_expr_type(&index.object) {
+            Type::Array(_, Some(size)) => {
+                let out_of_bounds = constant_int(&index.index)
+                    .filter(|value| usize::try_from(*value).map_or(true, |value| value >= size));
+                if let Some(value) = out_of_bounds {
+                    self.report(
+                        format!("Index {} out of bounds for array of length {}", value, size),
+                        index.index.span(),
+                    );
+                }
+            }
+            Type::Array(_, None) | Type::Unknown => {}
+            other => self.report(
+                format!("Cannot index into a value of type {}", other),
+                index.object.span(),
+            ),
+        }
+    }
+
+    fn resolve_call(&mut self, call: &CallExpr) {
+        match call.callee.as_ref() {
+            Expr::Variable(var) => match self.resolve_symbol(&var.ident) {
+                None => self.report(
+                    format!("Undefined function '{}'", var.ident),
+                    call.token.span.clone(),
+                ),
+                Some(symbol)
+                    if self.variant_def(&var.ident).is_none()
+                        && !is_callable(&symbol.symbol_type) =>
+                {
+                    self.report(
+                        format!(
+                            "'{}' is not a function, it has type {}",
+                            var.ident, symbol.symbol_type
+                        ),
+                        call.token.span.clone(),
+                    )
+                }
+                Some(_) => {}
+            },
+            Expr::Member(member) => {
+                self.resolve_expr(&member.object);
+                // A field holding a function is called when there's no such method
+                let holds_function = matches!(self.member_type(member), Ok(Type::Function(..)));
+                if let Err(message) = self.method(member) {
+                    if !holds_function {
+                        self.report(message, member.field.span.clone());
+                    }
+                }
+            }
+            Expr::Path(path) => {
+                if let Err(message) = self.associated_fn(path) {
+                    self.report(message, call.callee.span());
+                }
+            }
+            other => {
+                self.resolve_expr(other);
+
+                let callee_type = self.infer_expr_type(other);
+                if !is_callable(&callee_type) {
+                    self.report(
+                        format!("Cannot call a value of type {}", callee_type),
+                        other.span(),
+                    );
+                }
+            }
+        }
+
+        for arg in &call.args {
+            self.resolve_expr(arg);
+        }
+
+        let variant = call
+            .function_name()
+            .and_then(|name| self.variant_def(name))
+            .cloned();
+        if let Some(variant) = variant {
+            self.check_variant_values(call, &variant);
+        }
+
+        match self.generic_callee(call) {
+            Some(symbol) => {
+                if let Type::Function(params, _) = &symbol.symbol_type {
+                    if self.check_arity(call, &symbol.name, params.len()) {
+                        self.check_type_args(call, &symbol);
+                    }
+                }
+            }
+            None => {
+                if let Some((name, params)) = self.callee_params(call) {
+                    self.check_args(call, &name, &params);
+                }
+            }
+        }
+    }
+
+    /// The name and parameter types of what `call` calls, when they are known. Variants
+    /// and `len` are checked on their own.
+    fn callee_params(&self, call: &CallExpr) -> Option<(String, Vec<Type>)> {
+        match call.callee.as_ref() {
+            Expr::Variable(var) if self.variant_def(&var.ident).is_some() => None,
+            Expr::Variable(var) => match &self.resolve_symbol(&var.ident)?.symbol_type {
+                Type::Function(params, _) => Some((var.ident.clone(), params.clone())),
+                _ => None,
+            },
+            Expr::Member(member) => {
+                let name = member.field.literal();
+                match self.method(member) {
+                    Ok(def) => def.map(|def| (name, def.params.clone())),
+                    Err(_) => match self.member_type(member) {
+                        Ok(Type::Function(params, _)) => Some((name, params)),
+                        _ => None,
+                    },
+                }
+            }
+            Expr::Path(path) => {
+                let type_name = path.type_name.literal();
+                let def = self.associated_fn(path).ok()?;
+                let mut params = def.params.clone();
+                if def.receiver {
+                    let self_type = if self.structs.contains_key(&type_name) {
+                        Type::Struct(type_name.clone(), vec![])
+                    } else {
+                        Type::Enum(type_name.clone())
+                    };
+                    params.insert(0, self_type);
+                }
+                Some((qualified_name(&type_name, &path.name.literal()), params))
+            }
+            other => match self.infer_expr_type(other) {
+                Type::Function(params, _) => Some(("closure".to_string(), params)),
+                _ => None,
+            },
+        }
+    }
+
+    /// Reports a call to `name` without exactly `expected` arguments, at the first extra
+    /// argument if there are too many.
+    fn check_arity(&mut self, call: &CallExpr, name: &str, expected: usize) -> bool {
+        if call.args.len() == expected {
+            return true;
+        }
+
+        let span = call
+            .args
+            .get(expected)
+            .map_or(call.token.span.clone(), |arg| arg.span());
+        self.report(
+            format!(
+                "Function '{}' expects {} argument{}, found {}",
+                name,
+                expected,
+                if expected == 1 { "" } else { "s" },
+                call.args.len()
+            ),
+            span,
+        );
+
+        false
+    }
+
+    /// Checks the arguments of a call to `name` against the types of its parameters.
+    fn check_args(&mut self, call: &CallExpr, name: &str, params: &[Type]) {
+        if !self.check_arity(call, name, params.len()) {
+            return;
+        }
+
+        for (index, (arg, param)) in call.args.iter().zip(params).enumerate() {
+            let arg_type = self.infer_expr_type(arg);
+            if !compatible(&arg_type, param) {
+                self.report(
+                    format!(
+                        "Type mismatch in argument {} of '{}': expected {}, found {}",
+                        index + 1,
+                        name,
+                        param,
+                        arg_type
+                    ),
+                    arg.span(),
+                );
+            }
+        }
+    }
+
+    /// The generic function `call` calls by name, if it calls one.
+    fn generic_callee(&self, call: &CallExpr) -> Option<Rc<Symbol>> {
+        self.resolve_symbol(call.function_name()?)
+            .filter(|symbol| !symbol.type_params.is_empty())
+    }
+
+    /// Infers the type arguments of a call to a generic function from the arguments and
+    /// checks them against the operators the function applies to them.
+    fn check_type_args(&mut self, call: &CallExpr, symbol: &Symbol) {
+        let Type::Function(params, _) = &symbol.symbol_type else {
+            return;
+        };
+
+        let mut bindings = HashMap::new();
+        for (index, (arg, param)) in call.args.iter().zip(params).enumerate() {
+            let arg_type = self.infer_expr_type(arg);
+            if !unify(param, &arg_type, &mut bindings) {
+                self.report(
+                    format!(
+                        "Type mismatch in argument {} of '{}': expected {}, found {}",
+                        index + 1,
+                        symbol.name,
+                        substitute(param, &bindings),
+                        arg_type
+                    ),
+                    arg.span(),
+                );
+            }
+        }
+
+        for type_param in &symbol.type_params {
+            let Some(type_arg) = bindings.get(&type_param.name) else {
+                continue;
+            };
+            // The callee may not be resolved yet, `propagate_operators` catches up on the
+            // operators it applies
+            if let Type::Param(name) = type_arg {
+                self.forwarded_params.push(ForwardedParam {
+                    param: name.clone(),
+                    callee: symbol.name.clone(),
+                    callee_param: type_param.name.clone(),
+                });
+            }
+            for operator in &type_param.operators {
+                // Inside another generic function the requirement carries over to its
+                // own type parameter
+                if let Type::Param(name) = type_arg {
+                    self.require_operator(name, *operator);
+                } else if let Some(message) = unsupported_operator(*operator, type_arg) {
+                    self.report(
+                        format!(
+                            "{}, but '{}' applies it to its type parameter '{}'",
+                            message, symbol.name, type_param.name
+                        ),
+                        call.token.span.clone(),
+                    );
+                }
+            }
+
+            // Pointing at the first argument the type argument was inferred from
+            let span = call
+                .args
+                .iter()
+                .zip(params)
+                .find(|(_, param)| mentions(param, &type_param.name))
+                .map_or(call.token.span.clone(), |(arg, _)| arg.span());
+            self.check_bounds(&symbol.name, type_param, type_arg, &span);
+        }
+    }
+
+    /// What a call to a generic function returns with the type arguments inferred from
+    /// `args`.
+    fn generic_return_type(&self, symbol: &Symbol, args: &[Expr]) -> Type {
+        let Type::Function(params, return_type) = &symbol.symbol_type else {
+            return Type::Unknown;
+        };
+
+        let mut bindings = HashMap::new();
+        for (arg, param) in args.iter().zip(params) {
+            unify(param, &self.infer_expr_type(arg), &mut bindings);
+        }
+        substitute(return_type, &bindings)
+    }
+
+    /// The method `object.name(...)` calls, `None` when the type of `object` is unknown.
+    fn method(&self, member: &Member) -> Result<Option<&MethodDef>, String> {
+        let name = member.field.literal();
+        let type_name = match self.infer_expr_type(&member.object) {
+            Type::Struct(type_name, _) | Type::Enum(type_name) => type_name,
+            // Inside a generic function, the methods of the interfaces `T` is bound by
+            Type::Param(param) => {
+                return self
+                    .type_params
+                    .iter()
+                    .filter(|type_param| type_param.name == param)
+                    .flat_map(|type_param| &type_param.bounds)
+                    .filter_map(|bound| self.interfaces.get(bound))
+                    .flat_map(|def| &def.methods)
+                    .find(|(method, _)| *method == name)
+                    .map(|(_, def)| Some(def))
+                    .ok_or_else(|| format!("No method '{}' on type {}", name, param))
+            }
+            Type::Unknown => return Ok(None),
+            other => return Err(format!("No method '{}' on type {}", name, other)),
+        };
+
+        match self
+            .methods
+            .get(&type_name)
+            .and_then(|methods| methods.get(&name))
+        {
+            Some(def) if def.receiver => Ok(Some(def)),
+            Some(_) => Err(format!(
+                "'{}' doesn't take 'self', call it as '{}(...)'",
+                name,
+                qualified_name(&type_name, &name)
+            )),
+            None => Err(format!("No method '{}' on type {}", name, type_name)),
+        }
+    }
+
+    /// The function `Type::name(...)` calls. Methods taking `self` can be called this
+    /// way too, with the receiver as the first argument.
+    fn associated_fn(&self, path: &Path) -> Result<&MethodDef, String> {
+        let type_name = path.type_name.literal();
+        let name = path.name.literal();
+        if !self.is_type_declared(&type_name) {
+            return Err(format!("Undefined type '{}'", type_name));
+        }
+
+        self.methods
+            .get(&type_name)
+            .and_then(|methods| methods.get(&name))
+            .ok_or_else(|| format!("No function '{}' on type {}", name, type_name))
+    }
+
+    /// `len(x)`, the number of elements of an array or bytes of a string.
+    fn resolve_len(&mut self, call: &CallExpr) {
+        for arg in &call.args {
+            self.resolve_expr(arg);
+        }
+
+        let [arg] = call.args.as_slice() else {
+            self.report(
+                format!(
+                    "Function '{}' expects 1 argument, found {}",
+                    call.token.literal(),
+                    call.args.len()
+                ),
+                call.token.span.clone(),
+            );
+            return;
+        };
+
+        let arg_type = self.infer_expr_type(arg);
+        if !matches!(arg_type, Type::Array(..) | Type::String | Type::Unknown) {
+            self.report(
+                format!(
+                    "Function '{}' expects an array or a string, found {}",
+                    call.token.literal(),
+                    arg_type
+                ),
+                arg.span(),
+            );
+        }
+    }
+
+    /// Checks the values passed to a variant constructor like `Rect(1.0, 2.0)`.
+    fn check_variant_values(&mut self, call: &CallExpr, variant: &VariantDef) {
+        if call.args.len() != variant.fields.len() {
+            self.report(
+                format!(
+                    "Variant '{}' holds {} value(s), found {}",
+                    variant.name,
+                    variant.fields.len(),
+                    call.args.len()
+                ),
+                call.token.span.clone(),
+            );
+            return;
+        }
+
+        for (index, (arg, field_type)) in call.args.iter().zip(&variant.fields).enumerate() {
+            let arg_type = self.infer_expr_type(arg);
+            if !compatible(&arg_type, field_type) {
+                self.report(
+                    format!(
+                        "Type mismatch in value {} of '{}': expected {}, found {}",
+                        index + 1,
+                        variant.name,
+                        field_type,
+                        arg_type
+                    ),
+                    arg.span(),
+                );
+            }
+        }
+    }
+
+    /// Resolves every arm in its own scope holding the pattern bindings. The arms only
+    /// have to agree on a type when the `match` is `used_as_value`.
+    fn resolve_match(&mut self, match_expr: &Match, used_as_value: bool) {
+        self.resolve_expr(&match_expr.scrutinee);
+        let scrutinee_type = self.infer_expr_type(&match_expr.scrutinee);
+
+        let mut coverage = Coverage::default();
+        let mut match_type = Type::Unknown;
+        for arm in &match_expr.arms {
+            self.begin_scope();
+
+            let exhaustive = self.uncovered(&scrutinee_type, &coverage).is_none();
+            let covers_new_values =
+                self.check_pattern(&arm.pattern, &scrutinee_type, &mut coverage);
+            if exhaustive || !covers_new_values {
+                self.report(
+                    "Unreachable match arm, earlier arms already cover it".to_string(),
+                    arm.pattern.span(),
+                );
+            }
+
+            let (arm_type, span) = match &arm.body {
+                ArmBody::Expr(expr) => {
+                    self.resolve_expr(expr);
+                    (self.infer_expr_type(expr), expr.span())
+                }
+                ArmBody::Block(block) => {
+                    self.resolve_block(block);
+                    // A block that returns or leaves a loop never produces a value
+                    let arm_type = if diverges(block) {
+                        Type::Unknown
+                    } else {
+                        Type::Void
+                    };
+                    (arm_type, arm.pattern.span())
+                }
+            };
+
+            self.end_scope();
+
+            if !used_as_value {
+                continue;
+            }
+            if match_type == Type::Unknown {
+                match_type = arm_type;
+            } else if !compatible(&arm_type, &match_type) {
+                self.report(
+                    format!(
+                        "Match arms have incompatible types: expected {}, found {}",
+                        match_type, arm_type
+                    ),
+                    span,
+                );
+            }
+        }
+
+        if scrutinee_type != Type::Unknown {
+            if let Some(uncovered) = self.uncovered(&scrutinee_type, &coverage) {
+                self.report(
+                    format!("Non-exhaustive match: {}", uncovered),
+                    match_expr.match_token.span.clone(),
+                );
+            }
+        }
+
+        if !used_as_value {
+            match_type = Type::Void;
+        }
+        self.match_types
+            .insert(match_expr.match_token.span.start.index, match_type);
+    }
+
+    /// Checks `pattern` against the scrutinee type and defines its bindings in the current
+    /// scope. Returns whether it matches anything the earlier arms didn't.
+    fn check_pattern(
+        &mut self,
+        pattern: &Pattern,
+        scrutinee_type: &Type,
+        coverage: &mut Coverage,
+    ) -> bool {
+        match pattern {
+            Pattern::Wildcard(_) => {
+                coverage.wildcard = true;
+                true
+            }
+            Pattern::Literal(literal) => {
+                let literal_type = match literal.value {
+                    LiteralType::Int(_) => Type::Int,
+                    LiteralType::Float(_) => Type::Float,
+                    LiteralType::Bool(_) => Type::Bool,
+                    LiteralType::String(_) => Type::String,
+                    LiteralType::Null => Type::Void,
+                };
+                if !compatible(&literal_type, scrutinee_type) {
+                    self.report(
+                        format!(
+                            "Pattern of type {} cannot match a value of type {}",
+                            literal_type, scrutinee_type
+                        ),
+                        pattern.span(),
+                    );
+                }
+
+                if coverage.literals.contains(&literal.value) {
+                    return false;
+                }
+                coverage.literals.push(literal.value.clone());
+                true
+            }
+            Pattern::Variant { name, bindings } => {
+                let variant_name = name.literal();
+                let mut field_types = self.variant_pattern_types(pattern, scrutinee_type);
+                field_types.resize(bindings.len(), Type::Unknown);
+
+                for (binding, field_type) in bindings.iter().zip(field_types) {
+                    let binding_name = binding.literal();
+                    if binding_name == "_" {
+                        continue;
+                    }
+
+                    let symbol = Symbol {
+                        name: binding_name.clone(),
+                        symbol_type: field_type,
+                        is_mutable: false,
+                        type_params: vec![],
+                    };
+                    self.define_symbol(binding_name, symbol, binding.span.clone());
+                }
+
+                if coverage.variants.contains(&variant_name) {
+                    return false;
+                }
+                coverage.variants.push(variant_name);
+                true
+            }
+        }
+    }
+
+    /// The payload types a variant pattern binds, reporting variants that can't match.
+    fn variant_pattern_types(&mut self, pattern: &Pattern, scrutinee_type: &Type) -> Vec<Type> {
+        let Pattern::Variant { name, bindings } = pattern else {
+            return vec![];
+        };
+        let variant_name = name.literal();
+
+        let Some(enum_name) = self.variants.get(&variant_name).cloned() else {
+            self.report(
+                format!("Undefined variant '{}'", variant_name),
+                name.span.clone(),
+            );
+            return vec![];
+        };
+
+        match scrutinee_type {
+            Type::Enum(expected) if *expected != enum_name => {
+                self.report(
+                    format!(
+                        "Variant '{}' belongs to enum '{}', not '{}'",
+                        variant_name, enum_name, expected
+                    ),
+                    name.span.clone(),
+                );
+                vec![]
+            }
+            Type::Enum(_) | Type::Unknown => {
+                let fields = self
+                    .variant_def(&variant_name)
+                    .map(|variant| variant.fields.clone())
+                    .unwrap_or_default();
+
+                if fields.len() != bindings.len() {
+                    self.report(
+                        format!(
+                            "Variant '{}' holds {} value(s), but the pattern binds {}",
+                            variant_name,
+                            fields.len(),
+                            bindings.len()
+                        ),
+                        pattern.span(),
+                    );
+                }
+                fields
+            }
+            other => {
+                self.report(
+                    format!(
+                        "Variant '{}' cannot match a value of type {}",
+                        variant_name, other
+                    ),
+                    name.span.clone(),
+                );
+                vec![]
+            }
+        }
+    }
+
+    /// Describes the values of `ty` no arm covers yet, `None` once every value is covered.
+    fn uncovered(&self, ty: &Type, coverage: &Coverage) -> Option<String> {
+        if coverage.wildcard {
+            return None;
+        }
+
+        let missing: Vec<String> = match ty {
+            Type::Enum(name) => self
+                .enums
+                .get(name)?
+                .variants
+                .iter()
+                .filter(|variant| !coverage.variants.contains(&variant.name))
+                .map(|variant| format!("'{}'", variant.name))
+                .collect(),
+            Type::Bool => [true, false]
+                .into_iter()
+                .filter(|value| !coverage.literals.contains(&LiteralType::Bool(*value)))
+                .map(|value| format!("'{}'", value))
+                .collect(),
+            other => return Some(format!("values of type {} need a '_' arm", other)),
+        };
+
+        match missing.len() {
+            0 => None,
+            1 => Some(format!("{} is not covered", missing[0])),
+            _ => Some(format!("{} are not covered", missing.join(", "))),
+        }
+    }
+
+    fn resolve_struct_literal(&mut self, literal: &StructLiteral) {
+        for init in &literal.fields {
+            self.resolve_expr(&init.value);
+        }
+
+        let name = literal.name.literal();
+        let Some(def) = self.structs.get(&name).cloned() else {
+            self.report(
+                format!("Undefined struct '{}'", name),
+                literal.name.span.clone(),
+            );
+            return;
+        };
+
+        let mut bindings = HashMap::new();
+        let mut initialized: Vec<String> = vec![];
+        for init in &literal.fields {
+            let field_name = init.ident.literal();
+            let Some(field_type) = def.field(&field_name) else {
+                self.report(
+                    format!("Struct '{}' has no field '{}'", name, field_name),
+                    init.ident.span.clone(),
+                );
+                continue;
+            };
+
+            if initialized.contains(&field_name) {
+                self.report(
+                    format!("Field '{}' is initialized more than once", field_name),
+                    init.ident.span.clone(),
+                );
+            }
+            initialized.push(field_name.clone());
+
+            // The values of a generic struct decide its type arguments
+            let value_type = self.infer_expr_type(&init.value);
+            if !unify(field_type, &value_type, &mut bindings) {
+                self.report(
+                    format!(
+                        "Type mismatch in field '{}' of '{}': expected {}, found {}",
+                        field_name,
+                        name,
+                        substitute(field_type, &bindings),
+                        value_type
+                    ),
+                    init.value.span(),
+                );
+            }
+        }
+
+        for type_param in &def.type_params {
+            if let Some(type_arg) = bindings.get(&type_param.name) {
+                self.check_bounds(&name, type_param, type_arg, &literal.name.span);
+            }
+        }
+
+        let missing: Vec<String> = def
+            .fields
+            .iter()
+            .filter(|(field, _)| !initialized.contains(field))
+            .map(|(field, _)| format!("'{}'", field))
+            .collect();
+        if !missing.is_empty() {
+            self.report(
+                format!(
+                    "Missing {} {} in initializer of '{}'",
+                    if missing.len() == 1 {
+                        "field"
+                    } else {
+                        "fields"
+                    },
+                    missing.join(", "),
+                    name
+                ),
+                literal.name.span.clone(),
+            );
+        }
+    }
+
+    fn resolve_variable(&mut self, var: &Variable) {
+        match self.resolve_symbol(&var.ident) {
+            None => self.report(
+                format!("Undefined variable '{}'", var.ident),
+                var.token.span.clone(),
+            ),
+            Some(symbol) if !symbol.type_params.is_empty() => self.report(
+                format!(
+                    "Generic function '{}' has to be called, its type arguments are inferred from the arguments",
+                    var.ident
+                ),
+                var.token.span.clone(),
+            ),
+            Some(_) => {}
+        }
+
+        let holds_values = self
+            .variant_def(&var.ident)
+            .map(|variant| variant.fields.len())
+            .filter(|count| *count > 0);
+        if let Some(count) = holds_values {
+            self.report(
+                format!(
+                    "Variant '{}' holds {} value(s), use '{}(...)'",
+                    var.ident, count, var.ident
+                ),
+                var.token.span.clone(),
+            );
+        }
+    }
+
+    fn resolve_assign(&mut self, assign: &Assign) {
+        self.resolve_expr(&assign.value);
+        self.resolve_expr(&assign.target);
+
+        if self
+            .check_assignable(&assign.target, assign.token.span.clone())
+            .is_none()
+        {
+            return;
+        }
+        let target_type = self.infer_expr_type(&assign.target);
+        let value_type = self.infer_expr_type(&assign.value);
+        if !compatible(&value_type, &target_type) {
+            self.report(
+                format!(
+                    "Type mismatch in assignment to '{}': expected {}, found {}",
+                    assign.target.span().literal,
+                    target_type,
+                    value_type
+                ),
                 assign.token.span.clone(),
-            )
-            .into());
+            );
+        }
+    }
+
+    /// Reports assigning to `target`, at `span`, unless it's a mutable variable or a field or
+    /// element of one, declared inside the current closure if any. Returns the symbol of the
+    /// variable if there's one.
+    fn check_assignable(&mut self, target: &Expr, span: TextSpan) -> Option<Rc<Symbol>> {
+        let Some(var) = assigned_variable(target) else {
+            self.report(
+                "Only variables and their fields or elements can be assigned to".to_string(),
+                target.span(),
+            );
+            return None;
+        };
+        // An undefined variable is already reported
+        let symbol = self.resolve_symbol(&var.ident)?;
+
+        if !symbol.is_mutable {
+            self.report(
+                format!("Cannot assign to immutable variable '{}'", symbol.name),
+                span,
+            );
+        } else if self.is_captured(&var.ident) {
+            self.report(
+                format!(
+                    "Cannot assign to '{}' inside a closure, closures capture variables by value",
+                    symbol.name
+                ),
+                span,
+            );
         }
 
-        self.resolve_expr(&assign.value)?;
+        Some(symbol)
+    }
 
-        Ok(())
+    /// The type of `object.field`, or the reason it has none.
+    fn member_type(&self, member: &Member) -> Result<Type, String> {
+        let field = member.field.literal();
+        match self.infer_expr_type(&member.object) {
+            Type::Struct(name, args) => self
+                .structs
+                .get(&name)
+                .and_then(|def| def.instance_field(&field, &args))
+                .ok_or_else(|| format!("Struct '{}' has no field '{}'", name, field)),
+            Type::Unknown => Ok(Type::Unknown),
+            other => Err(format!("Type {} has no field '{}'", other, field)),
+        }
     }
 
-    fn infer_expr_type(&mut self, expr: &Expr) -> Result<Type> {
+    /// Types an expression without reporting anything, `resolve_expr` is responsible
+    /// for the errors. Anything that can't be typed is `Unknown`.
+    fn infer_expr_type(&self, expr: &Expr) -> Type {
         match expr {
-            Expr::Literal(lit) => Ok(match lit.value {
+            Expr::Literal(lit) => match lit.value {
                 LiteralType::Int(_) => Type::Int,
                 LiteralType::Float(_) => Type::Float,
                 LiteralType::Bool(_) => Type::Bool,
                 LiteralType::String(_) => Type::String,
                 LiteralType::Null => Type::Void,
-            }),
-            Expr::Variable(var) => {
-                if let Some(symbol) = self.resolve_symbol(&var.ident) {
-                    Ok(symbol.symbol_type.clone())
-                } else {
-                    Err(SemanticError(
-                        format!("Undefined variable '{}'", var.ident).to_string(),
-                        var.token.span.clone(),
-                    )
-                    .into())
-                }
-            }
+            },
+            Expr::Variable(var) => self
+                .resolve_symbol(&var.ident)
+                .map(|symbol| symbol.symbol_type.clone())
+                .unwrap_or(Type::Unknown),
             Expr::Binary(bin) => match bin.operator {
                 BinOpKind::GreaterThan
                 | BinOpKind::LessThan
                 | BinOpKind::Equals
                 | BinOpKind::NotEquals
                 | BinOpKind::LessThanOrEqual
-                | BinOpKind::GreaterThanOrEqual => Ok(Type::Bool),
-                BinOpKind::And | BinOpKind::Or => Ok(Type::Bool),
+                | BinOpKind::GreaterThanOrEqual => Type::Bool,
+                BinOpKind::And | BinOpKind::Or => Type::Bool,
                 BinOpKind::Plus
                 | BinOpKind::Minus
                 | BinOpKind::Multiply
                 | BinOpKind::Divide
                 | BinOpKind::Power
-                | BinOpKind::Modulo => {
-                    let left_type = self.infer_expr_type(&bin.left)?;
-                    let right_type = self.infer_expr_type(&bin.right)?;
+                | BinOpKind::Modulo
+                | BinOpKind::BitwiseAnd
+                | BinOpKind::BitwiseOr
+                | BinOpKind::BitwiseXor => {
+                    let left_type = self.infer_expr_type(&bin.left);
+                    let right_type = self.infer_expr_type(&bin.right);
                     if left_type == right_type {
-                        Ok(left_type)
+                        left_type
                     } else {
-                        Ok(Type::Void)
+                        Type::Unknown
                     }
                 }
-                _ => Ok(Type::Void),
+                BinOpKind::PlusEquals | BinOpKind::MinusEquals => self.infer_expr_type(&bin.left),
+                _ => Type::Void,
             },
             Expr::Unary(un) => self.infer_expr_type(&un.expr),
-            Expr::Logical(_) => Ok(Type::Bool),
-            Expr::Call(call) => {
-                if let Some(symbol) = self.resolve_symbol(&call.callee) {
-                    Ok(symbol.symbol_type.clone())
-                } else {
-                    Err(SemanticError(
-                        format!("Undefined function '{}'", call.callee).to_string(),
-                        call.token.span.clone(),
-                    )
-                    .into())
+            Expr::Logical(_) => Type::Bool,
+            Expr::Call(call) if is_builtin_call(call) => Type::Int,
+            Expr::Call(call) => match call.callee.as_ref() {
+                // Variant constructors are typed as the enum they build
+                Expr::Variable(var) if self.variants.contains_key(&var.ident) => {
+                    self.infer_expr_type(&call.callee)
                 }
-            }
+                Expr::Member(member) => match self.method(member) {
+                    Ok(Some(def)) => def.return_type.clone(),
+                    Ok(None) => Type::Unknown,
+                    Err(_) => return_type(self.member_type(member).unwrap_or(Type::Unknown)),
+                },
+                Expr::Path(path) => self
+                    .associated_fn(path)
+                    .map_or(Type::Unknown, |def| def.return_type.clone()),
+                callee => match self.generic_callee(call) {
+                    Some(symbol) => self.generic_return_type(&symbol, &call.args),
+                    None => return_type(self.infer_expr_type(callee)),
+                },
+            },
+            Expr::Path(_) => Type::Unknown,
             Expr::Assign(assign) => self.infer_expr_type(&assign.value),
             Expr::Parenthesized(paren) => self.infer_expr_type(&paren.expr),
+            Expr::Range(_) => Type::Range,
+            Expr::StructLiteral(literal) => {
+                let name = literal.name.literal();
+                let Some(def) = self.structs.get(&name) else {
+                    return Type::Unknown;
+                };
+
+                let mut bindings = HashMap::new();
+                for init in &literal.fields {
+                    if let Some(field_type) = def.field(&init.ident.literal()) {
+                        unify(
+                            field_type,
+                            &self.infer_expr_type(&init.value),
+                            &mut bindings,
+                        );
+                    }
+                }
+                let args = def
+                    .type_params
+                    .iter()
+                    .map(|param| bindings.get(&param.name).cloned().unwrap_or(Type::Unknown))
+                    .collect();
+                Type::Struct(name, args)
+            }
+            Expr::Member(member) => self.member_type(member).unwrap_or(Type::Unknown),
+            Expr::Match(match_expr) => self
+                .match_types
+                .get(&match_expr.match_token.span.start.index)
+                .cloned()
+                .unwrap_or(Type::Unknown),
+            Expr::ArrayLiteral(literal) => {
+                let element_type = literal
+                    .elements
+                    .first()
+                    .map_or(Type::Unknown, |first| self.infer_expr_type(first));
+                let size = match &literal.repeat {
+                    Some(count) => match count.kind {
+                        TokenKind::Integer(count) => count as usize,
+                        _ => 0,
+                    },
+                    None => literal.elements.len(),
+                };
+
+                Type::Array(Box::new(element_type), Some(size))
+            }
+            Expr::Index(index) => match self.infer_expr_type(&index.object) {
+                Type::Array(element, _) => *element,
+                _ => Type::Unknown,
+            },
+            Expr::Closure(closure) => self
+                .closure_types
+                .get(&closure.function.fn_token.span.start.index)
+                .cloned()
+                .unwrap_or(Type::Unknown),
         }
     }
 
-    fn map_type(&self, type_token: &Token) -> Type {
+    fn map_type(&mut self, type_name: &TypeName) -> Type {
+        let type_token = match type_name {
+            TypeName::Named(token) => token,
+            TypeName::Generic { name, args, .. } => {
+                let args: Vec<Type> = args.iter().map(|arg| self.map_type(arg)).collect();
+                return self.generic_struct_type(name, args);
+            }
+            TypeName::Array { element, .. } => {
+                let mut element_type = self.map_type(element);
+                if element_type == Type::Void {
+                    self.report("Arrays cannot hold Void values".to_string(), element.span());
+                    element_type = Type::Unknown;
+                }
+
+                return Type::Array(Box::new(element_type), type_name.size());
+            }
+            TypeName::Function {
+                params,
+                return_type,
+                ..
+            } => {
+                let mut param_types = vec![];
+                for param in params {
+                    let mut param_type = self.map_type(param);
+                    if param_type == Type::Void {
+                        self.report(
+                            "Parameters cannot be of type Void".to_string(),
+                            param.span(),
+                        );
+                        param_type = Type::Unknown;
+                    }
+                    param_types.push(param_type);
+                }
+                let return_type = match return_type {
+                    Some(return_type) => self.map_type(return_type),
+                    None => Type::Void,
+                };
+
+                return Type::Function(param_types, Box::new(return_type));
+            }
+        };
+
         match &type_token.kind {
             TokenKind::Identifier => match type_token.literal().as_str() {
                 "int" => Type::Int,
@@ -469,102 +2608,1026 @@ impl Resolver {
                 "bool" => Type::Bool,
                 "string" => Type::String,
                 "void" => Type::Void,
-                _ => Type::Void,
+                name if self.type_params.iter().any(|param| param.name == name) => {
+                    Type::Param(name.to_string())
+                }
+                name if self.structs.contains_key(name) => {
+                    self.generic_struct_type(type_token, vec![])
+                }
+                name if self.enums.contains_key(name) => Type::Enum(name.to_string()),
+                name if self.interfaces.contains_key(name) => {
+                    self.report(
+                        format!(
+                            "Interface '{}' can only be used as a bound, like '<T: {}>'",
+                            name, name
+                        ),
+                        type_token.span.clone(),
+                    );
+                    Type::Unknown
+                }
+                other => {
+                    self.report(format!("Unknown type '{}'", other), type_token.span.clone());
+                    Type::Unknown
+                }
             },
             _ => Type::Void,
         }
     }
 
-    fn check_binary_operator(&mut self, bin: &Binary) -> Result<()> {
-        let left_type = self.infer_expr_type(&bin.left)?;
-        let right_type = self.infer_expr_type(&bin.right)?;
-        match bin.operator {
+    /// `Pair<Int, String>`, checking the number of type arguments against the struct.
+    fn generic_struct_type(&mut self, name: &Token, args: Vec<Type>) -> Type {
+        let struct_name = name.literal();
+        let Some(def) = self.structs.get(&struct_name).cloned() else {
+            let message = if self.is_type_declared(&struct_name)
+                || matches!(
+                    struct_name.as_str(),
+                    "int" | "float" | "bool" | "string" | "void"
+                ) {
+                format!("Type '{}' doesn't take type arguments", struct_name)
+            } else {
+                format!("Unknown type '{}'", struct_name)
+            };
+            self.report(message, name.span.clone());
+            return Type::Unknown;
+        };
+
+        if def.type_params.len() != args.len() {
+            let message = format!(
+                "Struct '{}' expects {} type argument(s), found {}",
+                struct_name,
+                def.type_params.len(),
+                args.len()
+            );
+            self.report(message, name.span.clone());
+            return Type::Unknown;
+        }
+        if args.contains(&Type::Void) {
+            self.report(
+                "Type arguments cannot be Void".to_string(),
+                name.span.clone(),
+            );
+        }
+        for (type_param, arg) in def.type_params.iter().zip(&args) {
+            self.check_bounds(&struct_name, type_param, arg, &name.span);
+        }
+
+        Type::Struct(struct_name, args)
+    }
+
+    fn check_binary_operator(&mut self, bin: &Binary) {
+        if matches!(bin.operator, BinOpKind::PlusEquals | BinOpKind::MinusEquals) {
+            self.check_assignable(&bin.left, bin.span());
+        }
+
+        let left_type = self.infer_expr_type(&bin.left);
+        let right_type = self.infer_expr_type(&bin.right);
+        if left_type == Type::Unknown || right_type == Type::Unknown {
+            return;
+        }
+
+        let operator = match bin.operator {
+            BinOpKind::PlusEquals => BinOpKind::Plus,
+            BinOpKind::MinusEquals => BinOpKind::Minus,
+            operator => operator,
+        };
+        if operator != bin.operator && !compatible(&right_type, &left_type) {
+            // `x += e` takes the type of `e` like `x = x + e` would
+            self.report(
+                format!(
+                    "Type mismatch in assignment to '{}': expected {}, found {}",
+                    bin.left.span().literal,
+                    left_type,
+                    right_type
+                ),
+                bin.span(),
+            );
+            return;
+        }
+
+        let is_arithmetic = matches!(
+            operator,
             BinOpKind::Plus
-            | BinOpKind::Minus
-            | BinOpKind::Multiply
-            | BinOpKind::Divide
-            | BinOpKind::Power
-            | BinOpKind::Modulo => {
-                if left_type != right_type {
-                    return Err(SemanticError(
-                        format!(
-                            "Type mismatch in binary operation: {:?} and {:?}",
-                            left_type, right_type
-                        ),
-                        bin.span(),
-                    )
-                    .into());
+                | BinOpKind::Minus
+                | BinOpKind::Multiply
+                | BinOpKind::Divide
+                | BinOpKind::Power
+                | BinOpKind::Modulo
+                | BinOpKind::BitwiseAnd
+                | BinOpKind::BitwiseOr
+                | BinOpKind::BitwiseXor
+        );
+        if !is_arithmetic && !is_comparison(operator) {
+            return;
+        }
+
+        if left_type != right_type {
+            let message = if is_arithmetic {
+                format!(
+                    "Type mismatch in binary operation: {} and {}",
+                    left_type, right_type
+                )
+            } else {
+                "Comparison operators require operands of the same type".to_string()
+            };
+            self.report(message, bin.span());
+        } else if let Type::Param(name) = &left_type {
+            // Checked against the type arguments at every call instead
+            self.require_operator(name, operator);
+        } else if let Some(message) = unsupported_operator(operator, &left_type) {
+            self.report(message, bin.span());
+        }
+    }
+
+    fn check_unary_operator(&mut self, un: &Unary) {
+        let expr_type = self.infer_expr_type(&un.expr);
+        match un.operator.kind {
+            UnOpKind::Minus => {
+                if !matches!(expr_type, Type::Int | Type::Float | Type::Unknown) {
+                    self.report(
+                        format!("Unary operator '-' not supported for type {}", expr_type),
+                        un.operator.token.span.clone(),
+                    );
                 }
-                if left_type != Type::Int && left_type != Type::Float {
-                    return Err(SemanticError(
-                        format!(
-                            "Binary operator '{:?}' not supported for type {:?}",
-                            bin.operator, left_type
-                        ),
-                        bin.span(),
-                    )
-                    .into());
+            }
+            UnOpKind::BitwiseNot => {
+                if !matches!(expr_type, Type::Int | Type::Unknown) {
+                    self.report(
+                        format!("Unary operator '~' not supported for type {}", expr_type),
+                        un.operator.token.span.clone(),
+                    );
+                }
+            }
+            UnOpKind::Not => {
+                if !matches!(expr_type, Type::Bool | Type::Unknown) {
+                    self.report(
+                        format!("Unary operator '!' not supported for type {}", expr_type),
+                        un.operator.token.span.clone(),
+                    );
                 }
-
-                Ok(())
             }
-            BinOpKind::GreaterThan
+        }
+    }
+}
+
+/// The variable an assignment target writes into, `p` for `p`, `p.x` and `p.xs[0]`.
+fn assigned_variable(target: &Expr) -> Option<&Variable> {
+    match target {
+        Expr::Variable(var) => Some(var),
+        Expr::Member(member) => assigned_variable(&member.object),
+        Expr::Index(index) => assigned_variable(&index.object),
+        _ => None,
+    }
+}
+
+fn is_comparison(operator: BinOpKind) -> bool {
+    matches!(
+        operator,
+        BinOpKind::GreaterThan
             | BinOpKind::LessThan
             | BinOpKind::Equals
             | BinOpKind::NotEquals
             | BinOpKind::LessThanOrEqual
-            | BinOpKind::GreaterThanOrEqual => {
-                if left_type != right_type {
-                    return Err(SemanticError(
-                        "Comparison operators require operands of the same type".to_string(),
-                        bin.span(),
-                    )
-                    .into());
-                }
-                if left_type != Type::Int
-                    && left_type != Type::Float
-                    && left_type != Type::Bool
-                    && left_type != Type::String
-                {
-                    return Err(SemanticError(
-                        format!(
-                            "Comparison operator '{:?}' not supported for type {:?}",
-                            bin.operator, left_type
-                        ),
-                        bin.span(),
-                    )
-                    .into());
-                }
+            | BinOpKind::GreaterThanOrEqual
+    )
+}
 
-                Ok(())
+/// Why `operator` can't be applied to two operands of type `ty`, if it can't.
+fn unsupported_operator(operator: BinOpKind, ty: &Type) -> Option<String> {
+    match operator {
+        BinOpKind::Plus
+        | BinOpKind::Minus
+        | BinOpKind::Multiply
+        | BinOpKind::Divide
+        | BinOpKind::Power
+        | BinOpKind::Modulo
+            if !matches!(ty, Type::Int | Type::Float) =>
+        {
+            Some(format!(
+                "Binary operator '{:?}' not supported for type {}",
+                operator, ty
+            ))
+        }
+        BinOpKind::BitwiseAnd | BinOpKind::BitwiseOr | BinOpKind::BitwiseXor
+            if *ty != Type::Int =>
+        {
+            Some(format!(
+                "Binary operator '{:?}' not supported for type {}",
+                operator, ty
+            ))
+        }
+        operator
+            if is_comparison(operator)
+                && !matches!(ty, Type::Int | Type::Float | Type::Bool | Type::String) =>
+        {
+            Some(format!(
+                "Comparison operator '{:?}' not supported for type {}",
+                operator, ty
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Whether `ty` refers to the type parameter `name` anywhere.
+fn mentions(ty: &Type, name: &str) -> bool {
+    match ty {
+        Type::Param(param) => param == name,
+        Type::Array(element, _) => mentions(element, name),
+        Type::Struct(_, args) => args.iter().any(|arg| mentions(arg, name)),
+        Type::Function(params, return_type) => {
+            params.iter().any(|param| mentions(param, name)) || mentions(return_type, name)
+        }
+        _ => false,
+    }
+}
+
+/// Matches a value of type `found` against the declared type `expected`, binding the
+/// type parameters `expected` mentions. False when they don't fit together.
+fn unify(expected: &Type, found: &Type, bindings: &mut HashMap<String, Type>) -> bool {
+    match (expected, found) {
+        (Type::Param(name), found) => match bindings.get(name) {
+            Some(bound) if *bound != Type::Unknown => compatible(found, bound),
+            _ => {
+                bindings.insert(name.clone(), found.clone());
+                true
             }
-            _ => Ok(()),
+        },
+        (Type::Array(expected, expected_size), Type::Array(found, found_size)) => {
+            unify(expected, found, bindings)
+                && (expected_size.is_none() || found_size == expected_size)
+        }
+        (Type::Struct(expected, expected_args), Type::Struct(found, found_args)) => {
+            expected == found
+                && expected_args.len() == found_args.len()
+                && expected_args
+                    .iter()
+                    .zip(found_args)
+                    .all(|(expected, found)| unify(expected, found, bindings))
         }
+        (Type::Function(expected_params, expected), Type::Function(found_params, found)) => {
+            expected_params.len() == found_params.len()
+                && expected_params
+                    .iter()
+                    .zip(found_params)
+                    .all(|(expected, found)| unify(expected, found, bindings))
+                && unify(expected, found, bindings)
+        }
+        _ => compatible(found, expected),
     }
+}
 
-    fn check_unary_operator(&mut self, un: &Unary) -> Result<()> {
-        let expr_type = self.infer_expr_type(&un.expr)?;
-        match un.operator.kind {
-            UnOpKind::Minus => {
-                if expr_type != Type::Int && expr_type != Type::Float {
-                    return Err(SemanticError(
-                        format!("Unary operator '-' not supported for type {:?}", expr_type),
-                        un.operator.token.span.clone(),
-                    )
-                    .into());
+/// `ty` with its type parameters replaced by the types bound to them. Parameters
+/// without a binding become `Unknown`.
+fn substitute(ty: &Type, bindings: &HashMap<String, Type>) -> Type {
+    match ty {
+        Type::Param(name) => bindings.get(name).cloned().unwrap_or(Type::Unknown),
+        Type::Array(element, size) => Type::Array(Box::new(substitute(element, bindings)), *size),
+        Type::Struct(name, args) => Type::Struct(
+            name.clone(),
+            args.iter().map(|arg| substitute(arg, bindings)).collect(),
+        ),
+        Type::Function(params, return_type) => Type::Function(
+            params
+                .iter()
+                .map(|param| substitute(param, bindings))
+                .collect(),
+            Box::new(substitute(return_type, bindings)),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Whether a value of this type can be called like a function.
+fn is_callable(ty: &Type) -> bool {
+    matches!(ty, Type::Function(..) | Type::Unknown)
+}
+
+/// What calling a value of type `callee` returns.
+fn return_type(callee: Type) -> Type {
+    match callee {
+        Type::Function(_, return_type) => *return_type,
+        _ => Type::Unknown,
+    }
+}
+
+fn is_builtin_call(call: &CallExpr) -> bool {
+    call.function_name()
+        .is_some_and(|name| BUILTINS.contains(&name))
+}
+
+fn is_empty_array(expr: &Expr) -> bool {
+    matches!(expr, Expr::ArrayLiteral(literal) if literal.elements.is_empty())
+}
+
+/// The value of an integer literal, optionally negated.
+fn constant_int(expr: &Expr) -> Option<i64> {
+    match expr {
+        Expr::Literal(Literal {
+            value: LiteralType::Int(value),
+            ..
+        }) => Some(*value),
+        Expr::Unary(un) if un.operator.kind == UnOpKind::Minus => {
+            constant_int(&un.expr).map(i64::wrapping_neg)
+        }
+        Expr::Parenthesized(paren) => constant_int(&paren.expr),
+        _ => None,
+    }
+}
+
+/// Whether a block always leaves through `return`, `break` or `continue`.
+fn diverges(block: &Block) -> bool {
+    matches!(
+        block.stmts.last(),
+        Some(Stmt::Return(_) | Stmt::Break(_) | Stmt::Continue(_))
+    )
+}
+
+/// The functions of the standard library module `module`, empty if there's no such module.
+fn std_module_def(module: &str) -> ModuleDef {
+    StdFn::ALL
+        .into_iter()
+        .filter(|function| function.module() == module)
+        .map(|function| {
+            let symbol = Symbol {
+                name: function.name().to_string(),
+                symbol_type: function.function_type(),
+                is_mutable: false,
+                type_params: vec![],
+            };
+            let function = ModuleFn {
+                symbol,
+                exported: true,
+            };
+            (function.symbol.name.clone(), function)
+        })
+        .collect()
+}
+
+/// Whether a value of type `found` may be used where `expected` is required. A fixed
+/// size array can be used where any length is accepted, but not the other way around.
+fn compatible(found: &Type, expected: &Type) -> bool {
+    match (found, expected) {
+        (Type::Array(found, found_size), Type::Array(expected, expected_size)) => {
+            compatible(found, expected) && (expected_size.is_none() || found_size == expected_size)
+        }
+        (Type::Struct(found, found_args), Type::Struct(expected, expected_args)) => {
+            found == expected
+                && found_args.len() == expected_args.len()
+                && found_args
+                    .iter()
+                    .zip(expected_args)
+                    .all(|(found, expected)| compatible(found, expected))
+        }
+        (Type::Function(found_params, found), Type::Function(expected_params, expected)) => {
+            found_params.len() == expected_params.len()
+                && found_params
+                    .iter()
+                    .zip(expected_params)
+                    .all(|(found, expected)| compatible(found, expected))
+                && compatible(found, expected)
+        }
+        _ => found == expected || *found == Type::Unknown || *expected == Type::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::parser::Parser, lexer::Lexer};
+
+    /// The messages of the errors resolving `source` reports.
+    fn errors(source: &str) -> Vec<String> {
+        let tokens = Lexer::from_source(source.to_string()).lex().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+
+        match Resolver::new().resolve_ast(&ast) {
+            Ok(()) => vec![],
+            Err(err) => match err.downcast::<PulseError>() {
+                Ok(Errors(errors)) => errors.iter().map(ToString::to_string).collect(),
+                other => panic!("Unexpected error {:?}", other),
+            },
+        }
+    }
+
+    #[test]
+    fn fields_exist_only_on_structs() {
+        let source = "
+            struct Point { x: int, y: int }
+            fn main() -> int {
+                let p = Point { x: 1, y: 2 };
+                let n = 3;
+                return p.z + n.x;
+            }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Struct 'Point' has no field 'z'",
+                "Type Int has no field 'x'"
+            ]
+        );
+    }
+
+    #[test]
+    fn literals_initialize_each_field_once() {
+        let source = "
+            struct Point { x: int, y: int }
+            fn main() -> int {
+                let a = Point { x: 1, x: 2, y: 3 };
+                let b = Point { x: 1 };
+                let c = Point { x: 1, y: 2, z: 3 };
+                let d = Line { x: 1 };
+                return 0;
+            }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Field 'x' is initialized more than once",
+                "Missing field 'y' in initializer of 'Point'",
+                "Struct 'Point' has no field 'z'",
+                "Undefined struct 'Line'",
+            ]
+        );
+    }
+
+    #[test]
+    fn struct_declarations_are_checked() {
+        let source = "
+            struct Point { x: int, x: int }
+            struct Point { y: int }
+            struct Holder { p: Shape }
+            fn main() -> int { return 0; }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Duplicate field 'x' in struct 'Point'",
+                "Duplicate declaration of type 'Point'",
+                "Unknown type 'Shape'",
+            ]
+        );
+    }
+
+    #[test]
+    fn matches_must_cover_every_value() {
+        let source = "
+            enum Shape { Circle(float), Square(float), Empty }
+            fn area(s: Shape) -> int {
+                return match s { Circle(r) => 1, Empty => 0 };
+            }
+            fn flag(b: bool) -> int {
+                return match b { true => 1 };
+            }
+            fn count(n: int) -> int {
+                return match n { 0 => 0, 1 => 1 };
+            }
+            fn main() -> int { return 0; }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Non-exhaustive match: 'Square' is not covered",
+                "Non-exhaustive match: 'false' is not covered",
+                "Non-exhaustive match: values of type Int need a '_' arm",
+            ]
+        );
+    }
+
+    #[test]
+    fn exhaustive_matches_are_accepted() {
+        let source = "
+            enum Shape { Circle(float), Square(float), Empty }
+            fn area(s: Shape) -> int {
+                return match s { Circle(r) => 1, Square(side) => 2, Empty => 0 };
+            }
+            fn flag(b: bool) -> int {
+                return match b { true => 1, false => 0 };
+            }
+            fn count(n: int) -> int {
+                return match n { 0 => 0, _ => 1 };
+            }
+            fn main() -> int { return 0; }
+        ";
+
+        assert_eq!(errors(source), Vec::<String>::new());
+    }
+
+    #[test]
+    fn arms_after_full_coverage_are_unreachable() {
+        let source = "
+            enum Shape { Circle(float), Empty }
+            fn area(s: Shape) -> int {
+                return match s { Circle(r, extra) => 1, _ => 0, Empty => 2 };
+            }
+            fn main() -> int { return 0; }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Variant 'Circle' holds 1 value(s), but the pattern binds 2",
+                "Unreachable match arm, earlier arms already cover it",
+            ]
+        );
+    }
+
+    #[test]
+    fn array_elements_and_indexes_are_checked() {
+        let source = "
+            fn main() -> int {
+                let a = [1, 2.5, 3];
+                let b: [int; 4] = [0; 4];
+                let c: [int; 3] = b;
+                let d = b[true] + b[4] + 5[0];
+                let e = [];
+                b[0] = \"zero\";
+                return len(b) + len(7);
+            }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Array elements must have the same type: expected Int, found Float",
+                "Type mismatch in declaration of 'c': expected [Int; 3], found [Int; 4]",
+                "Array index must be of type Int, found Bool",
+                "Index 4 out of bounds for array of length 4",
+                "Cannot index into a value of type Int",
+                "Cannot infer the element type of an empty array, annotate the variable like 'let a: [int] = []'",
+                "Type mismatch in assignment to 'b[0]': expected Int, found String",
+                "Function 'len' expects an array or a string, found Int",
+            ]
+        );
+    }
+
+    #[test]
+    fn impl_blocks_are_checked() {
+        let source = "
+            struct Point { x: int, y: int }
+            impl Point {
+                fn new(x: int, y: int) -> Point { return Point { x: x, y: y }; }
+                fn norm(self) -> int { return self.x + self.y; }
+                fn norm(self) -> int { return 0; }
+            }
+            impl Missing {
+                fn get(self) -> int { return 0; }
+            }
+            fn free(self) -> int { return 0; }
+            fn main() -> int {
+                let p = Point::new(1, 2);
+                let q = p.new(1, 2);
+                return p.size() + Point::zero() + p.norm;
+            }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Duplicate function 'norm' on 'Point'",
+                "Undefined type 'Missing'",
+                "Only functions in an 'impl' block can take 'self'",
+                "'new' doesn't take 'self', call it as 'Point::new(...)'",
+                "No method 'size' on type Point",
+                "No function 'zero' on type Point",
+                "Struct 'Point' has no field 'norm'",
+            ]
+        );
+    }
+
+    #[test]
+    fn closures_capture_by_value() {
+        let source = "
+            fn main() -> int {
+                let total = 0;
+                let add = fn(x: int) -> int {
+                    total = total + x;
+                    let local = x;
+                    local = local + 1;
+                    return local + total;
+                };
+                return add(1);
+            }
+        ";
+
+        assert_eq!(
+            errors(source),
+            ["Cannot assign to 'total' inside a closure, closures capture variables by value"]
+        );
+    }
+
+    #[test]
+    fn only_function_values_can_be_called() {
+        let source = "
+            fn twice(f: fn(int) -> int, x: int) -> int { return f(f(x)); }
+            fn main() -> int {
+                let n = 3;
+                let inc = fn(x: int) -> int { return x + 1; };
+                let pick = fn(first: bool) -> fn(int) -> int { return inc; };
+                return twice(inc, 1) + pick(true)(2) + n(1) + (n + 1)(2);
+            }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "'n' is not a function, it has type Int",
+                "Cannot call a value of type Int",
+            ]
+        );
+    }
+
+    #[test]
+    fn generics_are_checked() {
+        let source = "
+            struct Pair<T> { first: T, second: T }
+            struct Wrapper<T, T> { value: T }
+            struct Unused<T> { value: int }
+            fn make<T>() -> int { return 0; }
+            fn same<T>(a: T, b: T) -> T { return a; }
+            fn larger<T>(a: T, b: T) -> bool { return a > b; }
+            fn main() -> int {
+                let p: Pair<int, int> = Pair { first: 1, second: 2 };
+                let q: int<bool> = 1;
+                let s = same(1, true);
+                let b = larger(Pair { first: 1, second: 2 }, Pair { first: 3, second: 4 });
+                let f = same;
+                return 0;
+            }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Duplicate type parameter 'T'",
+                "Type parameter 'T' is not used by the fields of 'Unused'",
+                "Type parameter 'T' is not used by the parameters of 'make', so it can't be inferred",
+                "Struct 'Pair' expects 1 type argument(s), found 2",
+                "Type 'int' doesn't take type arguments",
+                "Type mismatch in argument 2 of 'same': expected Int, found Bool",
+                "Comparison operator 'GreaterThan' not supported for type Pair<Int>, but 'larger' applies it to its type parameter 'T'",
+                "Generic function 'same' has to be called, its type arguments are inferred from the arguments",
+            ]
+        );
+    }
+
+    #[test]
+    fn impls_provide_every_interface_method() {
+        let source = "
+            interface Shape {
+                fn area(self) -> int;
+                fn scaled(self, factor: int) -> int;
+            }
+            struct Rect { w: int, h: int }
+            struct Dot { x: int }
+            impl Shape for Rect {
+                fn area(self) -> int { return self.w * self.h; }
+            }
+            impl Shape for Dot {
+                fn area(self) -> float { return 0.0; }
+                fn scaled(self, factor: int) -> int { return 0; }
+                fn name(self) -> string { return \"dot\"; }
+            }
+            fn main() -> int { return 0; }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Missing method 'scaled' of interface 'Shape' in impl for 'Rect'",
+                "Method 'area' doesn't match interface 'Shape': expected fn(self) -> Int, found fn(self) -> Float",
+                "Method 'name' is not a member of interface 'Shape'",
+            ]
+        );
+    }
+
+    #[test]
+    fn type_arguments_satisfy_their_bounds() {
+        let source = "
+            interface Shape { fn area(self) -> int; }
+            struct Rect { w: int, h: int }
+            struct Dot { x: int }
+            impl Shape for Rect {
+                fn area(self) -> int { return self.w * self.h; }
+            }
+            struct Labeled<T: Shape> { shape: T }
+            fn twice<T: Shape>(s: T) -> int { return s.area() * 2; }
+            fn named<T: Named>(s: T) -> int { return 0; }
+            fn main() -> int {
+                let fine = twice(Rect { w: 1, h: 2 });
+                let dot = twice(Dot { x: 1 });
+                let l = Labeled { shape: Dot { x: 2 } };
+                let shape: Shape = Rect { w: 1, h: 2 };
+                return 0;
+            }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Undefined interface 'Named'",
+                "Type Dot doesn't implement interface 'Shape', which 'twice' requires for 'T'",
+                "Type Dot doesn't implement interface 'Shape', which 'Labeled' requires for 'T'",
+                "Interface 'Shape' can only be used as a bound, like '<T: Shape>'",
+            ]
+        );
+    }
+
+    #[test]
+    fn std_functions_are_typed() {
+        let source = "
+            use { println, read_line, write } from \"std::io\";
+            use { open } from \"std::fs\";
+            fn main() -> int {
+                println(42);
+                let n: int = read_line();
+                println(read_line());
+                return 0;
+            }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Module 'std::io' has no function 'write'",
+                "The standard library has no module 'std::fs'",
+                "Type mismatch in argument 1 of 'println': expected String, found Int",
+                "Type mismatch in declaration of 'n': expected Int, found String",
+            ]
+        );
+    }
+
+    #[test]
+    fn calls_pass_one_argument_per_parameter() {
+        let source = "
+            struct Point { x: int, y: int }
+            impl Point {
+                fn new(x: int, y: int) -> Point { return Point { x: x, y: y }; }
+                fn scaled(self, factor: int) -> Point { return self; }
+            }
+            fn add(a: int, b: int) -> int { return a + b; }
+            fn one(a: int) -> int { return a; }
+            fn main() -> int {
+                let x = add(1);
+                let y = add(1, 2, 3);
+                let z = one();
+                let p = Point::new(1);
+                let q = p.scaled();
+                let f = fn(a: int) -> int { return a; };
+                return f(1, 2);
+            }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Function 'add' expects 2 arguments, found 1",
+                "Function 'add' expects 2 arguments, found 3",
+                "Function 'one' expects 1 argument, found 0",
+                "Function 'Point::new' expects 2 arguments, found 1",
+                "Function 'scaled' expects 1 argument, found 0",
+                "Function 'f' expects 1 argument, found 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn arguments_match_parameter_types() {
+        let source = "
+            struct Point { x: int, y: int }
+            fn norm(p: Point, scale: float) -> float { return scale; }
+            fn first<T>(a: T, b: T) -> T { return a; }
+            fn main() -> int {
+                let n = norm(1, 2.0);
+                let m = norm(Point { x: 1, y: 2 }, \"big\");
+                let f = first(1, true);
+                return 0;
+            }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Type mismatch in argument 1 of 'norm': expected Point, found Int",
+                "Type mismatch in argument 2 of 'norm': expected Float, found String",
+                "Type mismatch in argument 2 of 'first': expected Int, found Bool",
+            ]
+        );
+    }
+
+    #[test]
+    fn functions_and_methods_are_declared_before_bodies_are_checked() {
+        let source = "
+            fn main() -> int {
+                let p = Point::new(later(1), 2);
+                let bad: string = p.sum();
+                return later(1, 2) + p.sum();
+            }
+            fn later(a: int) -> int { return a; }
+            struct Point { x: int, y: int }
+            impl Point {
+                fn new(x: int, y: int) -> Point { return Point { x: x, y: y }; }
+                fn sum(self) -> int { return self.x + self.y; }
+            }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Type mismatch in declaration of 'bad': expected String, found Int",
+                "Function 'later' expects 1 argument, found 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn types_refer_to_types_declared_later() {
+        let source = "
+            struct Holder { shape: Shape, boxed: Box<Point> }
+            enum Shape { Dot(Point), Empty }
+            struct Box<T> { value: T }
+            struct Point { x: int, y: int }
+            fn main() -> int { return 0; }
+        ";
+
+        assert_eq!(errors(source), Vec::<String>::new());
+    }
+
+    #[test]
+    fn types_cannot_contain_themselves() {
+        let source = "
+            struct A { b: B }
+            struct B { a: [A] }
+            enum List { Empty, Cons(int, List) }
+            struct Callback { call: fn(Callback) -> int }
+            fn main() -> int { return 0; }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Struct 'A' cannot contain itself",
+                "Struct 'B' cannot contain itself",
+                "Enum 'List' cannot contain itself",
+            ]
+        );
+    }
+
+    #[test]
+    fn operators_of_generic_functions_reach_earlier_callers() {
+        let source = "
+            struct S { x: int }
+            fn outer<T>(a: T, b: T) -> bool { return middle(a, b); }
+            fn middle<T>(a: T, b: T) -> bool { return inner(a, b); }
+            fn inner<T>(a: T, b: T) -> bool { return a < b; }
+            fn main() -> int {
+                let fine = outer(1, 2);
+                let wrong = outer(S { x: 1 }, S { x: 2 });
+                return 0;
+            }
+        ";
+
+        assert_eq!(
+            errors(source),
+            ["Comparison operator 'LessThan' not supported for type S, but 'outer' applies it to its type parameter 'T'"]
+        );
+    }
+
+    #[test]
+    fn operators_of_mutually_recursive_generic_functions_are_shared() {
+        let source = "
+            struct S { x: int }
+            fn ping<T>(a: T, b: T, n: int) -> bool {
+                if n == 0 { return a == b; }
+                return pong(a, b, n - 1);
+            }
+            fn pong<T>(a: T, b: T, n: int) -> bool {
+                if n == 0 { return a < b; }
+                return ping(a, b, n - 1);
+            }
+            fn main() -> int {
+                let wrong = ping(S { x: 1 }, S { x: 2 }, 3);
+                return 0;
+            }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Comparison operator 'Equals' not supported for type S, but 'ping' applies it to its type parameter 'T'",
+                "Comparison operator 'LessThan' not supported for type S, but 'ping' applies it to its type parameter 'T'",
+            ]
+        );
+    }
+
+    #[test]
+    fn compound_assignments_and_bitwise_operators_are_checked() {
+        let source = "
+            fn main() -> int {
+                let s = \"a\";
+                s += 1;
+                s -= \"b\";
+                let n = 1;
+                n += 2.5;
+                n -= 1;
+                let xs = [1, 2];
+                xs[0] += true;
+                let flags = true & false;
+                let bits = 6 & 3 | 1 ^ 2.0;
+                return n;
+            }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Type mismatch in assignment to 's': expected String, found Int",
+                "Binary operator 'Minus' not supported for type String",
+                "Type mismatch in assignment to 'n': expected Int, found Float",
+                "Type mismatch in assignment to 'xs[0]': expected Int, found Bool",
+                "Binary operator 'BitwiseAnd' not supported for type Bool",
+                "Type mismatch in binary operation: Int and Float",
+            ]
+        );
+    }
+
+    #[test]
+    fn compound_assignments_need_a_mutable_target() {
+        let source = "
+            fn main() -> int {
+                let total = 0;
+                for i in 0..3 {
+                    i = 5;
+                    i += 1;
+                    i--;
+                    total++;
                 }
+                1 += 2;
+                return total;
             }
-            UnOpKind::BitwiseNot => {
-                if expr_type != Type::Int {
-                    return Err(SemanticError(
-                        format!("Unary operator '~' not supported for type {:?}", expr_type),
-                        un.operator.token.span.clone(),
-                    )
-                    .into());
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Cannot assign to immutable variable 'i'",
+                "Cannot assign to immutable variable 'i'",
+                "Cannot assign to immutable variable 'i'",
+                "Only variables and their fields or elements can be assigned to",
+            ]
+        );
+    }
+
+    #[test]
+    fn closures_cannot_update_captured_variables() {
+        let source = "
+            fn main() -> int {
+                let total = 0;
+                for i in 0..3 {
+                    let f = fn(x: int) -> int {
+                        total += x;
+                        total--;
+                        let sum = x;
+                        sum += 1;
+                        return sum;
+                    };
+                    f(i);
                 }
+                return total;
             }
-        }
+        ";
 
-        Ok(())
+        assert_eq!(
+            errors(source),
+            [
+                "Cannot assign to 'total' inside a closure, closures capture variables by value",
+                "Cannot assign to 'total' inside a closure, closures capture variables by value",
+            ]
+        );
+    }
+
+    #[test]
+    fn logical_not_takes_a_bool() {
+        let source = "
+            fn main() -> int {
+                let done = !(1 > 2);
+                let n: bool = !done;
+                let wrong = !1;
+                let also = -true;
+                return 0;
+            }
+        ";
+
+        assert_eq!(
+            errors(source),
+            [
+                "Unary operator '!' not supported for type Int",
+                "Unary operator '-' not supported for type Bool",
+            ]
+        );
     }
 }
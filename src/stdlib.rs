@@ -0,0 +1,88 @@
+use crate::resolver::Type;
+
+/// A function of the standard library, imported with `use { println } from "std::io"`.
+/// Every backend provides its own implementation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StdFn {
+    Println,
+    Eprintln,
+    Print,
+    ReadLine,
+}
+
+impl StdFn {
+    pub const ALL: [StdFn; 4] = [
+        StdFn::Println,
+        StdFn::Eprintln,
+        StdFn::Print,
+        StdFn::ReadLine,
+    ];
+
+    /// The function `name` of the standard library module `module`, if there is one.
+    pub fn find(module: &str, name: &str) -> Option<StdFn> {
+        StdFn::ALL
+            .into_iter()
+            .find(|function| function.module() == module && function.name() == name)
+    }
+
+    pub fn module(self) -> &'static str {
+        match self {
+            StdFn::Println | StdFn::Eprintln | StdFn::Print | StdFn::ReadLine => "std::io",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StdFn::Println => "println",
+            StdFn::Eprintln => "eprintln",
+            StdFn::Print => "print",
+            StdFn::ReadLine => "read_line",
+        }
+    }
+
+    pub fn params(self) -> Vec<Type> {
+        match self {
+            StdFn::Println | StdFn::Eprintln | StdFn::Print => vec![Type::String],
+            StdFn::ReadLine => vec![],
+        }
+    }
+
+    pub fn return_type(self) -> Type {
+        match self {
+            StdFn::Println | StdFn::Eprintln | StdFn::Print => Type::Void,
+            StdFn::ReadLine => Type::String,
+        }
+    }
+
+    pub fn function_type(self) -> Type {
+        Type::Function(self.params(), Box::new(self.return_type()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_functions_by_module_and_name() {
+        assert_eq!(StdFn::find("std::io", "read_line"), Some(StdFn::ReadLine));
+        assert_eq!(StdFn::find("std::io", "open"), None);
+        assert_eq!(StdFn::find("std::fs", "println"), None);
+
+        for function in StdFn::ALL {
+            assert_eq!(
+                StdFn::find(function.module(), function.name()),
+                Some(function)
+            );
+        }
+    }
+
+    #[test]
+    fn signatures_are_function_types() {
+        assert_eq!(
+            StdFn::ReadLine.function_type().to_string(),
+            "fn() -> String"
+        );
+        assert_eq!(StdFn::Println.function_type().to_string(), "fn(String)");
+    }
+}
@@ -0,0 +1,463 @@
+use pulse_lang::{
+    compile_source, error::PulseError, interp, lexer::span::TextSpan, project::EntryPoint,
+    CompiledUnit,
+};
+use std::{
+    env,
+    io::Write,
+    process::{self, Command, Output, Stdio},
+};
+
+/// Set in a child process started by `run_in_child` to the backend it runs.
+const CHILD: &str = "PULSE_TEST_CHILD";
+
+fn compile(source: &str) -> CompiledUnit {
+    compile_source(source)
+        .unwrap_or_else(|diagnostics| panic!("Expected the program to compile: {:?}", diagnostics))
+}
+
+/// Runs `main() -> int` in `source` on the interpreter, and on the JIT when LLVM is
+/// compiled in, and checks both exit with `expected`.
+fn assert_exit_code(source: &str, expected: i64) {
+    let unit = compile(source);
+    let entry_point = EntryPoint::from_ast(&unit.ast).unwrap();
+
+    let interpreted = interp::run(unit.ast.clone(), entry_point, 0).unwrap();
+    assert_eq!(interpreted, expected, "exit code of the interpreter");
+
+    #[cfg(feature = "llvm")]
+    assert_eq!(jit::run(&unit.ast), expected, "exit code of the LLVM JIT");
+}
+
+/// The message and span of the runtime error stopping `source` in the interpreter.
+fn interp_runtime_error(source: &str) -> (String, TextSpan) {
+    let unit = compile(source);
+    let entry_point = EntryPoint::from_ast(&unit.ast).unwrap();
+
+    let err = interp::run(unit.ast, entry_point, 0).unwrap_err();
+    match err.downcast::<PulseError>() {
+        Ok(PulseError::RuntimeError(message, span)) => (message, span),
+        other => panic!("Expected a runtime error, found {:?}", other),
+    }
+}
+
+/// Runs `source` on `backend`, "interp" or "jit", in a child process that runs only the
+/// test `test`, as programs write to stdout and runtime errors exit the whole process.
+/// Returns the exit code and the output of the program.
+fn run_in_child(test: &str, backend: &str, source: &str, stdin: &str) -> Output {
+    if let Ok(backend) = env::var(CHILD) {
+        let unit = compile(source);
+        let exit_code = match backend.as_str() {
+            #[cfg(feature = "llvm")]
+            "jit" => jit::run(&unit.ast),
+            _ => {
+                let entry_point = EntryPoint::from_ast(&unit.ast).unwrap();
+                interp::run(unit.ast, entry_point, 0).unwrap()
+            }
+        };
+        process::exit(exit_code as i32);
+    }
+
+    let mut child = Command::new(env::current_exe().unwrap())
+        .args(["--exact", test, "--nocapture", "--quiet"])
+        .env(CHILD, backend)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+
+    // The test harness announces the test before the program writes anything
+    let mut output = child.wait_with_output().unwrap();
+    let header = b"\nrunning 1 test\n";
+    assert!(
+        output.stdout.starts_with(header),
+        "stdout of the test harness"
+    );
+    output.stdout.drain(..header.len());
+
+    output
+}
+
+#[cfg(feature = "llvm")]
+mod jit {
+    use inkwell::context::Context;
+    use pulse_lang::{ast::ast::Ast, llvm::ir::CodeGen};
+
+    type Main = unsafe extern "C" fn() -> i64;
+
+    pub fn run(ast: &Ast) -> i64 {
+        let context = Context::create();
+        let mut codegen = CodeGen::new(&context, "main", 0).unwrap();
+        codegen.compile(ast).unwrap();
+
+        // SAFETY: every program in these tests declares `fn main() -> int`
+        let exit_code = unsafe {
+            codegen
+                .execution_engine
+                .get_function::<Main>("main")
+                .unwrap()
+                .call()
+        };
+
+        exit_code
+    }
+}
+
+#[test]
+fn functions_and_arithmetic() {
+    let source = "
+        fn fact(n: int) -> int {
+            if n <= 1 { return 1; }
+            return n * fact(n - 1);
+        }
+        fn classify(x: float) -> int {
+            if x < 0.0 {
+                return -1;
+            } else if x == 0.0 {
+                return 0;
+            }
+            return 1;
+        }
+        fn main() -> int {
+            let a = 2 ** 10;
+            let b = (a + 3) * 2;
+            let ok = a > 1 && b != 0 || false;
+            if !ok || !(b > a) { return 0; }
+            let bonus = 0;
+            if !(a < 0) { bonus = 3; }
+            return fact(5) + classify(-2.5) + b % 7 + -a / 512 + bonus;
+        }
+    ";
+
+    assert_exit_code(source, 120 - 1 + 2054 % 7 - 2 + 3);
+}
+
+#[test]
+fn loops() {
+    let source = "
+        fn main() -> int {
+            let total = 0;
+            let i = 0;
+            while i < 10 {
+                i++;
+                if i % 2 == 0 { continue; }
+                total += i;
+            }
+            let n = 0;
+            outer: loop {
+                let j = 0;
+                while true {
+                    j += 1;
+                    n++;
+                    if j == 3 { continue outer; }
+                    if n > 20 { break outer; }
+                }
+            }
+            return total + n;
+        }
+    ";
+
+    assert_exit_code(source, 47);
+}
+
+#[test]
+fn ranges() {
+    let source = "
+        fn main() -> int {
+            let sum = 0;
+            for i in 0..10 { sum += i; }
+            let r = 1..=4;
+            for i in r { sum += i; }
+            outer: for i in 0..5 {
+                for j in 0..5 {
+                    if j == 2 { continue outer; }
+                    if i == 3 { break outer; }
+                    sum += 100;
+                }
+            }
+            for i in 5..2 { sum += 1000; }
+            for i in 9223372036854775806..=9223372036854775807 { sum += 1; }
+            return sum;
+        }
+    ";
+
+    assert_exit_code(source, 657);
+}
+
+#[test]
+fn structs() {
+    let source = "
+        struct Point { x: int, y: int }
+        struct Line { start: Point, end: Point, name: string }
+        fn length2(l: Line) -> int {
+            let dx = l.end.x - l.start.x;
+            let dy = l.end.y - l.start.y;
+            return dx * dx + dy * dy;
+        }
+        fn origin() -> Point {
+            return Point { y: 0, x: 0 };
+        }
+        fn main() -> int {
+            let p: Point = Point { x: 3, y: 4 };
+            let l = Line { start: origin(), end: p, name: \"diag\" };
+            l.end.x = 6;
+            l.end.y += 4;
+            p.x -= 1;
+            return length2(l) + p.x;
+        }
+    ";
+
+    assert_exit_code(source, 102);
+}
+
+#[test]
+fn enums_and_match() {
+    let source = "
+        enum Shape { Square(int), Rect(int, int), Empty }
+        fn area(shape: Shape) -> int {
+            return match shape {
+                Square(side) => side * side,
+                Rect(w, h) => w * h,
+                Empty => 0,
+            };
+        }
+        fn describe(n: int) -> string {
+            return match n { 0 => \"zero\", -1 => \"minus one\", _ => \"many\" };
+        }
+        fn main() -> int {
+            let total = area(Square(3)) + area(Rect(2, 5)) + area(Empty);
+            if describe(-1) == \"minus one\" { total += 1; }
+            let more = match describe(7) { \"many\" => 10, _ => 0 };
+            while true {
+                let k = match total { 25 => { break; } _ => 1 };
+                total += k;
+            }
+            return total + more;
+        }
+    ";
+
+    assert_exit_code(source, 35);
+}
+
+#[test]
+fn arrays() {
+    let source = "
+        fn squares(n: int) -> [int] {
+            let xs = [0; 8];
+            for i in 0..n { xs[i] = i * i; }
+            return xs;
+        }
+        fn main() -> int {
+            let xs = squares(4);
+            xs[0] = 100;
+            let alias = xs;
+            alias[1] = 50;
+            let fixed: [int; 3] = [1, 2, 3];
+            return xs[0] + xs[1] + xs[3] + len(fixed) + len(\"four\");
+        }
+    ";
+
+    assert_exit_code(source, 100 + 50 + 9 + 3 + 4);
+}
+
+#[test]
+fn out_of_bounds_index_stops_both_backends() {
+    let source = "
+        fn main() -> int {
+            let xs = [1, 2, 3];
+            let i = len(xs) + 2;
+            return xs[i];
+        }
+    ";
+
+    let (message, span) = interp_runtime_error(source);
+    assert_eq!(message, "Index 5 out of bounds for array of length 3");
+    assert_eq!((span.start.line, span.start.column), (4, 22));
+
+    #[cfg(feature = "llvm")]
+    {
+        let output = run_in_child("out_of_bounds_index_stops_both_backends", "jit", source, "");
+        assert_eq!(output.status.code(), Some(101));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "error: Index 5 out of bounds for array of length 3\n---> 4:22\n"
+        );
+    }
+}
+
+#[test]
+fn methods() {
+    let source = "
+        struct Point { x: int, y: int }
+        impl Point {
+            fn new(x: int, y: int) -> Point {
+                return Point { x: x, y: y };
+            }
+            fn length2(self) -> int {
+                return self.x * self.x + self.y * self.y;
+            }
+            fn add(self, other: Point) -> Point {
+                return Point::new(self.x + other.x, self.y + other.y);
+            }
+        }
+        enum Shape { Square(int), Rect(int, int) }
+        impl Shape {
+            fn area(self) -> int {
+                return match self {
+                    Square(s) => s * s,
+                    Rect(w, h) => w * h,
+                };
+            }
+        }
+        fn main() -> int {
+            let p = Point::new(3, 4);
+            let q = p.add(Point::new(1, 1));
+            return p.length2() + Point::length2(q) + Rect(2, 5).area();
+        }
+    ";
+
+    assert_exit_code(source, 76);
+}
+
+#[test]
+fn closures_and_indirect_calls() {
+    let source = "
+        struct Counter { step: fn(int) -> int }
+        fn apply(f: fn(int) -> int, x: int) -> int { return f(x); }
+        fn double(x: int) -> int { return x * 2; }
+        fn adder(n: int) -> fn(int) -> int {
+            return fn(x: int) -> int { return x + n; };
+        }
+        fn main() -> int {
+            let k = 3;
+            let add_k = fn(x: int) -> int { return x + k; };
+            k = 100;
+            let counter = Counter { step: adder(10) };
+            let f = double;
+            return apply(add_k, 4) + apply(double, 5) + counter.step(1) + adder(2)(3) + f(6);
+        }
+    ";
+
+    assert_exit_code(source, 7 + 10 + 11 + 5 + 12);
+}
+
+#[test]
+fn closures_update_their_own_variables() {
+    let source = "
+        fn main() -> int {
+            let total = 0;
+            for i in 0..3 {
+                let f = fn(x: int) -> int {
+                    let sum = total;
+                    sum += x;
+                    sum++;
+                    return sum;
+                };
+                total = f(i);
+            }
+            return total;
+        }
+    ";
+
+    assert_exit_code(source, 6);
+}
+
+#[test]
+fn generics() {
+    let source = "
+        struct Pair<T> { first: T, second: T }
+        fn larger<T>(a: T, b: T) -> T {
+            if a > b { return a; }
+            return b;
+        }
+        fn first<T>(pair: Pair<T>) -> T { return pair.first; }
+        fn main() -> int {
+            let ints = Pair { first: 7, second: 2 };
+            let words: Pair<string> = Pair { first: \"a\", second: \"bc\" };
+            let xs = [larger(1, 9), first(ints), len(first(words))];
+            if larger(1.5, 0.5) == 1.5 { xs[2] += 10; }
+            return xs[0] + xs[1] + xs[2];
+        }
+    ";
+
+    assert_exit_code(source, 9 + 7 + 11);
+}
+
+#[test]
+fn interfaces() {
+    let source = "
+        interface Shape {
+            fn area(self) -> int;
+            fn scaled(self, factor: int) -> int;
+        }
+        interface Named { fn name(self) -> string; }
+        struct Rect { w: int, h: int }
+        enum Blob { Small, Big(int) }
+        impl Shape for Rect {
+            fn area(self) -> int { return self.w * self.h; }
+            fn scaled(self, factor: int) -> int { return self.area() * factor; }
+        }
+        impl Named for Rect {
+            fn name(self) -> string { return \"rect\"; }
+        }
+        impl Shape for Blob {
+            fn area(self) -> int {
+                return match self { Small => 1, Big(n) => n };
+            }
+            fn scaled(self, factor: int) -> int { return factor; }
+        }
+        struct Labeled<T: Shape> { shape: T, label: string }
+        fn total<T: Shape>(a: T, b: T) -> int { return a.area() + b.scaled(2); }
+        fn twice<S: Shape>(s: S) -> int { return s.area() * 2; }
+        fn describe<T: Shape + Named>(x: T) -> int { return len(x.name()) + twice(x); }
+        fn main() -> int {
+            let r = Rect { w: 3, h: 4 };
+            let l = Labeled { shape: Big(5), label: \"blob\" };
+            return total(r, Rect { w: 1, h: 2 }) + describe(r) + l.shape.area() + total(Small, Big(7));
+        }
+    ";
+
+    assert_exit_code(source, 52);
+}
+
+#[test]
+fn std_io_prints_and_reads_lines() {
+    let source = "
+        use { print, println, read_line } from \"std::io\";
+        fn main() -> int {
+            let name = read_line();
+            print(\"hello \");
+            println(name);
+            println(read_line());
+            if read_line() == \"\" { return len(name); }
+            return 0;
+        }
+    ";
+
+    let mut backends = vec!["interp"];
+    if cfg!(feature = "llvm") {
+        backends.push("jit");
+    }
+    for backend in backends {
+        let output = run_in_child(
+            "std_io_prints_and_reads_lines",
+            backend,
+            source,
+            "alice\r\nbob\r\n",
+        );
+        assert_eq!(output.status.code(), Some(5), "exit code of {}", backend);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "hello alice\nbob\n",
+            "output of {}",
+            backend
+        );
+    }
+}